@@ -0,0 +1,82 @@
+// headless mode: `shaper --convert in.svg out.svg [--simplify <tol>] [--to-json]`
+// reads an SVG, optionally re-fits it and writes it back out without opening
+// a window, so it can be used in scripts / CI.
+
+use std::process::ExitCode;
+
+use crate::io;
+use crate::units::Units;
+
+const USAGE: &str = "usage: shaper --convert <in.svg> <out> [--simplify <tolerance>] [--to-json]";
+
+/// exit codes: 0 ok, 1 read/parse/write failure, 2 bad arguments
+pub fn run(args: &[String]) -> ExitCode {
+    let mut input: Option<&str> = None;
+    let mut output: Option<&str> = None;
+    let mut simplify: Option<f64> = None;
+    let mut to_json = false;
+
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--convert" => {
+                input = it.next().map(String::as_str);
+                output = it.next().map(String::as_str);
+            }
+            "--simplify" => match it.next().and_then(|t| t.parse::<f64>().ok()) {
+                Some(tol) if tol > 0.0 => simplify = Some(tol),
+                _ => return usage_error("--simplify expects a positive number"),
+            },
+            "--to-json" => to_json = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return ExitCode::SUCCESS;
+            }
+            other => return usage_error(&format!("unknown argument `{other}`")),
+        }
+    }
+
+    let (Some(input), Some(output)) = (input, output) else {
+        return usage_error("--convert needs an input and an output path");
+    };
+
+    let src = match std::fs::read_to_string(input) {
+        Ok(src) => src,
+        Err(e) => {
+            eprintln!("error: can't read {input}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut shapes = Vec::new();
+    if let Err(e) = io::svg::import_svg(&src, &mut shapes) {
+        eprintln!("error: {input}: {e}");
+        return ExitCode::FAILURE;
+    }
+    if shapes.is_empty() {
+        eprintln!("error: no usable <path> elements in {input}");
+        return ExitCode::FAILURE;
+    }
+
+    if let Some(tol) = simplify {
+        io::svg::simplify_shapes(&mut shapes, tol);
+    }
+
+    let out = if to_json {
+        io::json::export_json(&shapes)
+    } else {
+        io::svg::export_svg(&shapes, None, None, Units::Px, 1.0)
+    };
+
+    if let Err(e) = std::fs::write(output, out) {
+        eprintln!("error: can't write {output}: {e}");
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
+
+fn usage_error(msg: &str) -> ExitCode {
+    eprintln!("error: {msg}\n{USAGE}");
+    ExitCode::from(2)
+}
@@ -0,0 +1,229 @@
+// the command palette (Ctrl+P): every action of the app by name, searchable,
+// so nothing has to be found in a menu or remembered as a shortcut.
+// actions are registered in `commands`, the palette lists whatever is there.
+
+use eframe::egui::{self, Context, Key, Modifiers, Rect};
+
+use crate::{Shaper, ToolKind};
+
+/// how big (screen px across) "Add rectangle" / "Add ellipse" make their shape
+const NEW_SHAPE_PX: f32 = 100.0;
+
+/// where a new rectangle / ellipse goes: the middle of the canvas, as
+/// (center, half its size) in world space
+fn new_shape_spot(app: &Shaper, canvas: Rect) -> (kurbo::Point, f64) {
+    let center = app.screen_to_world(canvas.center());
+    let half = app.screen_to_world_tol(NEW_SHAPE_PX / 2.0);
+    (kurbo::Point::new(center.x as f64, center.y as f64), half)
+}
+
+/// what a command does. gets the canvas rect on screen too,
+/// for the view commands
+pub type Action = Box<dyn Fn(&mut Shaper, &Context, Rect)>;
+
+/// an action the palette can run
+pub struct Command {
+    pub name: String,
+    /// the shortcut doing the same, shown next to the name
+    pub shortcut: Option<&'static str>,
+    pub run: Action,
+    /// whether it applies right now (e.g. enough shapes selected),
+    /// it's grayed out in the palette otherwise
+    pub enabled: fn(&Shaper) -> bool,
+}
+
+impl Command {
+    fn new(
+        name: impl Into<String>,
+        shortcut: Option<&'static str>,
+        run: impl Fn(&mut Shaper, &Context, Rect) + 'static,
+    ) -> Self {
+        Command {
+            name: name.into(),
+            shortcut,
+            run: Box::new(run),
+            enabled: |_| true,
+        }
+    }
+
+    /// only enabled while `enabled` says so
+    fn when(self, enabled: fn(&Shaper) -> bool) -> Self {
+        Command { enabled, ..self }
+    }
+
+    /// every word of `query` appears in the name (ignoring case)
+    fn matches(&self, query: &str) -> bool {
+        let name = self.name.to_lowercase();
+        query
+            .to_lowercase()
+            .split_whitespace()
+            .all(|word| name.contains(word))
+    }
+}
+
+/// all actions, in the order the palette lists them
+pub fn commands() -> Vec<Command> {
+    let mut commands = vec![
+        Command::new("Select all", None, |app, _, _| app.select_all()),
+        Command::new("Deselect", None, |app, _, _| app.clear_selection())
+            .when(|app| app.selected_shape_count() + app.selected_point_count() > 0),
+        Command::new("Delete selected shapes", Some("Del"), |app, _, _| {
+            app.delete_selected_shapes()
+        })
+        .when(|app| app.selected_shape_count() > 0),
+        Command::new("Combine into subpaths", None, |app, _, _| {
+            app.combine_selected_into_subpaths()
+        })
+        .when(|app| app.selected_shape_count() >= 2),
+        // in the stroke style of the last drawn shape
+        Command::new("Add rectangle", None, |app, _, canvas| {
+            let (center, half) = new_shape_spot(app, canvas);
+            let rect = kurbo::Rect::from_center_size(center, (half * 2.0, half * 2.0));
+            let (thickness, color) = (app.curr_shape.thickness, app.curr_shape.stroke_color);
+            let idx = app.add_rect(rect, thickness, color);
+            app.select_single_shape(idx);
+        }),
+        Command::new("Add ellipse", None, |app, _, canvas| {
+            let (center, half) = new_shape_spot(app, canvas);
+            let (thickness, color) = (app.curr_shape.thickness, app.curr_shape.stroke_color);
+            let idx = app.add_ellipse(center, half, half, thickness, color);
+            app.select_single_shape(idx);
+        }),
+        Command::new("Toggle handles", Some("H"), |app, _, _| {
+            app.show_handles = !app.show_handles
+        }),
+        Command::new("Zoom to fit", None, |app, _, canvas| {
+            app.zoom_to_fit(canvas)
+        }),
+        Command::new("Zoom in", Some("Ctrl +"), |app, _, canvas| {
+            app.zoom_by_step(1, canvas)
+        }),
+        Command::new("Zoom out", Some("Ctrl -"), |app, _, canvas| {
+            app.zoom_by_step(-1, canvas)
+        }),
+        Command::new("Zoom to 100%", Some("Ctrl 0"), |app, _, canvas| {
+            app.apply_zoom(1.0 / app.zoom, canvas.center())
+        }),
+        Command::new("Export SVG", None, |app, _, _| {
+            let contents = app.svg_export();
+            app.write_export(contents);
+        }),
+        Command::new("Export DXF", None, |app, _, _| {
+            let contents = app.export_dxf();
+            app.write_export(contents);
+        }),
+        Command::new("Import SVG", None, |app, ctx, _| app.import_svg_file(ctx)),
+    ];
+    for tool in ToolKind::ALL {
+        commands.push(Command::new(
+            format!("Tool: {}", tool.label()),
+            None,
+            move |app, _, _| {
+                app.selected_tool = tool;
+                app.previous_tool = None;
+            },
+        ));
+    }
+    commands
+}
+
+/// what's typed into the open palette
+#[derive(Default)]
+pub struct CommandPalette {
+    query: String,
+    /// index into the filtered list, moved with the arrow keys
+    highlighted: usize,
+}
+
+impl Shaper {
+    /// Ctrl+P opens / closes the palette. in it, typing filters the list,
+    /// up/down move the highlight, enter or a click runs the command
+    pub fn show_command_palette(&mut self, ctx: &Context, canvas: Rect) {
+        if ctx.input_mut(|i| i.consume_key(Modifiers::COMMAND, Key::P)) {
+            self.palette = match self.palette {
+                Some(_) => None,
+                None => Some(CommandPalette::default()),
+            };
+        }
+        if self.palette.is_none() {
+            return;
+        }
+        let commands = commands();
+        let enabled: Vec<bool> = commands.iter().map(|command| (command.enabled)(self)).collect();
+        let Some(palette) = &mut self.palette else {
+            return;
+        };
+
+        let mut run: Option<usize> = None;
+        let modal = egui::Modal::new(egui::Id::new("command palette")).show(ctx, |ui| {
+            ui.set_width(320.0);
+            // before the text field sees them, the arrows would move its cursor
+            let (up, down, enter) = ui.input_mut(|i| {
+                (
+                    i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                    i.consume_key(Modifiers::NONE, Key::ArrowDown),
+                    i.consume_key(Modifiers::NONE, Key::Enter),
+                )
+            });
+            let edit = ui.add(
+                egui::TextEdit::singleline(&mut palette.query)
+                    .hint_text("type a command")
+                    .desired_width(f32::INFINITY),
+            );
+            edit.request_focus();
+            if edit.changed() {
+                palette.highlighted = 0;
+            }
+
+            let shown: Vec<usize> = (0..commands.len())
+                .filter(|&i| commands[i].matches(&palette.query))
+                .collect();
+            if down && palette.highlighted + 1 < shown.len() {
+                palette.highlighted += 1;
+            }
+            if up {
+                palette.highlighted = palette.highlighted.saturating_sub(1);
+            }
+            palette.highlighted = palette.highlighted.min(shown.len().saturating_sub(1));
+
+            ui.separator();
+            if shown.is_empty() {
+                ui.weak("no matching command");
+            }
+            egui::ScrollArea::vertical()
+                .max_height(300.0)
+                .show(ui, |ui| {
+                    for (row, &i) in shown.iter().enumerate() {
+                        let command = &commands[i];
+                        let label = match command.shortcut {
+                            Some(shortcut) => format!("{}    {shortcut}", command.name),
+                            None => command.name.clone(),
+                        };
+                        let highlighted = row == palette.highlighted;
+                        let response = ui.add_enabled(
+                            enabled[i],
+                            egui::SelectableLabel::new(highlighted, label),
+                        );
+                        if highlighted && (up || down) {
+                            response.scroll_to_me(None);
+                        }
+                        if response.clicked() {
+                            run = Some(i);
+                        }
+                    }
+                });
+            if enter {
+                let highlighted = shown.get(palette.highlighted).copied();
+                run = run.or(highlighted.filter(|&i| enabled[i]));
+            }
+        });
+
+        // escape or a click outside closes it without running anything
+        if run.is_some() || modal.should_close() {
+            self.palette = None;
+        }
+        if let Some(i) = run {
+            (commands[i].run)(self, ctx, canvas);
+        }
+    }
+}
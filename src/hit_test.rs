@@ -0,0 +1,352 @@
+use kurbo::{ParamCurve, Point as KPoint, Rect as KRect};
+
+use crate::{Shape, Shaper, model};
+
+/// extra pick slack (in screen pixels) added on top of the visible
+/// size of whatever is being hit, so small things stay clickable.
+const HIT_SLOP_PX: f32 = 3.0;
+
+/// what lies under the pointer.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HitTestResult {
+    /// one of the four points of a segment (p0..=p3).
+    /// only reported while the handles are visible.
+    ControlPoint {
+        shape_idx: usize,
+        bez_idx: usize,
+        ctrl_idx: usize,
+    },
+    /// the stroke of a segment itself, `t` being the nearest
+    /// parameter on it
+    CurveSegment {
+        shape_idx: usize,
+        bez_idx: usize,
+        t: f64,
+    },
+    /// inside a shape's bounding box, but not on its stroke
+    ShapeBody { shape_idx: usize },
+    None,
+}
+
+impl HitTestResult {
+    /// the shape that was hit, if any
+    pub fn shape_idx(&self) -> Option<usize> {
+        match *self {
+            HitTestResult::ControlPoint { shape_idx, .. }
+            | HitTestResult::CurveSegment { shape_idx, .. }
+            | HitTestResult::ShapeBody { shape_idx } => Some(shape_idx),
+            HitTestResult::None => None,
+        }
+    }
+}
+
+impl Shaper {
+    /// how far (world units) the pointer may be from a point / the curve
+    /// of `shape` to still hit it, at the current zoom
+    pub fn pick_tolerances(&self, shape: &Shape) -> (f64, f64) {
+        let slop = self.screen_to_world_tol(HIT_SLOP_PX);
+        // handles are drawn `handle_radius` screen px big
+        let tol_point = self.screen_to_world_tol(HIT_SLOP_PX + self.handle_radius);
+        // half the stroke width is visibly "on" the curve, but thin
+        // strokes still get `min_pick_px` to be clickable at all
+        let tol_curve = (slop + shape.thickness as f64 * 0.5)
+            .max(self.screen_to_world_tol(self.min_pick_px));
+        (tol_point, tol_curve)
+    }
+
+    /// find the topmost thing under `mouse` (world space).
+    ///
+    /// shapes are tested from the last drawn (on top) to the first.
+    /// within a shape, control points win over the curve, and the
+    /// curve wins over the bounding box.
+    pub fn hit_test_all(&self, mouse: KPoint) -> HitTestResult {
+        self.hit_test_with_handles(mouse, |_| self.show_handles)
+    }
+
+    /// like `hit_test_all`, but the control points of a shape are tested
+    /// if `has_handles(shape_idx)` says its handles are on screen, for
+    /// tools that show handles for some shapes only
+    pub fn hit_test_with_handles(
+        &self,
+        mouse: KPoint,
+        has_handles: impl Fn(usize) -> bool,
+    ) -> HitTestResult {
+        (0..self.shapes.len())
+            .rev()
+            .map(|shape_idx| self.hit_shape(shape_idx, mouse, has_handles(shape_idx)))
+            .find(|hit| *hit != HitTestResult::None)
+            .unwrap_or(HitTestResult::None)
+    }
+
+    /// everything under `mouse` (world space), not just the topmost thing:
+    /// every control point (with the handles shown) within `tol` world
+    /// units and every segment within `tol` of its stroke's edge, in every
+    /// shape. a shape none of whose parts is in reach counts with its body
+    /// if `mouse` is inside its bounds. shapes from the top (last drawn)
+    /// down, within a shape its points before its segments. for picking
+    /// shapes hidden behind others.
+    pub fn hit_test_all_results(&self, mouse: KPoint, tol: f64) -> Vec<HitTestResult> {
+        let mut hits = Vec::new();
+        for (shape_idx, shape) in self.shapes.iter().enumerate().rev() {
+            let tol_curve = tol + shape.thickness as f64 * 0.5;
+            if !hit_bounds(shape, self.show_handles, tol, tol_curve)
+                .is_some_and(|bounds| bounds.contains(mouse))
+            {
+                continue;
+            }
+            let before = hits.len();
+            if self.show_handles {
+                for (bez_idx, bez) in shape.beziers.iter().enumerate() {
+                    for (ctrl_idx, pt) in [bez.p0, bez.p1, bez.p2, bez.p3].iter().enumerate() {
+                        if pt.distance(mouse) <= tol {
+                            hits.push(HitTestResult::ControlPoint {
+                                shape_idx,
+                                bez_idx,
+                                ctrl_idx,
+                            });
+                        }
+                    }
+                }
+            }
+            for (bez_idx, bez) in shape.beziers.iter().enumerate() {
+                let nearest = model::nearest(bez, mouse);
+                if nearest.distance_sq <= tol_curve * tol_curve {
+                    hits.push(HitTestResult::CurveSegment {
+                        shape_idx,
+                        bez_idx,
+                        t: nearest.t,
+                    });
+                }
+            }
+            let inside = shape.bounding_box().is_some_and(|bbox| bbox.contains(mouse));
+            if hits.len() == before && inside {
+                hits.push(HitTestResult::ShapeBody { shape_idx });
+            }
+        }
+        hits
+    }
+
+    /// what of `shapes[shape_idx]` is under `mouse`: a control point (if
+    /// `handles` are on screen) over the curve over the bounding box
+    fn hit_shape(&self, shape_idx: usize, mouse: KPoint, handles: bool) -> HitTestResult {
+        let shape = &self.shapes[shape_idx];
+        let (tol_point, tol_curve) = self.pick_tolerances(shape);
+
+        // cheap reject before `nearest` on every segment: the mouse has
+        // to be within reach of the shape's bounds at all
+        if !hit_bounds(shape, handles, tol_point, tol_curve)
+            .is_some_and(|bounds| bounds.contains(mouse))
+        {
+            return HitTestResult::None;
+        }
+        self.hit_shape_in_reach(shape_idx, mouse, handles)
+    }
+
+    /// `hit_shape` without the bounds check, testing every point and segment
+    fn hit_shape_in_reach(&self, shape_idx: usize, mouse: KPoint, handles: bool) -> HitTestResult {
+        let shape = &self.shapes[shape_idx];
+        let (tol_point, tol_curve) = self.pick_tolerances(shape);
+
+        if handles {
+            for (bez_idx, bez) in shape.beziers.iter().enumerate() {
+                let handles = [bez.p0, bez.p1, bez.p2, bez.p3];
+                for (ctrl_idx, pt) in handles.iter().enumerate() {
+                    if pt.distance(mouse) <= tol_point {
+                        return HitTestResult::ControlPoint {
+                            shape_idx,
+                            bez_idx,
+                            ctrl_idx,
+                        };
+                    }
+                }
+            }
+        }
+
+        for (bez_idx, bez) in shape.beziers.iter().enumerate() {
+            let nearest = model::nearest(bez, mouse);
+            if nearest.distance_sq <= tol_curve * tol_curve {
+                return HitTestResult::CurveSegment {
+                    shape_idx,
+                    bez_idx,
+                    t: nearest.t,
+                };
+            }
+        }
+
+        if shape.bounding_box().is_some_and(|bbox| bbox.contains(mouse)) {
+            return HitTestResult::ShapeBody { shape_idx };
+        }
+        HitTestResult::None
+    }
+
+    /// the anchor or segment midpoint of any shape closest to `world`, if
+    /// one is within `tol` (world units). new anchors snap to these so
+    /// hand-placed segments connect without gaps.
+    pub fn nearest_snap_target(&self, world: KPoint, tol: f64) -> Option<KPoint> {
+        self.shapes
+            .iter()
+            .filter(|shape| {
+                shape
+                    .bounding_box()
+                    .is_some_and(|bbox| bbox.inflate(tol, tol).contains(world))
+            })
+            .flat_map(|shape| shape.beziers.iter())
+            .flat_map(|bez| [bez.p0, bez.eval(0.5), bez.p3])
+            .map(|p| (p, p.distance(world)))
+            .filter(|&(_, dist)| dist <= tol)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(p, _)| p)
+    }
+}
+
+/// everything in `shape` that `hit_test_all` could report, grown by the
+/// pick tolerances. with the handles shown the control points count too,
+/// and they can stick out of the curve's own bounding box.
+fn hit_bounds(
+    shape: &Shape,
+    with_handles: bool,
+    tol_point: f64,
+    tol_curve: f64,
+) -> Option<KRect> {
+    let curve = shape.bounding_box()?.inflate(tol_curve, tol_curve);
+    if !with_handles {
+        return Some(curve);
+    }
+    let handles = shape
+        .beziers
+        .iter()
+        .flat_map(|bez| [bez.p0, bez.p1, bez.p2, bez.p3])
+        .map(|p| KRect::from_points(p, p))
+        .reduce(|a, b| a.union(b))?
+        .inflate(tol_point, tol_point);
+    Some(curve.union(handles))
+}
+
+#[cfg(test)]
+mod tests {
+    use eframe::egui::Color32;
+    use kurbo::CubicBez;
+
+    use super::*;
+
+    #[test]
+    fn thin_strokes_are_picked_from_min_pick_px_away() {
+        let mut app = Shaper::default();
+        app.add_line(KPoint::new(0.0, 0.0), KPoint::new(100.0, 0.0), 1.0, Color32::BLACK);
+        app.min_pick_px = 10.0;
+        // half a px of stroke and the slop alone wouldn't reach this far
+        let (_, tol_curve) = app.pick_tolerances(&app.shapes[0]);
+        assert_eq!(tol_curve, 10.0);
+        let hit = |app: &Shaper, y: f64| app.hit_test_all(KPoint::new(50.0, y)).shape_idx();
+        assert_eq!(hit(&app, 8.0), Some(0));
+        assert_eq!(hit(&app, 11.0), None);
+
+        // it's screen px: zoomed out, that's further in the world
+        app.zoom = 0.5;
+        assert_eq!(hit(&app, 19.0), Some(0));
+        assert_eq!(hit(&app, 21.0), None);
+    }
+
+    #[test]
+    fn the_bounds_check_does_not_change_what_is_hit() {
+        let mut app = Shaper::default();
+        app.add_line(KPoint::new(0.0, 0.0), KPoint::new(100.0, 0.0), 2.0, Color32::BLACK);
+        // handles far outside the curve's own bounding box, and a thick stroke
+        app.shapes[0].set_beziers(vec![CubicBez::new(
+            (0.0, 0.0),
+            (-40.0, 80.0),
+            (140.0, -80.0),
+            (100.0, 0.0),
+        )]);
+        app.shapes[0].thickness = 12.0;
+
+        let mut missed = 0;
+        for handles in [false, true] {
+            for x in (-60..=160).step_by(4) {
+                for y in (-100..=100).step_by(4) {
+                    let mouse = KPoint::new(x as f64, y as f64);
+                    let hit = app.hit_shape(0, mouse, handles);
+                    assert_eq!(
+                        hit,
+                        app.hit_shape_in_reach(0, mouse, handles),
+                        "at {mouse:?}, handles {handles}"
+                    );
+                    missed += (hit == HitTestResult::None) as usize;
+                }
+            }
+        }
+        // the grid reaches past the shape on every side
+        assert!(missed > 0);
+        assert_eq!(
+            app.hit_shape(0, KPoint::new(-40.0, 80.0), true),
+            HitTestResult::ControlPoint {
+                shape_idx: 0,
+                bez_idx: 0,
+                ctrl_idx: 1
+            }
+        );
+    }
+
+    #[test]
+    fn all_results_has_every_shape_under_the_pointer_top_first() {
+        let mut app = Shaper::default();
+        // the same line twice, one on top of the other, and one elsewhere
+        for y in [0.0, 0.0, 50.0] {
+            app.add_line(KPoint::new(0.0, y), KPoint::new(100.0, y), 2.0, Color32::BLACK);
+        }
+        let shapes = |hits: Vec<HitTestResult>| -> Vec<Option<usize>> {
+            hits.iter().map(HitTestResult::shape_idx).collect()
+        };
+
+        let mouse = KPoint::new(50.0, 0.0);
+        assert_eq!(shapes(app.hit_test_all_results(mouse, 4.0)), vec![Some(1), Some(0)]);
+        // the topmost is what `hit_test_all` finds
+        assert_eq!(app.hit_test_all(mouse).shape_idx(), Some(1));
+        assert!(app.hit_test_all_results(KPoint::new(50.0, 25.0), 4.0).is_empty());
+        // a wider reach gets the third line too (1 of it is its stroke)
+        assert_eq!(app.hit_test_all_results(KPoint::new(50.0, 25.0), 24.0).len(), 1);
+    }
+
+    #[test]
+    fn all_results_lists_every_point_and_segment_in_reach() {
+        let mut app = Shaper::default();
+        // two segments meeting at (50, 0), twice on top of each other
+        for _ in 0..2 {
+            let idx =
+                app.add_line(KPoint::new(0.0, 0.0), KPoint::new(50.0, 0.0), 2.0, Color32::BLACK);
+            let next = model::straight_cubic(KPoint::new(50.0, 0.0), KPoint::new(100.0, 0.0));
+            app.shapes[idx].beziers_mut().push(next);
+        }
+        let mouse = KPoint::new(51.0, 1.0);
+        let segment = |shape_idx, bez_idx| {
+            move |hit: &HitTestResult| {
+                matches!(*hit, HitTestResult::CurveSegment { shape_idx: s, bez_idx: b, .. }
+                    if (s, b) == (shape_idx, bez_idx))
+            }
+        };
+        let point = |shape_idx, bez_idx, ctrl_idx| HitTestResult::ControlPoint {
+            shape_idx,
+            bez_idx,
+            ctrl_idx,
+        };
+
+        // without handles only the segments, both of each shape
+        let hits = app.hit_test_all_results(mouse, 4.0);
+        assert_eq!(hits.len(), 4);
+        for (hit, (shape_idx, bez_idx)) in hits.iter().zip([(1, 0), (1, 1), (0, 0), (0, 1)]) {
+            assert!(segment(shape_idx, bez_idx)(hit), "{hit:?}");
+        }
+
+        // with them, the shared anchor of each shape (from both sides) first
+        app.show_handles = true;
+        let hits = app.hit_test_all_results(mouse, 4.0);
+        assert_eq!(&hits[..2], &[point(1, 0, 3), point(1, 1, 0)]);
+        assert!(segment(1, 0)(&hits[2]) && segment(1, 1)(&hits[3]));
+        assert_eq!(&hits[4..6], &[point(0, 0, 3), point(0, 1, 0)]);
+        assert_eq!(hits.len(), 8);
+        // a tight reach leaves out the points, the strokes are 1 either side
+        let hits = app.hit_test_all_results(mouse, 0.5);
+        assert_eq!(hits.len(), 4);
+        assert!(hits.iter().all(|hit| matches!(hit, HitTestResult::CurveSegment { .. })));
+    }
+}
@@ -0,0 +1,84 @@
+// minimal DXF writer for CAD / laser cutter workflows.
+//
+// every subpath of a shape becomes one 2D `LWPOLYLINE` (the curves are flattened first,
+// CAD tools rarely import cubic splines reliably), all on a single layer.
+// coordinates are written in the document's units (`Shaper::units`, also
+// in the header), y still pointing down like on the canvas. not supported
+// (yet): stroke widths, colors, fills, SPLINE entities and anything 3D.
+
+use std::fmt::Write as _;
+
+use crate::Shaper;
+
+/// max deviation (world units) of the flattened polylines from the curves
+const DXF_FLATTEN_TOL: f64 = 0.1;
+
+/// the layer every entity is put on
+const DXF_LAYER: &str = "SHAPER";
+
+impl Shaper {
+    pub fn export_dxf(&self) -> String {
+        let mut out = String::new();
+        // `write!` into a String can't fail
+        let mut pair = |code: i32, value: &str| {
+            let _ = writeln!(out, "{code}\n{value}");
+        };
+
+        pair(0, "SECTION");
+        pair(2, "HEADER");
+        pair(9, "$INSUNITS");
+        pair(70, &self.units.dxf_code().to_string());
+        pair(0, "ENDSEC");
+
+        pair(0, "SECTION");
+        pair(2, "ENTITIES");
+
+        for shape in &self.shapes {
+            for mut points in shape.flatten_subpaths(DXF_FLATTEN_TOL) {
+                if shape.closed {
+                    // the closed flag (70 = 1) already joins the ends
+                    points.pop();
+                }
+
+                pair(0, "LWPOLYLINE");
+                pair(8, DXF_LAYER);
+                pair(90, &points.len().to_string());
+                pair(70, if shape.closed { "1" } else { "0" });
+                for p in &points {
+                    pair(10, &(p.x / self.px_per_unit).to_string());
+                    pair(20, &(p.y / self.px_per_unit).to_string());
+                }
+            }
+        }
+
+        pair(0, "ENDSEC");
+        pair(0, "EOF");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use eframe::egui::Color32;
+    use kurbo::Point;
+
+    use crate::Shaper;
+    use crate::shape::Shape;
+
+    #[test]
+    fn every_subpath_is_its_own_polyline() {
+        let mut shape = Shape::new(2.0, Color32::BLACK);
+        for y in [0.0, 50.0] {
+            shape.start_subpath();
+            let line = crate::model::straight_cubic(Point::new(0.0, y), Point::new(100.0, y));
+            shape.beziers_mut().push(line);
+        }
+        let mut app = Shaper::default();
+        app.add_shape(shape);
+
+        let dxf = app.export_dxf();
+        assert_eq!(dxf.matches("LWPOLYLINE").count(), 2);
+        // two points each, no line from the end of one to the start of the other
+        assert_eq!(dxf.matches("\n90\n2\n").count(), 2);
+    }
+}
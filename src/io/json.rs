@@ -0,0 +1,104 @@
+use crate::Shaper;
+use crate::shape::Shape;
+
+/// dump the fitted geometry as JSON, for scripts that don't want to parse SVG:
+/// `[{"thickness": 10, "closed": false, "subpath_starts": [],`
+/// `"beziers": [[x0,y0,x1,y1,x2,y2,x3,y3], ...]}, ...]`, where
+/// `subpath_starts` are the indices into `beziers` where another,
+/// disconnected subpath starts (see `Shape::subpath_starts`)
+pub fn export_json(shapes: &[Shape]) -> String {
+    let items: Vec<String> = shapes
+        .iter()
+        .map(|shape| {
+            let beziers: Vec<String> = shape
+                .beziers
+                .iter()
+                .map(|b| {
+                    format!(
+                        "[{},{},{},{},{},{},{},{}]",
+                        b.p0.x, b.p0.y, b.p1.x, b.p1.y, b.p2.x, b.p2.y, b.p3.x, b.p3.y
+                    )
+                })
+                .collect();
+            let starts: Vec<String> = shape
+                .subpath_ranges()
+                .iter()
+                .skip(1)
+                .map(|range| range.start.to_string())
+                .collect();
+            format!(
+                "  {{\"thickness\": {}, \"closed\": {}, \"subpath_starts\": [{}], \"beziers\": [{}]}}",
+                shape.thickness,
+                shape.closed,
+                starts.join(", "),
+                beziers.join(", ")
+            )
+        })
+        .collect();
+
+    format!("[\n{}\n]\n", items.join(",\n"))
+}
+
+impl Shaper {
+    /// the JSON counterpart of `export_polyline_svg`, one polyline per
+    /// subpath (so a combined shape gives several):
+    /// `[{"thickness": 10, "closed": false, "points": [[x,y], ...]}, ...]`
+    pub fn export_polyline_json(&self, max_error: f32) -> String {
+        let items: Vec<String> = self
+            .shapes
+            .iter()
+            .flat_map(|shape| {
+                shape
+                    .flatten_subpaths(max_error as f64)
+                    .into_iter()
+                    .map(move |polyline| (shape, polyline))
+            })
+            .map(|(shape, polyline)| {
+                let points: Vec<String> =
+                    polyline.iter().map(|p| format!("[{},{}]", p.x, p.y)).collect();
+                format!(
+                    "  {{\"thickness\": {}, \"closed\": {}, \"points\": [{}]}}",
+                    shape.thickness,
+                    shape.closed,
+                    points.join(", ")
+                )
+            })
+            .collect();
+
+        format!("[\n{}\n]\n", items.join(",\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use eframe::egui::Color32;
+    use kurbo::Rect;
+
+    use super::*;
+    use crate::model;
+
+    /// one closed shape of two squares side by side, like "Combine" makes
+    fn two_squares() -> Shaper {
+        let mut shape = Shape::new(2.0, Color32::BLACK);
+        shape.start_subpath();
+        shape.beziers_mut().extend(model::rect_cubics(Rect::new(0.0, 0.0, 10.0, 10.0)));
+        shape.start_subpath();
+        shape.beziers_mut().extend(model::rect_cubics(Rect::new(20.0, 0.0, 30.0, 10.0)));
+        shape.closed = true;
+        let mut app = Shaper::default();
+        app.add_shape(shape);
+        app
+    }
+
+    #[test]
+    fn json_keeps_the_subpaths_apart() {
+        let app = two_squares();
+        assert!(export_json(&app.shapes).contains("\"subpath_starts\": [4]"));
+
+        // one polyline per square, the second starting on its own corner
+        let polylines = app.export_polyline_json(0.1);
+        assert_eq!(polylines.matches("\"points\"").count(), 2);
+        assert!(polylines.contains("\"points\": [[20,0]"));
+        assert!(!polylines.contains("[10,10], [20,0]"));
+    }
+}
@@ -0,0 +1,907 @@
+use std::collections::HashSet;
+
+use eframe::egui::Color32;
+use kurbo::{Arc, CubicBez, Point, QuadBez, Rect, SvgArc, Vec2};
+
+use crate::model::{self, straight_cubic};
+use crate::Shaper;
+use crate::shape::{FillRule, Shape};
+use crate::units::Units;
+
+/// empty space (world units) kept around the content in the viewBox
+const EXPORT_MARGIN: f64 = 10.0;
+
+/// serialize all shapes into a standalone SVG document, one `<path>` per
+/// shape. the viewBox is the `page` if there is one, otherwise the union
+/// bounding box of the content (plus a margin). with a `background` color
+/// the whole viewBox is filled with it first, otherwise the background
+/// stays transparent. the document's width and height are given in
+/// `units`, `px_per_unit` world units making one.
+pub fn export_svg(
+    shapes: &[Shape],
+    background: Option<Color32>,
+    page: Option<Rect>,
+    units: Units,
+    px_per_unit: f64,
+) -> String {
+    let mut out = svg_header(shapes, background, page, units, px_per_unit);
+    let mut ids = SvgIds::default();
+    for shape in shapes.iter().filter(|s| !s.beziers.is_empty()) {
+        // one M..C..(Z) run per subpath
+        let d = shape
+            .subpath_ranges()
+            .into_iter()
+            .map(|range| path_data(&shape.beziers[range], shape.closed))
+            .collect::<Vec<_>>()
+            .join(" ");
+        out.push_str(&path_element(&d, shape, &ids.next(&shape.name)));
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+/// what an export without a page covers: the union bounding box of all
+/// shapes, including their stroke width, plus `EXPORT_MARGIN`.
+/// `None` if there is nothing drawn.
+pub fn content_bounds(shapes: &[Shape]) -> Option<Rect> {
+    shapes
+        .iter()
+        .filter_map(|shape| {
+            // grow by half the stroke so thick strokes aren't cut off
+            let half = shape.thickness as f64 * 0.5;
+            shape.bounding_box().map(|b| b.inflate(half, half))
+        })
+        .reduce(|a, b| a.union(b))
+        .map(|bbox| bbox.inflate(EXPORT_MARGIN, EXPORT_MARGIN))
+}
+
+/// the opening `<svg>` tag, sized to the `page` or else the content,
+/// plus a rect covering it all if there is a `background`.
+/// the viewBox stays in world units (px), width/height are in `units`
+fn svg_header(
+    shapes: &[Shape],
+    background: Option<Color32>,
+    page: Option<Rect>,
+    units: Units,
+    px_per_unit: f64,
+) -> String {
+    let bbox = page.unwrap_or_else(|| content_bounds(shapes).unwrap_or_default());
+    // plain numbers are px already
+    let suffix = if units == Units::Px { "" } else { units.suffix() };
+
+    let mut header = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\" width=\"{}\" height=\"{}\">\n",
+        fmt_num(bbox.x0),
+        fmt_num(bbox.y0),
+        fmt_num(bbox.width()),
+        fmt_num(bbox.height()),
+        fmt_num(bbox.width() / px_per_unit) + suffix,
+        fmt_num(bbox.height() / px_per_unit) + suffix,
+    );
+    if let Some(color) = background {
+        header.push_str(&format!(
+            "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+            fmt_num(bbox.x0),
+            fmt_num(bbox.y0),
+            fmt_num(bbox.width()),
+            fmt_num(bbox.height()),
+            hex_color(color),
+        ));
+    }
+    header
+}
+
+/// hands out valid, unique element ids based on the shape names,
+/// so exports of the same drawing always get the same ids
+#[derive(Default)]
+struct SvgIds {
+    used: HashSet<String>,
+}
+
+impl SvgIds {
+    /// `name` as an XML id: runs of anything but letters, digits, `_` and `.`
+    /// become one `-`, it must not start with a digit/`.`, and repeated
+    /// names get a `-2`, `-3`, ... suffix
+    fn next(&mut self, name: &str) -> String {
+        let replaced: String = name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '_' | '.') { c } else { '-' })
+            .collect();
+        // "my shape (2)" -> "my-shape-2"
+        let mut base = replaced
+            .split('-')
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("-");
+        if base.is_empty() {
+            base = "shape".to_owned();
+        } else if !base.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            base.insert_str(0, "shape-");
+        }
+
+        let mut id = base.clone();
+        let mut n = 2;
+        while self.used.contains(&id) {
+            id = format!("{base}-{n}");
+            n += 1;
+        }
+        self.used.insert(id.clone());
+        id
+    }
+}
+
+/// one `<path>` line with the shape's stroke/fill styling
+fn path_element(d: &str, shape: &Shape, id: &str) -> String {
+    let filled = shape.closed && shape.fill_color != Color32::TRANSPARENT;
+    let (fill, fill_opacity) = if filled {
+        (hex_color(shape.fill_color), opacity_attr("fill-opacity", shape.fill_color))
+    } else {
+        ("none".to_owned(), String::new())
+    };
+    // nonzero is the SVG default
+    let fill_rule = match shape.fill_rule {
+        FillRule::EvenOdd if filled => " fill-rule=\"evenodd\"",
+        _ => "",
+    };
+    format!(
+        "  <path id=\"{}\" d=\"{}\" fill=\"{}\"{}{} stroke=\"{}\"{} stroke-width=\"{}\" stroke-linecap=\"round\" stroke-linejoin=\"round\"/>\n",
+        id,
+        d,
+        fill,
+        fill_opacity,
+        fill_rule,
+        hex_color(shape.stroke_color),
+        opacity_attr("stroke-opacity", shape.stroke_color),
+        fmt_num(shape.thickness as f64),
+    )
+}
+
+/// `M x y C ... [Z]` for one chain of segments
+fn path_data(beziers: &[CubicBez], closed: bool) -> String {
+    let mut d = String::new();
+    if let Some(first) = beziers.first() {
+        d.push_str(&format!("M{} {}", fmt_num(first.p0.x), fmt_num(first.p0.y)));
+    }
+    for bez in beziers {
+        // cubics that are exactly a line or a raised quadratic (as read
+        // from `L`/`Q`) are written as those again
+        if *bez == straight_cubic(bez.p0, bez.p3) {
+            d.push_str(&format!(" L{} {}", fmt_num(bez.p3.x), fmt_num(bez.p3.y)));
+            continue;
+        }
+        if let Some(ctrl) = as_quadratic(bez) {
+            d.push_str(&format!(
+                " Q{} {} {} {}",
+                fmt_num(ctrl.x),
+                fmt_num(ctrl.y),
+                fmt_num(bez.p3.x),
+                fmt_num(bez.p3.y),
+            ));
+            continue;
+        }
+        d.push_str(&format!(
+            " C{} {} {} {} {} {}",
+            fmt_num(bez.p1.x),
+            fmt_num(bez.p1.y),
+            fmt_num(bez.p2.x),
+            fmt_num(bez.p2.y),
+            fmt_num(bez.p3.x),
+            fmt_num(bez.p3.y),
+        ));
+    }
+    if closed {
+        d.push_str(" Z");
+    }
+    d
+}
+
+/// max deviation (world units) of the cubics an imported arc becomes
+const ARC_TOLERANCE: f64 = 0.01;
+
+/// an SVG arc as cubics. per the spec, radii too small to reach the end
+/// are scaled up, zero radii make it a straight line and an arc ending
+/// where it started is left out.
+fn arc_to_cubics(arc: &SvgArc) -> Vec<CubicBez> {
+    if arc.from == arc.to {
+        return Vec::new();
+    }
+    let Some(kurbo_arc) = Arc::from_svg_arc(arc) else {
+        return vec![straight_cubic(arc.from, arc.to)];
+    };
+    let mut cubics = Vec::new();
+    let mut from = arc.from;
+    kurbo_arc.to_cubic_beziers(ARC_TOLERANCE, |p1, p2, p3| {
+        cubics.push(CubicBez::new(from, p1, p2, p3));
+        from = p3;
+    });
+    // land exactly on the end point, the next segment starts there
+    if let Some(last) = cubics.last_mut() {
+        let delta = arc.to - last.p3;
+        last.p3 = arc.to;
+        last.p2 += delta;
+    }
+    cubics
+}
+
+/// the control point of the quadratic `bez` was raised from (see
+/// `QuadBez::raise`), if it is one. both handles then point at the same
+/// quadratic control point from their ends.
+fn as_quadratic(bez: &CubicBez) -> Option<Point> {
+    let from_start = bez.p0 + (bez.p1 - bez.p0) * 1.5;
+    let from_end = bez.p3 + (bez.p2 - bez.p3) * 1.5;
+    (from_start.distance(from_end) < 1e-9).then_some(from_start)
+}
+
+/// at most 3 decimals, without trailing zeros
+fn fmt_num(v: f64) -> String {
+    let s = format!("{:.3}", v);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" { "0".to_owned() } else { s.to_owned() }
+}
+
+/// `#rrggbb` of `c` without its alpha (see `opacity_attr`). `Color32` is
+/// premultiplied, the SVG color is not.
+fn hex_color(c: Color32) -> String {
+    let [r, g, b, _] = c.to_srgba_unmultiplied();
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+/// ` name="0.5"` for a translucent `c`, nothing for an opaque one
+fn opacity_attr(name: &str, c: Color32) -> String {
+    if c.a() == 255 {
+        return String::new();
+    }
+    format!(" {}=\"{}\"", name, fmt_num(c.a() as f64 / 255.0))
+}
+
+/// `color` with the alpha from an `opacity` attribute value (0..1)
+fn with_opacity(color: Color32, opacity: Option<&str>) -> Color32 {
+    let Some(opacity) = opacity.and_then(|o| o.trim().parse::<f32>().ok()) else {
+        return color;
+    };
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    let a = (a as f32 * opacity.clamp(0.0, 1.0)).round() as u8;
+    Color32::from_rgba_unmultiplied(r, g, b, a)
+}
+
+/// where and why the path data of an SVG couldn't be read
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SvgParseError {
+    /// byte offset into the document
+    pub offset: usize,
+    /// the command being read, or the character in the way
+    pub command: char,
+    /// what's wrong, read as "<reason> `<command>`"
+    pub reason: &'static str,
+}
+
+impl std::fmt::Display for SvgParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} `{}` at byte {}", self.reason, self.command, self.offset)
+    }
+}
+
+impl std::error::Error for SvgParseError {}
+
+const BAD_ARGUMENTS: &str = "missing or malformed arguments for";
+const UNKNOWN_COMMAND: &str = "unknown path command";
+const NO_COMMAND: &str = "no command before";
+const UNEXPECTED_CHAR: &str = "unexpected character";
+
+/// read every `<path>` of an SVG document into shapes, one shape per path
+/// (its subpaths stay together, so the fill rule works across them),
+/// appended to `shapes`. returns how many were added.
+///
+/// the `id` of a path becomes the shape's name.
+/// all path commands are understood, absolute and relative. `stroke`,
+/// `stroke-width` and `fill` attributes are picked up when they are plain
+/// hex colors / numbers, along with `stroke-opacity` and `fill-opacity`.
+///
+/// stops at the first path whose data can't be read. the paths before it
+/// stay in `shapes`, that one and everything after it is left out.
+pub fn import_svg(src: &str, shapes: &mut Vec<Shape>) -> Result<usize, SvgParseError> {
+    let before = shapes.len();
+
+    let mut rest = src;
+    while let Some(start) = rest.find("<path") {
+        let tag_start = &rest[start..];
+        let Some(end) = tag_start.find('>') else {
+            break;
+        };
+        let tag = &tag_start[..end];
+        rest = &tag_start[end..];
+
+        let Some(d) = attr(tag, "d") else {
+            continue;
+        };
+        let thickness = attr(tag, "stroke-width")
+            .and_then(|w| w.trim().parse::<f32>().ok())
+            .unwrap_or(1.0);
+        let stroke_color = attr(tag, "stroke")
+            .and_then(parse_hex_color)
+            .map(|c| with_opacity(c, attr(tag, "stroke-opacity")))
+            .unwrap_or(Color32::BLACK);
+        let fill_color = attr(tag, "fill")
+            .and_then(parse_hex_color)
+            .map(|c| with_opacity(c, attr(tag, "fill-opacity")))
+            .unwrap_or(Color32::TRANSPARENT);
+        let fill_rule = match attr(tag, "fill-rule") {
+            Some("evenodd") => FillRule::EvenOdd,
+            _ => FillRule::NonZero,
+        };
+
+        let name = attr(tag, "id").unwrap_or_default();
+        // `d` points into `src`, so this is where it starts in the document
+        let d_offset = d.as_ptr() as usize - src.as_ptr() as usize;
+        let subpaths = parse_path_data(d).map_err(|e| SvgParseError {
+            offset: d_offset + e.offset,
+            ..e
+        })?;
+        if subpaths.is_empty() {
+            continue;
+        }
+        let mut shape = Shape::new(thickness, stroke_color);
+        shape.name = name.to_owned();
+        // a shape is closed or open as a whole. `Z` already added the
+        // closing segment, so a closed subpath in an open shape keeps its
+        // outline and only loses the fill
+        shape.closed = subpaths.iter().all(|(_, closed)| *closed);
+        for (beziers, _) in subpaths {
+            shape.start_subpath();
+            shape.beziers_mut().extend(beziers);
+        }
+        if shape.closed {
+            shape.fill_color = fill_color;
+            shape.fill_rule = fill_rule;
+        }
+        shapes.push(shape);
+    }
+
+    Ok(shapes.len() - before)
+}
+
+/// value of attribute `name` inside a tag, e.g. `attr("<path d=\"M0 0\"", "d")`
+fn attr<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let bytes = tag.as_bytes();
+    let mut from = 0;
+    while let Some(pos) = tag[from..].find(name) {
+        let at = from + pos;
+        from = at + name.len();
+        // must be a whole attribute name: whitespace before, `=` after
+        if at == 0 || !bytes[at - 1].is_ascii_whitespace() {
+            continue;
+        }
+        let after = tag[from..].trim_start();
+        let Some(after) = after.strip_prefix('=') else {
+            continue;
+        };
+        let after = after.trim_start();
+        let quote = after.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            continue;
+        }
+        let value = &after[1..];
+        return value.find(quote).map(|close| &value[..close]);
+    }
+    None
+}
+
+/// `#rgb` or `#rrggbb`
+fn parse_hex_color(s: &str) -> Option<Color32> {
+    let hex = s.trim().strip_prefix('#')?;
+    let channel = |i: usize, len: usize| u8::from_str_radix(hex.get(i..i + len)?, 16).ok();
+    match hex.len() {
+        3 => Some(Color32::from_rgb(
+            channel(0, 1)? * 17,
+            channel(1, 1)? * 17,
+            channel(2, 1)? * 17,
+        )),
+        6 => Some(Color32::from_rgb(channel(0, 2)?, channel(2, 2)?, channel(4, 2)?)),
+        _ => None,
+    }
+}
+
+/// split path data into subpaths of cubic segments (+ whether each is closed).
+/// lowercase (relative) commands are turned into absolute coordinates
+/// from the current point as they are read. error offsets are into `d`.
+fn parse_path_data(d: &str) -> Result<Vec<(Vec<CubicBez>, bool)>, SvgParseError> {
+    let mut subpaths = Vec::new();
+    let mut current: Vec<CubicBez> = Vec::new();
+    let mut start = Point::ZERO;
+    let mut pos = Point::ZERO;
+    // second control point of the last C/S, for the reflection of a following S
+    let mut last_cubic_ctrl: Option<Point> = None;
+    // control point of the last Q/T, for the reflection of a following T
+    let mut last_quad_ctrl: Option<Point> = None;
+
+    let mut tokens = Tokenizer {
+        src: d,
+        at: 0,
+        failed_at: None,
+    };
+    let mut cmd: Option<char> = None;
+
+    loop {
+        let next = match tokens.peek_command() {
+            Some(c) => {
+                tokens.at += c.len_utf8();
+                cmd = Some(c);
+                c
+            }
+            // numbers without a command letter repeat the last command
+            None if tokens.has_number() => match cmd {
+                // extra pairs after a moveto are linetos
+                Some('M') => 'L',
+                Some('m') => 'l',
+                Some(c) => c,
+                None => return Err(tokens.error(tokens.current(), NO_COMMAND)),
+            },
+            None => match tokens.current() {
+                '\0' => break,
+                c => return Err(tokens.error(c, UNEXPECTED_CHAR)),
+            },
+        };
+
+        // relative coordinates are offsets from the point the command starts at
+        let relative = next.is_ascii_lowercase();
+        let origin = pos;
+        let abs = |p: Point| if relative { p + origin.to_vec2() } else { p };
+
+        let cubic_ctrl = last_cubic_ctrl.take();
+        let quad_ctrl = last_quad_ctrl.take();
+        match next.to_ascii_uppercase() {
+            'M' => {
+                let Some(p) = tokens.point() else {
+                    return Err(tokens.error(next, BAD_ARGUMENTS));
+                };
+                if !current.is_empty() {
+                    subpaths.push((std::mem::take(&mut current), false));
+                }
+                start = abs(p);
+                pos = start;
+            }
+            'L' => {
+                let Some(p) = tokens.point() else {
+                    return Err(tokens.error(next, BAD_ARGUMENTS));
+                };
+                let p = abs(p);
+                current.push(straight_cubic(pos, p));
+                pos = p;
+            }
+            'H' => {
+                let Some(x) = tokens.number() else {
+                    return Err(tokens.error(next, BAD_ARGUMENTS));
+                };
+                let p = Point::new(if relative { pos.x + x } else { x }, pos.y);
+                current.push(straight_cubic(pos, p));
+                pos = p;
+            }
+            'V' => {
+                let Some(y) = tokens.number() else {
+                    return Err(tokens.error(next, BAD_ARGUMENTS));
+                };
+                let p = Point::new(pos.x, if relative { pos.y + y } else { y });
+                current.push(straight_cubic(pos, p));
+                pos = p;
+            }
+            'C' => {
+                let (Some(p1), Some(p2), Some(p3)) = (tokens.point(), tokens.point(), tokens.point())
+                else {
+                    return Err(tokens.error(next, BAD_ARGUMENTS));
+                };
+                let (p1, p2, p3) = (abs(p1), abs(p2), abs(p3));
+                current.push(CubicBez { p0: pos, p1, p2, p3 });
+                last_cubic_ctrl = Some(p2);
+                pos = p3;
+            }
+            'S' => {
+                let (Some(p2), Some(p3)) = (tokens.point(), tokens.point()) else {
+                    return Err(tokens.error(next, BAD_ARGUMENTS));
+                };
+                let (p2, p3) = (abs(p2), abs(p3));
+                // the first control point mirrors the last one, if the
+                // previous command was a C/S, otherwise it's the current point
+                let p1 = cubic_ctrl.map_or(pos, |c| pos + (pos - c));
+                current.push(CubicBez { p0: pos, p1, p2, p3 });
+                last_cubic_ctrl = Some(p2);
+                pos = p3;
+            }
+            'Q' => {
+                let (Some(ctrl), Some(p)) = (tokens.point(), tokens.point()) else {
+                    return Err(tokens.error(next, BAD_ARGUMENTS));
+                };
+                let (ctrl, p) = (abs(ctrl), abs(p));
+                current.push(QuadBez::new(pos, ctrl, p).raise());
+                last_quad_ctrl = Some(ctrl);
+                pos = p;
+            }
+            'T' => {
+                let Some(p) = tokens.point() else {
+                    return Err(tokens.error(next, BAD_ARGUMENTS));
+                };
+                let p = abs(p);
+                // the control point mirrors the last one, if the previous
+                // command was a Q/T, otherwise it's the current point
+                let ctrl = quad_ctrl.map_or(pos, |c| pos + (pos - c));
+                current.push(QuadBez::new(pos, ctrl, p).raise());
+                last_quad_ctrl = Some(ctrl);
+                pos = p;
+            }
+            'A' => {
+                let (Some(rx), Some(ry), Some(rotation)) =
+                    (tokens.number(), tokens.number(), tokens.number())
+                else {
+                    return Err(tokens.error(next, BAD_ARGUMENTS));
+                };
+                let (Some(large_arc), Some(sweep), Some(p)) =
+                    (tokens.flag(), tokens.flag(), tokens.point())
+                else {
+                    return Err(tokens.error(next, BAD_ARGUMENTS));
+                };
+                let p = abs(p);
+                let arc = SvgArc {
+                    from: pos,
+                    to: p,
+                    radii: Vec2::new(rx, ry),
+                    x_rotation: rotation.to_radians(),
+                    large_arc,
+                    sweep,
+                };
+                current.extend(arc_to_cubics(&arc));
+                pos = p;
+            }
+            'Z' => {
+                if pos.distance(start) > 1e-9 {
+                    current.push(straight_cubic(pos, start));
+                }
+                pos = start;
+                if !current.is_empty() {
+                    subpaths.push((std::mem::take(&mut current), true));
+                }
+                cmd = None;
+            }
+            _ => {
+                // point at the letter itself, the cursor is already past it
+                tokens.at -= next.len_utf8();
+                return Err(tokens.error(next, UNKNOWN_COMMAND));
+            }
+        }
+    }
+
+    if !current.is_empty() {
+        subpaths.push((current, false));
+    }
+    Ok(subpaths)
+}
+
+/// minimal scanner over SVG path data
+struct Tokenizer<'a> {
+    src: &'a str,
+    at: usize,
+    /// where the first read that found no number/flag was. the arguments
+    /// of a command are read all at once, so later reads may have moved on
+    failed_at: Option<usize>,
+}
+
+impl Tokenizer<'_> {
+    fn skip_separators(&mut self) {
+        let rest = &self.src[self.at..];
+        let trimmed = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        self.at += rest.len() - trimmed.len();
+    }
+
+    /// the command letter at the cursor, if the next token is one
+    fn peek_command(&mut self) -> Option<char> {
+        self.skip_separators();
+        self.src[self.at..]
+            .chars()
+            .next()
+            .filter(|c| c.is_ascii_alphabetic() && *c != 'e' && *c != 'E')
+    }
+
+    fn has_number(&mut self) -> bool {
+        self.skip_separators();
+        self.src[self.at..]
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_digit() || c == '-' || c == '+' || c == '.')
+    }
+
+    /// read one number, handling the compact forms like `1-2` or `.5.5`
+    fn number(&mut self) -> Option<f64> {
+        if !self.has_number() {
+            self.failed_at.get_or_insert(self.at);
+            return None;
+        }
+        let bytes = self.src.as_bytes();
+        let begin = self.at;
+        let mut i = self.at;
+        if bytes[i] == b'-' || bytes[i] == b'+' {
+            i += 1;
+        }
+        let mut seen_dot = false;
+        while i < bytes.len() && (bytes[i].is_ascii_digit() || (bytes[i] == b'.' && !seen_dot)) {
+            seen_dot |= bytes[i] == b'.';
+            i += 1;
+        }
+        // exponent
+        if i < bytes.len() && (bytes[i] == b'e' || bytes[i] == b'E') {
+            let mut j = i + 1;
+            if j < bytes.len() && (bytes[j] == b'-' || bytes[j] == b'+') {
+                j += 1;
+            }
+            if j < bytes.len() && bytes[j].is_ascii_digit() {
+                i = j;
+                while i < bytes.len() && bytes[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+        }
+        let Ok(value) = self.src[begin..i].parse::<f64>() else {
+            self.failed_at.get_or_insert(begin);
+            return None;
+        };
+        self.at = i;
+        Some(value)
+    }
+
+    /// an arc flag: a single `0` or `1`, which may be written
+    /// without a separator before the next number (`a1 1 0 01 5 5`)
+    fn flag(&mut self) -> Option<bool> {
+        self.skip_separators();
+        let flag = match self.src[self.at..].chars().next() {
+            Some('0') => false,
+            Some('1') => true,
+            _ => {
+                self.failed_at.get_or_insert(self.at);
+                return None;
+            }
+        };
+        self.at += 1;
+        Some(flag)
+    }
+
+    fn point(&mut self) -> Option<Point> {
+        let x = self.number()?;
+        let y = self.number()?;
+        Some(Point::new(x, y))
+    }
+
+    /// the character at the cursor, `'\0'` at the end
+    fn current(&self) -> char {
+        self.src[self.at..].chars().next().unwrap_or('\0')
+    }
+
+    /// an error at the first failed read, or else the cursor
+    fn error(&self, command: char, reason: &'static str) -> SvgParseError {
+        SvgParseError {
+            offset: self.failed_at.unwrap_or(self.at),
+            command,
+            reason,
+        }
+    }
+}
+
+/// re-fit every shape from a flattened version of its own outline,
+/// e.g. to reduce the segment count of an imported file. every subpath
+/// is refit on its own, so they stay apart
+pub fn simplify_shapes(shapes: &mut [Shape], tol: f64) {
+    for shape in shapes {
+        let mut refit = Shape::new(shape.thickness, shape.stroke_color);
+        for points in shape.flatten_subpaths(tol * 0.5) {
+            let (fit, corners) = model::fit_curve(&points, tol, model::STRAIGHT_ANGLE_TOL);
+            refit.start_subpath();
+            let offset = refit.beziers.len();
+            refit.corners.extend(corners.into_iter().map(|k| k + offset));
+            refit.beziers_mut().extend(fit);
+        }
+        if !refit.beziers.is_empty() {
+            shape.set_beziers(refit.beziers);
+            shape.subpath_starts = refit.subpath_starts;
+            shape.corners = refit.corners;
+        }
+    }
+}
+
+impl Shaper {
+    /// like `export_svg`, but every shape is baked into straight line
+    /// segments (only `M`/`L`/`Z`) that stay within `max_error` world units
+    /// of the curves. for plotters, laser cutters etc. that can't do cubics.
+    pub fn export_polyline_svg(&self, max_error: f32) -> String {
+        let mut out = svg_header(
+            &self.shapes,
+            None,
+            self.export_region(),
+            self.units,
+            self.px_per_unit,
+        );
+        let mut ids = SvgIds::default();
+        for shape in self.shapes.iter().filter(|s| !s.beziers.is_empty()) {
+            // one polyline per subpath, each starting with its own `M`
+            let mut d = String::new();
+            for mut points in shape.flatten_subpaths(max_error as f64) {
+                if shape.closed {
+                    // `Z` already goes back to the start
+                    points.pop();
+                }
+                for (i, p) in points.iter().enumerate() {
+                    let cmd = if i == 0 { 'M' } else { 'L' };
+                    if !d.is_empty() {
+                        d.push(' ');
+                    }
+                    d.push_str(&format!("{cmd}{} {}", fmt_num(p.x), fmt_num(p.y)));
+                }
+                if shape.closed {
+                    d.push_str(" Z");
+                }
+            }
+            out.push_str(&path_element(&d, shape, &ids.next(&shape.name)));
+        }
+        out.push_str("</svg>\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kurbo::ParamCurve;
+
+    /// one shape of two open, disconnected lines (as "Combine" makes them)
+    fn two_lines() -> Shaper {
+        let mut shape = Shape::new(2.0, Color32::BLACK);
+        for y in [0.0, 50.0] {
+            shape.start_subpath();
+            shape.beziers_mut().push(straight_cubic(Point::new(0.0, y), Point::new(100.0, y)));
+        }
+        let mut app = Shaper::default();
+        app.add_shape(shape);
+        app
+    }
+
+    #[test]
+    fn polyline_export_starts_every_subpath_on_its_own() {
+        let svg = two_lines().export_polyline_svg(0.1);
+        assert!(svg.contains("d=\"M0 0 L100 0 M0 50 L100 50\""), "{svg}");
+    }
+
+    #[test]
+    fn polyline_export_uses_as_many_lines_as_the_tolerance_needs() {
+        // a quarter circle of radius 100, as a cubic
+        let k = 100.0 * 0.5523;
+        let arc = CubicBez::new((100.0, 0.0), (100.0, k), (k, 100.0), (0.0, 100.0));
+        let mut app = Shaper::default();
+        let mut shape = Shape::new(2.0, Color32::BLACK);
+        shape.set_beziers(vec![arc]);
+        app.add_shape(shape);
+
+        let lines = |max_error: f32| app.export_polyline_svg(max_error).matches(" L").count();
+        // n chords across 90° stay within 1 of the circle once
+        // 100 * (1 - cos(45° / n)) <= 1, that is from n = 6 on
+        assert_eq!(lines(1.0), 6);
+        assert!(lines(0.1) > 6);
+        assert!(lines(20.0) < 6);
+    }
+
+    #[test]
+    fn simplify_keeps_the_subpaths_apart() {
+        let mut shapes = two_lines().shapes;
+        simplify_shapes(&mut shapes, 1.0);
+        let shape = &shapes[0];
+        assert_eq!(shape.subpath_ranges().len(), 2);
+        let second = &shape.beziers[shape.subpath_ranges()[1].clone()];
+        assert_eq!(second[0].p0, Point::new(0.0, 50.0));
+    }
+
+    fn parse_error(d: &str) -> SvgParseError {
+        parse_path_data(d).expect_err(d)
+    }
+
+    #[test]
+    fn bad_path_data_says_where() {
+        // a sign without digits
+        let e = parse_error("M0 0 L- 5");
+        assert_eq!((e.offset, e.command, e.reason), (6, 'L', BAD_ARGUMENTS));
+        // numbers before any command
+        let e = parse_error("  10 20 L5 5");
+        assert_eq!((e.offset, e.command, e.reason), (2, '1', NO_COMMAND));
+        // the end point of the curve is missing
+        let e = parse_error("M0 0 C1 1 2 2");
+        assert_eq!((e.offset, e.command, e.reason), (13, 'C', BAD_ARGUMENTS));
+    }
+
+    #[test]
+    fn import_errors_are_offsets_into_the_document() {
+        let src = r#"<path d="M0 0 L1 1"/><path d="M0 0 L- 5"/><path d="M0 0 L2 2"/>"#;
+        let mut shapes = Vec::new();
+        let e = import_svg(src, &mut shapes).unwrap_err();
+        assert_eq!(&src[e.offset..e.offset + 1], "-");
+        assert_eq!(e.offset, src.find("L-").unwrap() + 1);
+        // the path before it is kept, the one after it isn't read
+        assert_eq!(shapes.len(), 1);
+    }
+
+    #[test]
+    fn quadratics_come_back_out_as_q() {
+        let (beziers, closed) = parse_path_data("M0 0 Q50 100 100 0").unwrap().remove(0);
+        let raised = QuadBez::new((0.0, 0.0), (50.0, 100.0), (100.0, 0.0)).raise();
+        assert_eq!(beziers, vec![raised]);
+        let ctrl = as_quadratic(&beziers[0]).unwrap();
+        assert!(ctrl.distance(Point::new(50.0, 100.0)) < 1e-9);
+        assert_eq!(path_data(&beziers, closed), "M0 0 Q50 100 100 0");
+
+        // a cubic that isn't one stays a `C`
+        let cubic = CubicBez::new((0.0, 0.0), (10.0, 50.0), (60.0, 50.0), (100.0, 0.0));
+        assert_eq!(as_quadratic(&cubic), None);
+        assert_eq!(path_data(&[cubic], false), "M0 0 C10 50 60 50 100 0");
+    }
+
+    #[test]
+    fn arcs_become_cubics_from_end_to_end_along_the_circle() {
+        let (beziers, _) = parse_path_data("M0 0 A50 50 0 0 1 100 0").unwrap().remove(0);
+        assert!(beziers.len() >= 2);
+        assert_eq!(beziers[0].p0, Point::new(0.0, 0.0));
+        assert_eq!(beziers.last().unwrap().p3, Point::new(100.0, 0.0));
+        let center = Point::new(50.0, 0.0);
+        for pair in beziers.windows(2) {
+            assert_eq!(pair[0].p3, pair[1].p0);
+        }
+        for bez in &beziers {
+            let mid = bez.eval(0.5);
+            assert!((mid.distance(center) - 50.0).abs() < ARC_TOLERANCE * 2.0);
+        }
+
+        // zero radii: a line, same start and end: nothing
+        let line = SvgArc {
+            from: Point::ZERO,
+            to: Point::new(10.0, 0.0),
+            radii: Vec2::ZERO,
+            x_rotation: 0.0,
+            large_arc: false,
+            sweep: true,
+        };
+        assert_eq!(arc_to_cubics(&line), vec![straight_cubic(line.from, line.to)]);
+        assert!(arc_to_cubics(&SvgArc { to: Point::ZERO, ..line }).is_empty());
+    }
+
+    #[test]
+    fn relative_commands_go_on_from_the_current_point() {
+        let d = "M10 10 L20 10 l10 0 h10 v10 H10 V10 m5 5 c0 10 10 10 10 0 s10 -10 10 0 \
+                 C50 0 60 0 70 15";
+        let subpaths = parse_path_data(d).unwrap();
+        assert_eq!(subpaths.len(), 2);
+
+        let corners = [(10, 10), (20, 10), (30, 10), (40, 10), (40, 20), (10, 20), (10, 10)]
+            .map(|(x, y)| Point::new(x as f64, y as f64));
+        let lines: Vec<CubicBez> = corners.windows(2).map(|w| straight_cubic(w[0], w[1])).collect();
+        assert_eq!(subpaths[0], (lines, false));
+
+        let curves = vec![
+            CubicBez::new((15.0, 15.0), (15.0, 25.0), (25.0, 25.0), (25.0, 15.0)),
+            // `s` mirrors the last handle around the current point
+            CubicBez::new((25.0, 15.0), (25.0, 5.0), (35.0, 5.0), (35.0, 15.0)),
+            CubicBez::new((35.0, 15.0), (50.0, 0.0), (60.0, 0.0), (70.0, 15.0)),
+        ];
+        assert_eq!(subpaths[1], (curves, false));
+    }
+
+    #[test]
+    fn a_path_with_several_subpaths_is_one_shape() {
+        let src = r##"<path id="eight" fill="#ff0000" fill-rule="evenodd"
+            d="M0 0 H100 V100 H0 Z M50 50 H150 V150 H50 Z"/>
+            <path d="M0 0 L10 0 Z M20 0 L30 0"/>"##;
+        let mut shapes = Vec::new();
+        assert_eq!(import_svg(src, &mut shapes), Ok(2));
+
+        let eight = &shapes[0];
+        assert_eq!(eight.name, "eight");
+        assert!(eight.closed);
+        assert!(eight.fill_rule == FillRule::EvenOdd);
+        assert_eq!(eight.subpath_ranges(), vec![0..4, 4..8]);
+        assert_eq!(eight.beziers[4].p0, Point::new(50.0, 50.0));
+
+        // one open subpath leaves the whole shape open
+        assert!(!shapes[1].closed);
+        assert_eq!(shapes[1].subpath_ranges().len(), 2);
+    }
+}
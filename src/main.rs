@@ -1,28 +1,128 @@
+mod cli;
+mod commands;
+mod hit_test;
+mod io {
+    pub mod dxf;
+    pub mod json;
+    pub mod svg;
+}
+mod model;
+mod navigator;
+mod reference;
+mod selection;
 mod shape;
+mod shape_ops;
 mod tool;
+mod units;
 mod tools {
     pub mod drawing_tool;
     pub mod editing_tool;
     pub mod panning_tool;
+    pub mod selecting_tool;
+    pub mod smoothing_tool;
+    pub mod bucket_tool;
+    pub mod knife_tool;
 }
 use core::f32;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::process::ExitCode;
 
-use crate::shape::Shape;
+use crate::commands::CommandPalette;
+use crate::reference::ReferenceImage;
+use crate::selection::PointId;
+use crate::shape::{HandleShape, Shape};
 use crate::tool::Tool;
+use crate::units::Units;
 use eframe::egui::{self, Context, Visuals};
 use egui::emath::Vec2;
 use egui::{Align, Color32, Layout, Sense, Pos2};
 use tools::drawing_tool::DrawingTool;
 use tools::editing_tool::EditingTool;
 use tools::panning_tool::PanningTool;
+use tools::selecting_tool::SelectingTool;
+use tools::smoothing_tool::SmoothingTool;
+use tools::bucket_tool::BucketTool;
+use tools::knife_tool::KnifeTool;
+
+/// how much of the content (screen px) `clamp_pan` keeps in view
+const PAN_CLAMP_MARGIN: f32 = 40.0;
+
+/// how long (seconds) a toast stays up
+const TOAST_SECS: f64 = 6.0;
+
+/// how fast scrolling zooms: the zoom is multiplied by e^(speed * scroll px)
+const SCROLL_ZOOM_SPEED: f32 = 0.009;
+
+/// zoom factor of one Ctrl + / Ctrl - press
+const KEYBOARD_ZOOM_STEP: f32 = 1.25;
+
+/// page sizes to pick from in the settings, in world units (CSS px, 96 dpi)
+const PAGE_PRESETS: [(&str, (f64, f64)); 3] = [
+    ("A4", (793.7, 1122.5)),
+    ("A5", (559.4, 793.7)),
+    ("Letter", (816.0, 1056.0)),
+];
+
+/// offset (screen px) of the drop shadow under the page
+const PAGE_SHADOW_OFFSET: f32 = 4.0;
+
+// keys in eframe's storage, see `Shaper::save` and `Shaper::restore_session`
+const STORAGE_TOOL: &str = "selected_tool";
+/// "zoom pan_x pan_y"
+const STORAGE_VIEW: &str = "view";
 
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub enum ToolKind {
     Drawing,
     Panning,
     Editing,
-    // for later:
-    //Selection
+    Selection,
+    Smooth,
+    Bucket,
+    Knife,
+}
+
+impl ToolKind {
+    const ALL: [ToolKind; 7] = [
+        ToolKind::Drawing,
+        ToolKind::Panning,
+        ToolKind::Editing,
+        ToolKind::Selection,
+        ToolKind::Smooth,
+        ToolKind::Bucket,
+        ToolKind::Knife,
+    ];
+
+    /// stable name, used to persist the tool between sessions
+    fn storage_name(self) -> &'static str {
+        match self {
+            ToolKind::Drawing => "drawing",
+            ToolKind::Panning => "panning",
+            ToolKind::Editing => "editing",
+            ToolKind::Selection => "selection",
+            ToolKind::Smooth => "smooth",
+            ToolKind::Bucket => "bucket",
+            ToolKind::Knife => "knife",
+        }
+    }
+
+    fn from_storage_name(name: &str) -> Option<ToolKind> {
+        ToolKind::ALL.into_iter().find(|tool| tool.storage_name() == name)
+    }
+
+    /// name in the ui, as on the tools window buttons
+    fn label(self) -> &'static str {
+        match self {
+            ToolKind::Drawing => "Draw",
+            ToolKind::Panning => "Pan-Zoom",
+            ToolKind::Editing => "Edit",
+            ToolKind::Selection => "Select",
+            ToolKind::Smooth => "Smooth",
+            ToolKind::Bucket => "Bucket",
+            ToolKind::Knife => "Knife",
+        }
+    }
 }
 
 #[allow(dead_code)]
@@ -30,12 +130,64 @@ pub enum ToolKind {
 struct Shaper {
     // render the control points or not
     pub show_handles: bool,
+    // draw the handles under the strokes instead of over them
+    // (thick strokes hide them then, but thin ones stay visible)
+    pub handles_behind_strokes: bool,
+    // strokes can always be picked from at least this far away (screen px),
+    // however thin they are
+    pub min_pick_px: f32,
+    // debug overlay: `segment:point` index next to every point
+    // of the selected shapes, see `Shape::draw_point_labels`
+    pub show_point_labels: bool,
+    // debug overlay: document size and what a frame costs, see `draw_stats`
+    pub show_stats: bool,
+    // screen points the strokes were flattened to this frame,
+    // counted by `Shape::draw_beziers` for the stats overlay
+    pub flattened_points: Cell<usize>,
 
     // render the original line for comparison
     pub draw_original_stroke: bool,
+    // ...but only for the selected shapes
+    pub original_stroke_selected_only: bool,
+    // how it's drawn. the width is in screen px, it doesn't scale with
+    // the zoom so it never covers up the fit
+    pub original_stroke_color: Color32,
+    pub original_stroke_width: f32,
+
+    // stroke shapes with our own mitered, feathered mesh instead of
+    // egui's line, which gets ugly at the joints of thick strokes
+    pub hq_strokes: bool,
+
+    // color painted behind all shapes
+    pub canvas_background: Color32,
+
+    // keep some of the drawing on screen while panning
+    pub clamp_pan: bool,
+    // the mouse wheel pans instead of zooming, see `handle_scroll`
+    pub scroll_pans: bool,
+
+    // optional artboard (width, height) in world units, with its top left
+    // corner at the world origin. the export region when set
+    pub page_size: Option<(f64, f64)>,
+    // crop the shapes to the page, on screen and in exports.
+    // off, whatever sticks out (bleed) is kept
+    pub clip_to_page: bool,
+
+    // real-world units for measurements and exports,
+    // `px_per_unit` world units (px) making one of them
+    pub units: Units,
+    pub px_per_unit: f64,
+
+    // image behind the drawing to trace over
+    pub reference: Option<ReferenceImage>,
+    // file to load it from, and what went wrong the last time
+    reference_path: String,
+    reference_status: Option<String>,
 
     // list to store all the shapes the user draws:
     pub shapes: Vec<Shape>,
+    // how many shapes got a default name so far, for "Shape N"
+    shape_counter: usize,
 
     //current shape to store the currently drawing shape in:
     pub curr_shape: Shape,
@@ -58,11 +210,21 @@ struct Shaper {
 
     // which tool is currently active
     pub selected_tool: ToolKind,
+    // the tool that handled the last frame's input; when it differs from
+    // `selected_tool` it gets `Tool::on_deactivate`
+    active_tool: ToolKind,
+    // the tool to go back to when leaving a tool that was
+    // entered temporarily (e.g. Selection -> Edit on double-click)
+    pub previous_tool: Option<ToolKind>,
 
     // keep each tool in a `Box<dyn Tool>`, so they can be swapped at runtime.
     drawing_tool: Option<Box<dyn Tool>>,
     panning_tool: Option<Box<dyn Tool>>,
     editing_tool: Option<Box<dyn Tool>>,
+    selecting_tool: Option<Box<dyn Tool>>,
+    smoothing_tool: Option<Box<dyn Tool>>,
+    bucket_tool: Option<Box<dyn Tool>>,
+    knife_tool: Option<Box<dyn Tool>>,
 
     // will be probably moved to drawing tool once selection tool is
     // implemented. currently thickness is being used to change the width
@@ -71,15 +233,41 @@ struct Shaper {
     // variable to dictate the new shape thickness.
     thickness: f64,
 
-    // variable to track the index
-    // of the selected point/s
-    // should maybe be moved to
-    // the editing-tool
-    selected_p: i32,
+    // indices (into `shapes`) of the selected shapes
+    pub selected_shapes: HashSet<usize>,
+    // the selected point/s, across all shapes.
+    // must be remapped (`remap_selection`) whenever
+    // the indices in `shapes` change.
+    pub selected_points: HashSet<PointId>,
+    // anchors the Edit tool won't move, in `anchor_id` form.
+    // remapped along with `selected_points`
+    pub locked_points: HashSet<PointId>,
+
+    // export settings
+    export_path: String,
+    // leave the background out of the export instead of using `canvas_background`
+    export_transparent: bool,
+    // max deviation (world units) of the polyline exports
+    polyline_tolerance: f32,
+    // result of the last export, shown below the buttons
+    export_status: Option<String>,
+    // short message over the canvas and the `ctx` time it goes away at
+    toast: Option<(String, f64)>,
+    // the command palette while it's open, see `commands`
+    palette: Option<CommandPalette>,
+
+    // canvas size of the last frame, to keep the view centered on resize
+    prev_canvas_size: Option<Vec2>,
+
+    // world rect the navigator maps, held still while its viewport is dragged
+    navigator_drag_frame: Option<kurbo::Rect>,
 
     // settings variables
+    // handle size and arm width, screen px (the same at any zoom)
     handle_radius: f32,
     handle_arm_thicknes: f32,
+    anchor_shape: HandleShape,
+    control_shape: HandleShape,
     overlay_beziers_thickness: f32,
     p_color: Color32,
     cp_color: Color32,
@@ -100,10 +288,30 @@ impl Default for Shaper {
 
         Shaper {
             shapes: Vec::new(),
+            shape_counter: 0,
             curr_shape: Shape::new(10.0, Color32::BLACK),
             bezier_tolerance: 10.0,
             show_handles: false,
+            handles_behind_strokes: false,
+            show_point_labels: false,
+            show_stats: false,
+            flattened_points: Cell::new(0),
+            min_pick_px: 4.0,
             draw_original_stroke: false,
+            original_stroke_selected_only: false,
+            original_stroke_color: Color32::GREEN,
+            original_stroke_width: 1.0,
+            hq_strokes: false,
+            clamp_pan: false,
+            scroll_pans: false,
+            page_size: None,
+            clip_to_page: true,
+            units: Units::Px,
+            px_per_unit: 1.0,
+            reference: None,
+            reference_path: "reference.png".to_owned(),
+            reference_status: None,
+            canvas_background: Color32::WHITE,
 
             pan_offset: Vec2::ZERO,
             zoom: 1.0,
@@ -112,16 +320,36 @@ impl Default for Shaper {
             zoom_percent: zoom_percent_val,
 
             selected_tool: ToolKind::Drawing,
+            active_tool: ToolKind::Drawing,
+            previous_tool: None,
             drawing_tool: Some(Box::new(DrawingTool::new())),
             panning_tool: Some(Box::new(PanningTool::new())),
             editing_tool: Some(Box::new(EditingTool::new())),
+            selecting_tool: Some(Box::new(SelectingTool::new())),
+            smoothing_tool: Some(Box::new(SmoothingTool::new())),
+            bucket_tool: Some(Box::new(BucketTool::new())),
+            knife_tool: Some(Box::new(KnifeTool::new())),
             thickness: 10.0,
 
-            selected_p: -1, //
+            selected_shapes: HashSet::new(),
+            selected_points: HashSet::new(),
+            locked_points: HashSet::new(),
+
+            export_path: "drawing.svg".to_owned(),
+            export_transparent: false,
+            polyline_tolerance: 0.5,
+            export_status: None,
+            toast: None,
+            palette: None,
+
+            prev_canvas_size: None,
+            navigator_drag_frame: None,
 
             // sizes
             handle_radius: 2.0,
             handle_arm_thicknes: 1.0,
+            anchor_shape: HandleShape::Square,
+            control_shape: HandleShape::Circle,
             overlay_beziers_thickness: 1.0,
             // colors
             p_color: Color32::WHITE,
@@ -132,18 +360,55 @@ impl Default for Shaper {
         }
     }
 }
-fn main() {
+fn main() -> ExitCode {
+    // any arguments mean headless (cli) mode, no window
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        return cli::run(&args);
+    }
+
     let native_options = eframe::NativeOptions::default();
     let _ = eframe::run_native(
         "Shaper",
         native_options,
         Box::new(|cc| Ok(Box::new(Shaper::new(cc)))),
     );
+    ExitCode::SUCCESS
 }
 
 impl Shaper {
-    fn new(_cc: &eframe::CreationContext<'_>) -> Self {
-        Self::default()
+    fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        let mut app = Self::default();
+        // Ctrl +/-/0 zoom the canvas (see `keyboard_zoom`), not the whole ui
+        cc.egui_ctx.options_mut(|o| o.zoom_with_keyboard = false);
+        if let Some(storage) = cc.storage {
+            app.restore_session(storage);
+        }
+        app
+    }
+
+    /// pick up the tool and view from the last session (see `save`).
+    /// anything missing, unknown or out of range is left at its default.
+    fn restore_session(&mut self, storage: &dyn eframe::Storage) {
+        if let Some(tool) = storage
+            .get_string(STORAGE_TOOL)
+            .and_then(|name| ToolKind::from_storage_name(&name))
+        {
+            self.selected_tool = tool;
+        }
+
+        let view: Vec<f32> = storage
+            .get_string(STORAGE_VIEW)
+            .map(|view| view.split_whitespace().filter_map(|n| n.parse().ok()).collect())
+            .unwrap_or_default();
+        match view[..] {
+            [zoom, x, y] if zoom.is_finite() && x.is_finite() && y.is_finite() && zoom > 0.0 => {
+                self.zoom = zoom.clamp(self.min_zoom, self.max_zoom);
+                self.pan_offset = Vec2::new(x, y);
+                self.calc_zoom_level();
+            }
+            _ => {}
+        }
     }
 
     /// given a point in the drawing’s logical coordinate system,
@@ -155,6 +420,59 @@ impl Shaper {
         )
     }
 
+    /// a distance on screen (egui points, e.g. a hit slop or brush radius)
+    /// as a distance in world units at the current zoom.
+    ///
+    /// egui points are already DPI independent (egui multiplies them by
+    /// `ctx.pixels_per_point()` itself), so on HiDPI/Retina screens a grab
+    /// area of N points is already the same physical size as on a normal
+    /// screen. `pixels_per_point` must not be applied again here.
+    pub fn screen_to_world_tol(&self, screen_pts: f32) -> f64 {
+        (screen_pts / self.zoom) as f64
+    }
+
+    /// whether `shapes[shape_idx]` gets its handles drawn: all shapes with
+    /// `show_handles`, otherwise the ones being edited in the Edit tool
+    pub fn handles_shown(&self, shape_idx: usize) -> bool {
+        self.show_handles
+            || (self.selected_tool == ToolKind::Editing && self.selected_shapes.contains(&shape_idx))
+    }
+
+    /// the artboard in world space, see `page_size`
+    pub fn page_rect(&self) -> Option<kurbo::Rect> {
+        self.page_size.map(|(w, h)| kurbo::Rect::new(0.0, 0.0, w, h))
+    }
+
+    /// the artboard on screen, see `page_size`
+    fn page_screen_rect(&self) -> Option<egui::Rect> {
+        let page = self.page_rect()?;
+        Some(egui::Rect::from_two_pos(
+            self.world_to_screen(egui::pos2(page.x0 as f32, page.y0 as f32)),
+            self.world_to_screen(egui::pos2(page.x1 as f32, page.y1 as f32)),
+        ))
+    }
+
+    /// the viewBox of the SVG exports: the page, or with `clip_to_page`
+    /// off the page plus anything sticking out of it. `None` without a
+    /// page, then the exports fit the content.
+    pub fn export_region(&self) -> Option<kurbo::Rect> {
+        let page = self.page_rect()?;
+        if self.clip_to_page {
+            return Some(page);
+        }
+        Some(io::svg::content_bounds(&self.shapes).map_or(page, |content| content.union(page)))
+    }
+
+    /// keep the world point at the center of the canvas fixed when the
+    /// canvas (window) changes size, instead of pinning the top-left corner.
+    fn handle_resize(&mut self, canvas_size: Vec2) {
+        if let Some(prev) = self.prev_canvas_size.filter(|prev| *prev != canvas_size) {
+            // the center moves by half the size change
+            self.pan_offset += (canvas_size - prev) * 0.5;
+        }
+        self.prev_canvas_size = Some(canvas_size);
+    }
+
     pub fn screen_to_world(&self, p: egui::Pos2) -> egui::Pos2 {
         egui::Pos2::new(
             (p.x - self.pan_offset.x) / self.zoom,
@@ -162,8 +480,172 @@ impl Shaper {
         )
     }
 
+    /// append a shape on top of all others. an unnamed shape gets
+    /// the next free default name ("Shape 1", "Shape 2", ...).
+    /// returns its index.
+    pub fn add_shape(&mut self, mut shape: Shape) -> usize {
+        if shape.name.is_empty() {
+            self.shape_counter += 1;
+            shape.name = format!("Shape {}", self.shape_counter);
+        }
+        self.shapes.push(shape);
+        self.shapes.len() - 1
+    }
+
+    /// remove the shape at `idx`, shifting the selection
+    /// of every shape after it down by one.
+    pub fn remove_shape(&mut self, idx: usize) -> Option<Shape> {
+        if idx >= self.shapes.len() {
+            return None;
+        }
+        let removed = self.shapes.remove(idx);
+
+        let old_to_new: HashMap<usize, Option<usize>> = (idx..=self.shapes.len())
+            .map(|old| (old, if old == idx { None } else { Some(old - 1) }))
+            .collect();
+        self.remap_selection(&old_to_new);
+
+        Some(removed)
+    }
+
+    /// when `clamp_pan` is on, shift `pan_offset` so at least `PAN_CLAMP_MARGIN`
+    /// screen pixels of the content (union bbox of all shapes) stay inside
+    /// `viewport`. no-op while there is nothing drawn.
+    pub fn clamp_pan(&mut self, viewport: egui::Rect) {
+        if !self.clamp_pan {
+            return;
+        }
+        let Some(bbox) = self
+            .shapes
+            .iter()
+            .filter_map(|shape| shape.bounding_box())
+            .reduce(|a, b| a.union(b))
+        else {
+            return;
+        };
+
+        let min = self.world_to_screen(Pos2::new(bbox.x0 as f32, bbox.y0 as f32));
+        let max = self.world_to_screen(Pos2::new(bbox.x1 as f32, bbox.y1 as f32));
+        let allowed = viewport.shrink(PAN_CLAMP_MARGIN);
+
+        let axis_fix = |lo: f32, hi: f32, view_lo: f32, view_hi: f32| {
+            if hi < view_lo {
+                view_lo - hi
+            } else if lo > view_hi {
+                view_hi - lo
+            } else {
+                0.0
+            }
+        };
+        self.pan_offset.x += axis_fix(min.x, max.x, allowed.min.x, allowed.max.x);
+        self.pan_offset.y += axis_fix(min.y, max.y, allowed.min.y, allowed.max.y);
+    }
+
     // func to update the zoom_level variable internatlly
     // based on the also internally stored zoom variable.
+    /// multiply the zoom by `factor` (clamped to `min_zoom..=max_zoom`),
+    /// keeping the world point under `anchor` (screen space) where it is
+    pub fn apply_zoom(&mut self, factor: f32, anchor: egui::Pos2) {
+        let old_world_pos = self.screen_to_world(anchor);
+        self.zoom = (self.zoom * factor).clamp(self.min_zoom, self.max_zoom);
+        let new_world_pos = self.screen_to_world(anchor);
+
+        // adjust pan offset to keep the anchor stable
+        self.pan_offset += (new_world_pos - old_world_pos) * self.zoom;
+        self.calc_zoom_level();
+    }
+
+    /// the mouse wheel over the canvas, shared by all tools. by default
+    /// scrolling zooms about the pointer, shift+scroll pans sideways and
+    /// ctrl+scroll pans up and down. with `scroll_pans` plain scrolling
+    /// pans and ctrl+scroll zooms. pinch gestures always zoom.
+    pub fn handle_scroll(&mut self, ctx: &Context, response: &egui::Response) {
+        let Some(pointer_pos) = response.hover_pos() else {
+            return;
+        };
+        // egui already turns shift+scroll into horizontal scrolling, and
+        // ctrl+scroll into a zoom factor, which is turned back here
+        let egui_zoom_speed = ctx.options(|o| o.scroll_zoom_speed);
+        let (scroll, zoom, pinch, command) = ctx.input(|i| {
+            (
+                i.smooth_scroll_delta,
+                i.zoom_delta(),
+                i.multi_touch().is_some(),
+                i.modifiers.command,
+            )
+        });
+        let ctrl_scroll = if command && !pinch {
+            zoom.ln() / egui_zoom_speed
+        } else {
+            0.0
+        };
+
+        let (pan, zoom_by) = match (self.scroll_pans, command && !pinch) {
+            (true, true) => (scroll, (ctrl_scroll * SCROLL_ZOOM_SPEED).exp()),
+            (true, false) => (scroll, zoom),
+            (false, true) => (Vec2::new(scroll.x, ctrl_scroll), 1.0),
+            (false, false) => {
+                let zoom_by = zoom * (scroll.y * SCROLL_ZOOM_SPEED).exp();
+                (Vec2::new(scroll.x, 0.0), zoom_by)
+            }
+        };
+
+        if pan != Vec2::ZERO {
+            self.pan_offset += pan;
+            self.clamp_pan(response.rect);
+        }
+        if zoom_by != 1.0 {
+            self.apply_zoom(zoom_by, pointer_pos);
+        }
+    }
+
+    /// Ctrl + / Ctrl - zoom in / out about the middle of `canvas`,
+    /// Ctrl 0 goes back to 100%
+    fn keyboard_zoom(&mut self, ctx: &Context, canvas: egui::Rect) {
+        use egui::{Key, Modifiers};
+        let center = canvas.center();
+        let (zoom_in, zoom_out, reset) = ctx.input_mut(|i| {
+            (
+                // `+` is shift + `=` on most layouts
+                i.consume_key(Modifiers::COMMAND, Key::Plus)
+                    || i.consume_key(Modifiers::COMMAND, Key::Equals),
+                i.consume_key(Modifiers::COMMAND, Key::Minus),
+                i.consume_key(Modifiers::COMMAND, Key::Num0),
+            )
+        });
+        if zoom_in {
+            self.zoom_by_step(1, canvas);
+        }
+        if zoom_out {
+            self.zoom_by_step(-1, canvas);
+        }
+        if reset {
+            self.apply_zoom(1.0 / self.zoom, center);
+        }
+    }
+
+    /// zoom `steps` keyboard steps in (or out, negative) about the
+    /// middle of `canvas`
+    fn zoom_by_step(&mut self, steps: i32, canvas: egui::Rect) {
+        self.apply_zoom(KEYBOARD_ZOOM_STEP.powi(steps), canvas.center());
+    }
+
+    /// zoom and pan so the whole drawing fills `canvas` (screen space),
+    /// with a margin. no-op while there is nothing drawn.
+    fn zoom_to_fit(&mut self, canvas: egui::Rect) {
+        let Some(bounds) = io::svg::content_bounds(&self.shapes) else {
+            return;
+        };
+        let view = canvas.shrink(PAN_CLAMP_MARGIN);
+        let fit = (view.width() as f64 / bounds.width().max(1.0))
+            .min(view.height() as f64 / bounds.height().max(1.0));
+        self.zoom = (fit as f32).clamp(self.min_zoom, self.max_zoom);
+        let center = bounds.center();
+        let center = egui::pos2(center.x as f32, center.y as f32);
+        self.pan_offset = canvas.center().to_vec2() - center.to_vec2() * self.zoom;
+        self.calc_zoom_level();
+    }
+
     pub fn calc_zoom_level(&mut self) {
         // calc zoom_percent based on the default zoom
         self.zoom_percent = (self.zoom - self.min_zoom) / (self.max_zoom - self.min_zoom) * 100.0;
@@ -171,7 +653,16 @@ impl Shaper {
 }
 
 impl eframe::App for Shaper {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+    /// remember the tool and view for the next session
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(STORAGE_TOOL, self.selected_tool.storage_name().to_owned());
+        storage.set_string(
+            STORAGE_VIEW,
+            format!("{} {} {}", self.zoom, self.pan_offset.x, self.pan_offset.y),
+        );
+    }
+
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
         // set bgc/other visuals if needed
         ctx.set_visuals(Visuals {
             window_fill: Color32::WHITE,
@@ -184,53 +675,147 @@ impl eframe::App for Shaper {
                 egui::Vec2::new(ctx.available_rect().width(), canvas_height),
                 Sense::drag(),
             );
+            // before anything is drawn with `world_to_screen`, so nothing
+            // lags a frame behind the new size
+            self.handle_resize(response.rect.size());
+
+            painter.rect_filled(response.rect, 0.0, self.canvas_background);
+            let page_on_screen = self.page_screen_rect();
+            if let Some(page) = page_on_screen {
+                let shadow = page.translate(Vec2::splat(PAGE_SHADOW_OFFSET));
+                painter.rect_filled(shadow, 0.0, Color32::from_black_alpha(60));
+                painter.rect_filled(page, 0.0, Color32::WHITE);
+            }
+            if let Some(reference) = &self.reference {
+                reference.paint(&painter, self);
+            }
+
+            // the tool changed since the last frame: let the old one wrap up
+            if self.active_tool != self.selected_tool {
+                let old = self.active_tool;
+                self.active_tool = self.selected_tool;
+                if let Some(mut tool) = self.tool_slot(old).take() {
+                    tool.on_deactivate(self);
+                    *self.tool_slot(old) = Some(tool);
+                }
+            }
 
             // handle input based on selected tool
             // this requires a couple extra steps to make it work:
             // copy the enum value out of self:
             let current_tool = self.selected_tool;
-            match current_tool {
-                ToolKind::Drawing => {
-                    // 1) take() the DrawingTool out of the Option<Box<dyn Tool>>
-                    let mut tool: Box<dyn Tool + 'static> = self
-                        .drawing_tool
-                        .take()
-                        .expect("drawing_tool was None when it shouldn`t be");
+            // not while the command palette is open: it has the keyboard
+            // (Backspace in its search field mustn't delete shapes)
+            if self.palette.is_none() {
+                match current_tool {
+                    ToolKind::Drawing => {
+                        // 1) take() the DrawingTool out of the Option<Box<dyn Tool>>
+                        let mut tool: Box<dyn Tool + 'static> = self
+                            .drawing_tool
+                            .take()
+                            .expect("drawing_tool was None when it shouldn`t be");
 
-                    // 2) call handle_input, giving it mutable access to both tool and app
-                    tool.handle_input(ctx, &response, self);
+                        // 2) call handle_input, giving it mutable access to both tool and app
+                        tool.handle_input(ctx, &response, self);
 
-                    // 3) put the Box<dyn Tool> back into self
-                    self.drawing_tool = Some(tool);
-                }
-                ToolKind::Panning => {
-                    let mut tool = self
-                        .panning_tool
-                        .take()
-                        .expect("panning_tool was None when it shouldn`t be");
+                        // 3) put the Box<dyn Tool> back into self
+                        self.drawing_tool = Some(tool);
+                    }
+                    ToolKind::Panning => {
+                        let mut tool = self
+                            .panning_tool
+                            .take()
+                            .expect("panning_tool was None when it shouldn`t be");
 
-                    tool.handle_input(ctx, &response, self);
+                        tool.handle_input(ctx, &response, self);
 
-                    self.panning_tool = Some(tool);
-                }
+                        self.panning_tool = Some(tool);
+                    }
 
-                ToolKind::Editing => {
-                    let mut tool = self
-                        .editing_tool
-                        .take()
-                        .expect("editing_tool was None when it shouldn`t be");
+                    ToolKind::Editing => {
+                        let mut tool = self
+                            .editing_tool
+                            .take()
+                            .expect("editing_tool was None when it shouldn`t be");
 
-                    tool.handle_input(ctx, &response, self);
+                        tool.handle_input(ctx, &response, self);
 
-                    self.editing_tool = Some(tool);
+                        self.editing_tool = Some(tool);
+                    }
+
+                    ToolKind::Selection => {
+                        let mut tool = self
+                            .selecting_tool
+                            .take()
+                            .expect("selecting_tool was None when it shouldn`t be");
+
+                        tool.handle_input(ctx, &response, self);
+
+                        self.selecting_tool = Some(tool);
+                    }
+
+                    ToolKind::Smooth => {
+                        let mut tool = self
+                            .smoothing_tool
+                            .take()
+                            .expect("smoothing_tool was None when it shouldn`t be");
+
+                        tool.handle_input(ctx, &response, self);
+
+                        self.smoothing_tool = Some(tool);
+                    }
+
+                    ToolKind::Bucket => {
+                        let mut tool = self
+                            .bucket_tool
+                            .take()
+                            .expect("bucket_tool was None when it shouldn`t be");
+
+                        tool.handle_input(ctx, &response, self);
+
+                        self.bucket_tool = Some(tool);
+                    }
+
+                    ToolKind::Knife => {
+                        let mut tool = self
+                            .knife_tool
+                            .take()
+                            .expect("knife_tool was None when it shouldn`t be");
+
+                        tool.handle_input(ctx, &response, self);
+
+                        self.knife_tool = Some(tool);
+                    }
                 }
             }
 
+            // the drawing itself, cropped to the page if asked to
+            let content_painter = match page_on_screen.filter(|_| self.clip_to_page) {
+                Some(page) => painter.with_clip_rect(painter.clip_rect().intersect(page)),
+                None => painter.clone(),
+            };
+
+            // the handles of every shape that has them on, see `handles_shown`
+            let draw_handles = |painter: &egui::Painter| {
+                for (shape_idx, shape) in self.shapes.iter().enumerate() {
+                    if self.handles_shown(shape_idx) {
+                        shape.draw_handles(painter, self);
+                    }
+                }
+            };
+            if self.handles_behind_strokes {
+                draw_handles(&painter);
+            }
+
             // draw all finished shapes (Béziers, raw, handles) by using world_to_screen() internally —
+            self.flattened_points.set(0);
             for shape in &self.shapes {
-                shape.draw_beziers(&painter, self);
+                shape.draw_beziers(&content_painter, self);
             }
 
+            // the strokes of a compound shape drawn so far (see the drawing tool)
+            self.curr_shape.draw_beziers(&content_painter, self);
+
             // draw in-progress stroke
             // using this method:
             // https://github.com/emilk/egui/blob/main/crates/egui_demo_lib/src/demo/painting.rs
@@ -244,29 +829,38 @@ impl eframe::App for Shaper {
                     .collect();
 
                 // then push one Shape::line with all of them:
-                painter.add(egui::Shape::line(
+                content_painter.add(egui::Shape::line(
                     pts,
                     egui::Stroke::new(
-                        self.curr_shape.thickness * self.zoom,
+                        (self.curr_shape.thickness * self.zoom)
+                            .max(shape::MIN_SCREEN_STROKE_WIDTH),
                         self.curr_shape.stroke_color,
                     ),
                 ));
             }
 
-            // optionally draw raw strokes in green:
+            // optionally draw the raw strokes over the fit:
             if self.draw_original_stroke {
-                for shape in &self.shapes {
+                for (shape_idx, shape) in self.shapes.iter().enumerate() {
+                    if shape.raw_strokes.is_empty()
+                        || (self.original_stroke_selected_only
+                            && !self.selected_shapes.contains(&shape_idx))
+                    {
+                        continue;
+                    }
                     shape.draw_raw(&painter, self);
                 }
             }
             // optionally draw handles in panning/drawing interactive mode:
-            if self.show_handles {
-                for shape in &self.shapes {
-                    // draw the overlay beziers first
+            // the overlay beziers first (on the stroke, wherever the handles go)
+            for (shape_idx, shape) in self.shapes.iter().enumerate() {
+                if self.handles_shown(shape_idx) {
                     shape.draw_overlay_beziers(&painter, self);
-                    shape.draw_handles(&painter, self);
                 }
             }
+            if !self.handles_behind_strokes {
+                draw_handles(&painter);
+            }
 
             // — let the active tool paint any overlays (e.g. pan‐mode highlight) —
             match current_tool {
@@ -295,13 +889,64 @@ impl eframe::App for Shaper {
                     tool.paint(ctx, &painter, self);
                     self.editing_tool = Some(tool);
                 }
+
+                ToolKind::Selection => {
+                    let mut tool = self
+                        .selecting_tool
+                        .take()
+                        .expect("selecting_tool was None when it shouldn`t be");
+                    tool.paint(ctx, &painter, self);
+                    self.selecting_tool = Some(tool);
+                }
+
+                ToolKind::Smooth => {
+                    let mut tool = self
+                        .smoothing_tool
+                        .take()
+                        .expect("smoothing_tool was None when it shouldn`t be");
+                    tool.paint(ctx, &painter, self);
+                    self.smoothing_tool = Some(tool);
+                }
+
+                ToolKind::Bucket => {
+                    let mut tool = self
+                        .bucket_tool
+                        .take()
+                        .expect("bucket_tool was None when it shouldn`t be");
+                    tool.paint(ctx, &painter, self);
+                    self.bucket_tool = Some(tool);
+                }
+
+                ToolKind::Knife => {
+                    let mut tool = self
+                        .knife_tool
+                        .take()
+                        .expect("knife_tool was None when it shouldn`t be");
+                    tool.paint(ctx, &painter, self);
+                    self.knife_tool = Some(tool);
+                }
+            }
+
+            if self.show_stats {
+                self.draw_stats(&painter, response.rect, frame.info().cpu_usage);
             }
 
             // draw the settings & tool‐selector windows (always at fixed screen coords)
 
+            // H toggles the handles of all shapes (not while typing)
+            if !ctx.wants_keyboard_input() && ctx.input(|i| i.key_pressed(egui::Key::H)) {
+                self.show_handles = !self.show_handles;
+            }
+            if !ctx.wants_keyboard_input() {
+                self.keyboard_zoom(ctx, response.rect);
+            }
+
             self.show_settings_window(ctx);
             self.show_tools_window(ctx);
+            self.show_navigator_window(ctx, response.rect);
+            self.show_toast_window(ctx);
             self.show_tool_specific_ui(ctx);
+            self.show_command_palette(ctx, response.rect);
         });
     }
 }
@@ -314,9 +959,251 @@ impl Shaper {
             .collapsible(false)
             .title_bar(false)
             .show(ctx, |ui| {
-                ui.checkbox(&mut self.show_handles, "Show handles");
+                ui.checkbox(&mut self.show_handles, "Show handles (H)");
+                ui.checkbox(&mut self.handles_behind_strokes, "Handles behind strokes");
+                self.handle_style_ui(ui);
                 ui.checkbox(&mut self.draw_original_stroke, "Draw original stroke");
+                ui.add_enabled(
+                    self.draw_original_stroke,
+                    egui::Checkbox::new(
+                        &mut self.original_stroke_selected_only,
+                        "Original stroke: selected only",
+                    ),
+                );
+                ui.add_enabled_ui(self.draw_original_stroke, |ui| {
+                    ui.horizontal(|ui| {
+                        egui::widgets::color_picker::color_edit_button_srgba(
+                            ui,
+                            &mut self.original_stroke_color,
+                            egui::color_picker::Alpha::OnlyBlend,
+                        );
+                        ui.add(
+                            egui::DragValue::new(&mut self.original_stroke_width)
+                                .range(0.5..=10.0)
+                                .speed(0.1)
+                                .prefix("Original stroke: ")
+                                .suffix(" px"),
+                        );
+                    });
+                });
+                ui.checkbox(&mut self.hq_strokes, "High quality strokes");
+                ui.checkbox(&mut self.show_point_labels, "Show point indices (debug)");
+                ui.checkbox(&mut self.show_stats, "Show stats (debug)");
+                ui.add(
+                    egui::DragValue::new(&mut self.min_pick_px)
+                        .range(0.0..=30.0)
+                        .prefix("Min pick distance: ")
+                        .suffix(" px"),
+                );
+
+                ui.horizontal(|ui| {
+                    egui::widgets::color_picker::color_edit_button_srgba(
+                        ui,
+                        &mut self.canvas_background,
+                        egui::color_picker::Alpha::Opaque,
+                    );
+                    ui.label("Background");
+                });
+
+                self.units_ui(ui);
+                self.page_ui(ui);
+                ui.collapsing("Reference image", |ui| self.reference_ui(ui));
+                ui.collapsing("Export", |ui| self.export_ui(ui));
+                ui.checkbox(&mut self.clamp_pan, "Keep drawing in view when panning");
+                ui.horizontal(|ui| {
+                    ui.label("Mouse wheel:");
+                    ui.radio_value(&mut self.scroll_pans, false, "zooms")
+                        .on_hover_text("shift pans sideways, ctrl up and down");
+                    ui.radio_value(&mut self.scroll_pans, true, "pans")
+                        .on_hover_text("shift pans sideways, ctrl zooms");
+                });
+            });
+    }
+
+    /// shape / segment counts, the points drawn and the time the last
+    /// frame took (`cpu_usage`, seconds), in the bottom left of `canvas`.
+    /// tells when a document is getting heavy
+    fn draw_stats(&self, painter: &egui::Painter, canvas: egui::Rect, cpu_usage: Option<f32>) {
+        let segments: usize = self.shapes.iter().map(|shape| shape.beziers.len()).sum();
+        let frame_time = match cpu_usage {
+            Some(secs) => format!("{:.1} ms", secs * 1000.0),
+            None => "-".to_string(),
+        };
+        let text = format!(
+            "shapes: {}\nsegments: {segments}\nflattened points: {}\nframe: {frame_time}",
+            self.shapes.len(),
+            self.flattened_points.get(),
+        );
+        painter.text(
+            canvas.left_bottom() + Vec2::new(8.0, -8.0),
+            egui::Align2::LEFT_BOTTOM,
+            text,
+            egui::FontId::monospace(12.0),
+            Color32::from_black_alpha(180),
+        );
+    }
+
+    fn handle_style_ui(&mut self, ui: &mut egui::Ui) {
+        for (label, shape) in [
+            ("Anchors:", &mut self.anchor_shape),
+            ("Control points:", &mut self.control_shape),
+        ] {
+            ui.horizontal(|ui| {
+                ui.label(label);
+                for option in [HandleShape::Square, HandleShape::Circle] {
+                    ui.radio_value(shape, option, option.label());
+                }
+            });
+        }
+        ui.add(
+            egui::DragValue::new(&mut self.handle_radius)
+                .range(1.0..=10.0)
+                .speed(0.1)
+                .prefix("Handle size: ")
+                .suffix(" px"),
+        );
+    }
+
+    fn page_ui(&mut self, ui: &mut egui::Ui) {
+        let current = match self.page_size {
+            None => "None",
+            Some(size) => PAGE_PRESETS
+                .iter()
+                .find(|(_, preset)| *preset == size)
+                .map_or("Custom", |(name, _)| *name),
+        };
+        ui.horizontal(|ui| {
+            ui.label("Page:");
+            egui::ComboBox::from_id_salt("page size")
+                .selected_text(current)
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.page_size, None, "None");
+                    for (name, size) in PAGE_PRESETS {
+                        ui.selectable_value(&mut self.page_size, Some(size), name);
+                    }
+                });
+        });
+        // any other size is a custom one
+        if let Some((w, h)) = &mut self.page_size {
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(w).range(1.0..=100_000.0).prefix("w: "));
+                ui.add(egui::DragValue::new(h).range(1.0..=100_000.0).prefix("h: "));
             });
+            // the page in real-world units too
+            if let Some((w, h)) = self.page_size.filter(|_| self.units != Units::Px) {
+                ui.label(format!("{} × {}", self.format_length(w), self.format_length(h)));
+            }
+            ui.checkbox(&mut self.clip_to_page, "Clip to page");
+        }
+    }
+
+    fn export_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("File:");
+            ui.text_edit_singleline(&mut self.export_path);
+        });
+
+        let mut export: Option<String> = None;
+        ui.horizontal(|ui| {
+            if ui.button("Export SVG").clicked() {
+                export = Some(self.svg_export());
+            }
+            if ui.button("Export DXF").clicked() {
+                export = Some(self.export_dxf());
+            }
+            if ui.button("Import SVG").clicked() {
+                self.import_svg_file(ui.ctx());
+            }
+        });
+        ui.checkbox(&mut self.export_transparent, "Transparent background");
+
+        // curves baked into straight lines, for plotters / laser cutters
+        ui.add(
+            egui::Slider::new(&mut self.polyline_tolerance, 0.01..=10.0)
+                .logarithmic(true)
+                .text("Polyline tolerance"),
+        );
+        ui.horizontal(|ui| {
+            if ui.button("Export polyline SVG").clicked() {
+                export = Some(self.export_polyline_svg(self.polyline_tolerance));
+            }
+            if ui.button("Export polyline JSON").clicked() {
+                export = Some(self.export_polyline_json(self.polyline_tolerance));
+            }
+        });
+
+        if let Some(contents) = export {
+            self.write_export(contents);
+        }
+        if let Some(status) = &self.export_status {
+            ui.label(status);
+        }
+    }
+
+    /// the drawing as SVG with the current export settings
+    fn svg_export(&self) -> String {
+        let background = (!self.export_transparent).then_some(self.canvas_background);
+        io::svg::export_svg(
+            &self.shapes,
+            background,
+            self.export_region(),
+            self.units,
+            self.px_per_unit,
+        )
+    }
+
+    /// save an export to `export_path`, noting how it went in `export_status`
+    fn write_export(&mut self, contents: String) {
+        self.export_status = Some(match std::fs::write(&self.export_path, contents) {
+            Ok(()) => format!("saved {}", self.export_path),
+            Err(e) => format!("error: {e}"),
+        });
+    }
+
+    /// add the paths of the SVG at `export_path` as new shapes. what
+    /// can't be read is reported in a toast, the paths before it are kept
+    fn import_svg_file(&mut self, ctx: &Context) {
+        let src = match std::fs::read_to_string(&self.export_path) {
+            Ok(src) => src,
+            Err(e) => {
+                self.export_status = Some(format!("error: {e}"));
+                return;
+            }
+        };
+        let mut shapes = Vec::new();
+        let result = io::svg::import_svg(&src, &mut shapes);
+        let count = shapes.len();
+        for shape in shapes {
+            self.add_shape(shape);
+        }
+        self.export_status = Some(format!("imported {count} shapes from {}", self.export_path));
+        if let Err(e) = result {
+            self.show_toast(ctx, format!("{}: {e}", self.export_path));
+        }
+    }
+
+    /// put `message` up over the canvas for `TOAST_SECS`
+    fn show_toast(&mut self, ctx: &Context, message: String) {
+        self.toast = Some((message, ctx.input(|i| i.time) + TOAST_SECS));
+    }
+
+    fn show_toast_window(&mut self, ctx: &Context) {
+        let Some((message, until)) = &self.toast else {
+            return;
+        };
+        let now = ctx.input(|i| i.time);
+        if now >= *until {
+            self.toast = None;
+            return;
+        }
+        egui::Area::new(egui::Id::new("toast"))
+            .anchor(egui::Align2::CENTER_TOP, egui::Vec2::new(0.0, 10.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.colored_label(ui.visuals().error_fg_color, message);
+                });
+            });
+        ctx.request_repaint_after_secs((*until - now) as f32);
     }
 
     // tools window
@@ -330,17 +1217,50 @@ impl Shaper {
                 ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
                     if ui.button("Draw").clicked() {
                         self.selected_tool = ToolKind::Drawing;
+                        self.previous_tool = None;
                     }
                     if ui.button("Pan-Zoom").clicked() {
                         self.selected_tool = ToolKind::Panning;
+                        self.previous_tool = None;
+                    }
+                    if ui.button("Select").clicked() {
+                        self.selected_tool = ToolKind::Selection;
+                        self.previous_tool = None;
                     }
                     if ui.button("Edit").clicked() {
                         self.selected_tool = ToolKind::Editing;
+                        self.previous_tool = None;
+                    }
+                    if ui.button("Knife").clicked() {
+                        self.selected_tool = ToolKind::Knife;
+                        self.previous_tool = None;
+                    }
+                    if ui.button("Bucket").clicked() {
+                        self.selected_tool = ToolKind::Bucket;
+                        self.previous_tool = None;
+                    }
+                    if ui.button("Smooth").clicked() {
+                        self.selected_tool = ToolKind::Smooth;
+                        self.previous_tool = None;
                     }
                 });
             });
     }
 
+    /// where the tool `kind` is kept (it's `None` while taken out
+    /// to be called, see `update`)
+    fn tool_slot(&mut self, kind: ToolKind) -> &mut Option<Box<dyn Tool>> {
+        match kind {
+            ToolKind::Drawing => &mut self.drawing_tool,
+            ToolKind::Panning => &mut self.panning_tool,
+            ToolKind::Editing => &mut self.editing_tool,
+            ToolKind::Selection => &mut self.selecting_tool,
+            ToolKind::Smooth => &mut self.smoothing_tool,
+            ToolKind::Bucket => &mut self.bucket_tool,
+            ToolKind::Knife => &mut self.knife_tool,
+        }
+    }
+
     /// Displays the UI for the currently selected tool.
     ///
     /// This method handles a common Rust borrowing pattern: to avoid
@@ -369,6 +1289,26 @@ impl Shaper {
                 tool.tool_ui(ctx, self);
                 self.editing_tool = Some(tool);
             }
+            ToolKind::Selection => {
+                let mut tool = self.selecting_tool.take().expect("selecting_tool was None");
+                tool.tool_ui(ctx, self);
+                self.selecting_tool = Some(tool);
+            }
+            ToolKind::Smooth => {
+                let mut tool = self.smoothing_tool.take().expect("smoothing_tool was None");
+                tool.tool_ui(ctx, self);
+                self.smoothing_tool = Some(tool);
+            }
+            ToolKind::Bucket => {
+                let mut tool = self.bucket_tool.take().expect("bucket_tool was None");
+                tool.tool_ui(ctx, self);
+                self.bucket_tool = Some(tool);
+            }
+            ToolKind::Knife => {
+                let mut tool = self.knife_tool.take().expect("knife_tool was None");
+                tool.tool_ui(ctx, self);
+                self.knife_tool = Some(tool);
+            }
         }
     }
 }
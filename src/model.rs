@@ -0,0 +1,764 @@
+// pure geometry on kurbo types. nothing in here knows about egui/eframe
+// (no Pos2, no Color32, no Painter), so it can be used without a window,
+// e.g. for batch processing. `Shape` and `Shaper` delegate to these.
+//
+// these are functions over segment chains only. the document itself (the
+// shapes list, hit testing in hit_test.rs, anchor insert/delete in
+// shape_ops.rs) still lives on `Shaper`, there's no egui-free `Document`:
+// `Shape` keeps its raw strokes as `Pos2` and its colors as `Color32`.
+
+use kurbo::{
+    BezPath, CubicBez, Line, Nearest, ParamCurve, ParamCurveArclen, ParamCurveDeriv,
+    ParamCurveExtrema, ParamCurveNearest, PathSeg, Point, Rect, Vec2,
+};
+use simplify_rs::{Point as SrPoint, simplify};
+
+/// fit a chain of cubic Béziers through the raw points with `simplify-rs`.
+/// `tol` is the maximum allowed deviation (world units).
+/// returns an empty chain for fewer than 2 points. the first p0 and the
+/// last p3 are always exactly the first and last raw point.
+/// segments within `straight_tol` radians of straight become exact lines,
+/// see `straighten_near_lines`; the anchors it returns come along.
+pub fn fit_curve(raw: &[Point], tol: f64, straight_tol: f64) -> (Vec<CubicBez>, Vec<usize>) {
+    // Convert kurbo::Point → simplify_rs::Point (which is { x: f64, y: f64 })
+    let sr_points: Vec<SrPoint> = raw.iter().map(|p| SrPoint { x: p.x, y: p.y }).collect();
+
+    if sr_points.len() < 2 {
+        return (Vec::new(), Vec::new()); // nothing to fit
+    }
+
+    // → flat Vec<SrPoint>: every 4 points [P0,P1,P2,P3] are one cubic
+    let flat: Vec<SrPoint> = simplify(&sr_points, tol);
+
+    let mut beziers: Vec<CubicBez> = flat
+        .chunks_exact(4)
+        .map(|chunk| CubicBez {
+            p0: Point::new(chunk[0].x, chunk[0].y),
+            p1: Point::new(chunk[1].x, chunk[1].y),
+            p2: Point::new(chunk[2].x, chunk[2].y),
+            p3: Point::new(chunk[3].x, chunk[3].y),
+        })
+        .collect();
+
+    // the curve has to start and end exactly where the stroke did, whatever
+    // the fit did to them. the neighbouring handle moves along, so the
+    // tangent at the end stays the same
+    if let Some(first) = beziers.first_mut() {
+        let drift = raw[0] - first.p0;
+        first.p0 += drift;
+        first.p1 += drift;
+    }
+    if let Some(last) = beziers.last_mut() {
+        let drift = raw[raw.len() - 1] - last.p3;
+        last.p3 += drift;
+        last.p2 += drift;
+    }
+
+    let corners = straighten_near_lines(&mut beziers, straight_tol);
+    (beziers, corners)
+}
+
+/// how far (radians) a handle may point off its segment's chord for
+/// `fit_curve` to still take the segment as straight, unless told otherwise
+pub const STRAIGHT_ANGLE_TOL: f64 = 3.0 * std::f64::consts::PI / 180.0;
+
+/// whether both handles of `bez` point along its chord, within `max_angle`
+/// radians. a handle of (next to) zero length has no direction, so it can't
+/// be off; a segment of (next to) zero length counts as straight too.
+pub fn is_nearly_straight(bez: &CubicBez, max_angle: f64) -> bool {
+    let chord = bez.p3 - bez.p0;
+    if chord.hypot() < 1e-9 {
+        return true;
+    }
+    let along = |handle: Vec2| handle.hypot() < 1e-9 || angle_between(handle, chord) <= max_angle;
+    along(bez.p1 - bez.p0) && along(bez.p3 - bez.p2)
+}
+
+/// the angle (radians, 0..=PI) between two non-zero vectors
+fn angle_between(a: Vec2, b: Vec2) -> f64 {
+    let cos = a.dot(b) / (a.hypot() * b.hypot());
+    cos.clamp(-1.0, 1.0).acos()
+}
+
+/// replace every segment whose handles both point along its chord (within
+/// `max_angle` radians) by an exact `straight_cubic`. the fit leaves such
+/// segments slightly bent, with handles that are a pain to edit; snapped,
+/// where two of them meet at an angle the anchor is a clean corner.
+/// returns those corners: the inner anchors next to a straightened segment,
+/// as the index of the segment starting there (ascending).
+pub fn straighten_near_lines(beziers: &mut [CubicBez], max_angle: f64) -> Vec<usize> {
+    let mut straightened = vec![false; beziers.len()];
+    for (bez, straight) in beziers.iter_mut().zip(&mut straightened) {
+        let chord = bez.p3 - bez.p0;
+        if chord.hypot() < 1e-9 {
+            continue;
+        }
+        if is_nearly_straight(bez, max_angle) {
+            *bez = straight_cubic(bez.p0, bez.p3);
+            *straight = true;
+        }
+    }
+    (1..beziers.len())
+        .filter(|&k| straightened[k - 1] || straightened[k])
+        .collect()
+}
+
+/// the chain without the anchors that sit in the middle of a straight run:
+/// both segments around the anchor nearly straight and their chords within
+/// `max_angle` radians of each other. the two become one `straight_cubic`.
+/// the segments are merged one after the other, so a long run of short
+/// pieces ends up as a single segment. `keep(k)` (anchor `k` being the p0
+/// of segment `k`) protects an anchor from being removed. the ends of an
+/// open chain always stay, in a closed one anchor 0 can go too.
+pub fn remove_collinear_anchors(
+    beziers: &[CubicBez],
+    closed: bool,
+    max_angle: f64,
+    keep: impl Fn(usize) -> bool,
+) -> Vec<CubicBez> {
+    let mergeable = |a: &CubicBez, b: &CubicBez| {
+        let (chord_a, chord_b) = (a.p3 - a.p0, b.p3 - b.p0);
+        is_nearly_straight(a, max_angle)
+            && is_nearly_straight(b, max_angle)
+            && (chord_a.hypot() < 1e-9
+                || chord_b.hypot() < 1e-9
+                || angle_between(chord_a, chord_b) <= max_angle)
+    };
+
+    let mut out: Vec<CubicBez> = Vec::with_capacity(beziers.len());
+    for (k, bez) in beziers.iter().enumerate() {
+        match out.last_mut() {
+            Some(last) if !keep(k) && mergeable(last, bez) => {
+                *last = straight_cubic(last.p0, bez.p3);
+            }
+            _ => out.push(*bez),
+        }
+    }
+    // the anchor where a closed chain wraps around
+    if closed && out.len() > 2 && !keep(0) && mergeable(&out[out.len() - 1], &out[0]) {
+        let last = out.pop().unwrap();
+        out[0] = straight_cubic(last.p0, out[0].p3);
+    }
+    out
+}
+
+/// fit exactly `n` cubic segments through the raw points, e.g. so two
+/// strokes get the same number of segments (for interpolating between them).
+///
+/// the polyline is cut into `n` pieces of equal length (chord length); each
+/// piece becomes one cubic through its two end points, with the handles
+/// placed by a least-squares fit over the piece's points (parameterized by
+/// chord length). neighbouring segments share their end point, but the
+/// handles aren't forced to be tangent-continuous.
+pub fn fit_segment_count(raw: &[Point], n: usize) -> Vec<CubicBez> {
+    if raw.len() < 2 || n == 0 {
+        return Vec::new();
+    }
+
+    let total: f64 = raw.windows(2).map(|w| w[0].distance(w[1])).sum();
+    if total <= 0.0 {
+        return Vec::new();
+    }
+
+    // cut the polyline at every `total / n` of length,
+    // inserting a point exactly at each cut
+    let piece_len = total / n as f64;
+    let mut pieces: Vec<Vec<Point>> = Vec::with_capacity(n);
+    let mut current = vec![raw[0]];
+    let mut walked = 0.0;
+    for w in raw.windows(2) {
+        let seg_len = w[0].distance(w[1]);
+        let seg_start = walked;
+        walked += seg_len;
+        while pieces.len() + 1 < n && walked >= piece_len * (pieces.len() + 1) as f64 {
+            let cut_at = piece_len * (pieces.len() + 1) as f64;
+            let t = if seg_len > 0.0 { (cut_at - seg_start) / seg_len } else { 0.0 };
+            let cut = w[0].lerp(w[1], t);
+            current.push(cut);
+            pieces.push(std::mem::replace(&mut current, vec![cut]));
+        }
+        current.push(w[1]);
+    }
+    pieces.push(current);
+
+    pieces.iter().map(|piece| fit_single_cubic(piece)).collect()
+}
+
+/// least-squares cubic through `points` with p0/p3 pinned to the first/last point
+fn fit_single_cubic(points: &[Point]) -> CubicBez {
+    let p0 = points[0];
+    let p3 = points[points.len() - 1];
+
+    // chord length parameterization
+    let mut ts = Vec::with_capacity(points.len());
+    let mut acc = 0.0;
+    ts.push(0.0);
+    for w in points.windows(2) {
+        acc += w[0].distance(w[1]);
+        ts.push(acc);
+    }
+    if acc <= 0.0 {
+        return straight_cubic(p0, p3);
+    }
+
+    // normal equations for p1/p2: [c11 c12; c12 c22] [p1; p2] = [x1; x2]
+    let (mut c11, mut c12, mut c22) = (0.0, 0.0, 0.0);
+    let (mut x1, mut x2) = (Vec2::ZERO, Vec2::ZERO);
+    for (p, t) in points.iter().zip(&ts) {
+        let t = t / acc;
+        let mt = 1.0 - t;
+        let (b0, b1, b2, b3) = (mt * mt * mt, 3.0 * mt * mt * t, 3.0 * mt * t * t, t * t * t);
+        c11 += b1 * b1;
+        c12 += b1 * b2;
+        c22 += b2 * b2;
+        let rest = p.to_vec2() - p0.to_vec2() * b0 - p3.to_vec2() * b3;
+        x1 += rest * b1;
+        x2 += rest * b2;
+    }
+
+    let det = c11 * c22 - c12 * c12;
+    if det.abs() < 1e-12 {
+        // too few points to say anything about the handles
+        return straight_cubic(p0, p3);
+    }
+    let p1 = ((x1 * c22 - x2 * c12) / det).to_point();
+    let p2 = ((x2 * c11 - x1 * c12) / det).to_point();
+    CubicBez { p0, p1, p2, p3 }
+}
+
+/// a cubic that is visually a straight line from `p0` to `p3`,
+/// with its handles on the chord at 1/3 and 2/3.
+pub fn straight_cubic(p0: Point, p3: Point) -> CubicBez {
+    CubicBez {
+        p0,
+        p1: p0.lerp(p3, 1.0 / 3.0),
+        p2: p0.lerp(p3, 2.0 / 3.0),
+        p3,
+    }
+}
+
+/// the four sides of `rect` as straight cubics, clockwise on screen
+/// (y down) starting at the top left corner. meant to be drawn closed.
+pub fn rect_cubics(rect: Rect) -> Vec<CubicBez> {
+    let corners = [
+        Point::new(rect.x0, rect.y0),
+        Point::new(rect.x1, rect.y0),
+        Point::new(rect.x1, rect.y1),
+        Point::new(rect.x0, rect.y1),
+    ];
+    (0..4)
+        .map(|i| straight_cubic(corners[i], corners[(i + 1) % 4]))
+        .collect()
+}
+
+/// axis aligned ellipse as four cubics (one per quadrant), starting at the
+/// rightmost point. uses the usual kappa handle length, which is off the
+/// true ellipse by less than 0.03% of the radius. meant to be drawn closed.
+pub fn ellipse_cubics(center: Point, rx: f64, ry: f64) -> Vec<CubicBez> {
+    const KAPPA: f64 = 0.552_284_749_831;
+    let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+    let (cx, cy) = (center.x, center.y);
+    let right = Point::new(cx + rx, cy);
+    let bottom = Point::new(cx, cy + ry);
+    let left = Point::new(cx - rx, cy);
+    let top = Point::new(cx, cy - ry);
+    vec![
+        CubicBez::new(right, Point::new(cx + rx, cy + ky), Point::new(cx + kx, cy + ry), bottom),
+        CubicBez::new(bottom, Point::new(cx - kx, cy + ry), Point::new(cx - rx, cy + ky), left),
+        CubicBez::new(left, Point::new(cx - rx, cy - ky), Point::new(cx - kx, cy - ry), top),
+        CubicBez::new(top, Point::new(cx + kx, cy - ry), Point::new(cx + rx, cy - ky), right),
+    ]
+}
+
+/// union of the bounding boxes of all segments, `None` for an empty chain
+pub fn bounding_box(beziers: &[CubicBez]) -> Option<Rect> {
+    beziers
+        .iter()
+        .map(|bez| bez.bounding_box())
+        .reduce(|a, b| a.union(b))
+}
+
+/// the chain as one kurbo path
+pub fn to_bez_path(beziers: &[CubicBez], closed: bool) -> BezPath {
+    let mut path = BezPath::new();
+    if let Some(first) = beziers.first() {
+        path.move_to(first.p0);
+        for bez in beziers {
+            path.curve_to(bez.p1, bez.p2, bez.p3);
+        }
+        if closed {
+            path.close_path();
+        }
+    }
+    path
+}
+
+/// accuracy (world units) for the arc length computations
+const ARCLEN_ACCURACY: f64 = 1e-3;
+
+/// points along the chain spaced `spacing` apart by arc length (world units),
+/// starting at the first p0. the spacing carries over segment boundaries,
+/// so the whole chain is treated as one curve. the last point is only
+/// included if it falls on a multiple of `spacing`.
+#[allow(dead_code)] // nothing samples outlines evenly yet (dashes, roughen)
+pub fn sample_even(beziers: &[CubicBez], spacing: f64) -> Vec<Point> {
+    let mut points = Vec::new();
+    if spacing <= 0.0 {
+        return points;
+    }
+
+    // distance into the current segment where the next sample goes
+    let mut next = 0.0;
+    for bez in beziers {
+        let len = bez.arclen(ARCLEN_ACCURACY);
+        while next <= len {
+            let t = bez.inv_arclen(next, ARCLEN_ACCURACY);
+            points.push(bez.eval(t));
+            next += spacing;
+        }
+        next -= len;
+    }
+    points
+}
+
+/// the point of `bez` nearest to `p`, like kurbo's `nearest`.
+///
+/// kurbo's solver goes wrong on cubics that are (close to) a straight line
+/// with evenly spaced handles, like every `straight_cubic`: it can report a
+/// point far off the line. so the spot on the curve at `p`'s projection
+/// onto the chord is tried as well, and the closer of the two wins.
+pub fn nearest(bez: &CubicBez, p: Point) -> Nearest {
+    let found = bez.nearest(p, 1e-6);
+    let t = Line::new(bez.p0, bez.p3).nearest(p, 1e-6).t;
+    let distance_sq = bez.eval(t).distance_squared(p);
+    if distance_sq < found.distance_sq {
+        Nearest { distance_sq, t }
+    } else {
+        found
+    }
+}
+
+/// total arc length of the chain (world units)
+pub fn chain_length(beziers: &[CubicBez]) -> f64 {
+    beziers.iter().map(|bez| bez.arclen(ARCLEN_ACCURACY)).sum()
+}
+
+/// unit tangent of `bez` at `t`. where the derivative vanishes (a handle
+/// sitting on its anchor) the direction to the next distinct point of the
+/// control polygon is used instead. `(1, 0)` for a segment that is a point.
+pub fn bezier_tangent(bez: &CubicBez, t: f64) -> Vec2 {
+    let d = bez.deriv().eval(t).to_vec2();
+    if d.hypot() > 1e-9 {
+        return d.normalize();
+    }
+    let fallbacks = if t < 0.5 {
+        [bez.p1 - bez.p0, bez.p2 - bez.p0, bez.p3 - bez.p0]
+    } else {
+        [bez.p3 - bez.p2, bez.p3 - bez.p1, bez.p3 - bez.p0]
+    };
+    fallbacks
+        .into_iter()
+        .find(|v| v.hypot() > 1e-9)
+        .map_or(Vec2::new(1.0, 0.0), Vec2::normalize)
+}
+
+/// position and unit tangent at each of `distances` (world units, sorted
+/// ascending) along the chain by arc length. distances past the end of
+/// the chain are left out.
+pub fn frames_at(beziers: &[CubicBez], distances: &[f64]) -> Vec<(Point, Vec2)> {
+    let mut frames = Vec::with_capacity(distances.len());
+    let mut segments = beziers.iter().map(|bez| (bez, bez.arclen(ARCLEN_ACCURACY))).peekable();
+    // arc length up to the start of the current segment
+    let mut offset = 0.0;
+    for &dist in distances {
+        while let Some(&(_, len)) = segments.peek() {
+            // the last segment takes everything up to its (rounded) end
+            let last = segments.len() == 1;
+            if dist <= offset + len || (last && dist <= offset + len + ARCLEN_ACCURACY) {
+                break;
+            }
+            offset += len;
+            segments.next();
+        }
+        let Some(&(bez, len)) = segments.peek() else {
+            break;
+        };
+        let t = bez.inv_arclen((dist - offset).clamp(0.0, len), ARCLEN_ACCURACY);
+        frames.push((bez.eval(t), bezier_tangent(bez, t)));
+    }
+    frames
+}
+
+/// the chain as a polyline that stays within `tol` (world units) of the
+/// curves. for a closed chain the last point repeats the first.
+pub fn flatten(beziers: &[CubicBez], closed: bool, tol: f64) -> Vec<Point> {
+    let mut points = Vec::new();
+    kurbo::flatten(to_bez_path(beziers, closed), tol, |el| match el {
+        kurbo::PathEl::MoveTo(p) | kurbo::PathEl::LineTo(p) => points.push(p),
+        _ => {}
+    });
+    points
+}
+
+/// where the straight `line` crosses the chain, as `(segment index, t)`
+/// pairs sorted along the chain
+pub fn line_crossings(beziers: &[CubicBez], line: Line) -> Vec<(usize, f64)> {
+    let mut cuts = Vec::new();
+    for (i, bez) in beziers.iter().enumerate() {
+        let mut ts: Vec<f64> = PathSeg::Cubic(*bez)
+            .intersect_line(line)
+            .iter()
+            .map(|hit| hit.segment_t)
+            .collect();
+        ts.sort_by(|a, b| a.total_cmp(b));
+        cuts.extend(ts.into_iter().map(|t| (i, t)));
+    }
+    cuts
+}
+
+/// break the chain apart at `cuts` (`(segment index, t)`, sorted along the
+/// chain, see `line_crossings`). segments are split with `subsegment`, so
+/// the curves keep their exact shape. a closed chain is opened up: its
+/// last and first pieces are joined, as they were connected through the
+/// closing point. without cuts the chain comes back as is.
+pub fn cut_chain(beziers: &[CubicBez], closed: bool, cuts: &[(usize, f64)]) -> Vec<Vec<CubicBez>> {
+    // pieces shorter than this (in t) are dropped, e.g. when
+    // a cut lands exactly on an anchor
+    const T_EPS: f64 = 1e-6;
+
+    if cuts.is_empty() {
+        return vec![beziers.to_vec()];
+    }
+
+    let mut pieces: Vec<Vec<CubicBez>> = Vec::new();
+    let mut current: Vec<CubicBez> = Vec::new();
+    for (i, bez) in beziers.iter().enumerate() {
+        let mut t0 = 0.0;
+        for &(_, t) in cuts.iter().filter(|(idx, _)| *idx == i) {
+            if t - t0 > T_EPS {
+                current.push(bez.subsegment(t0..t));
+            }
+            pieces.push(std::mem::take(&mut current));
+            t0 = t;
+        }
+        if 1.0 - t0 > T_EPS {
+            current.push(bez.subsegment(t0..1.0));
+        }
+    }
+    pieces.push(current);
+
+    if closed {
+        // the tail runs through the closing point into the head
+        let tail = pieces.pop().unwrap_or_default();
+        let head = std::mem::take(&mut pieces[0]);
+        pieces[0] = tail.into_iter().chain(head).collect();
+    }
+
+    pieces.retain(|piece| !piece.is_empty());
+    pieces
+}
+
+/// the in-between of two chains at `t` (0 = `a`, 1 = `b`), every control
+/// point interpolated linearly. `None` unless both have the same segment count.
+pub fn lerp_chains(a: &[CubicBez], b: &[CubicBez], t: f64) -> Option<Vec<CubicBez>> {
+    if a.len() != b.len() {
+        return None;
+    }
+    Some(
+        a.iter()
+            .zip(b)
+            .map(|(a, b)| CubicBez {
+                p0: a.p0.lerp(b.p0, t),
+                p1: a.p1.lerp(b.p1, t),
+                p2: a.p2.lerp(b.p2, t),
+                p3: a.p3.lerp(b.p3, t),
+            })
+            .collect(),
+    )
+}
+
+/// remove anchor `k` (0..=len, the p0 of segment `k` / the p3 of `k - 1`).
+///
+/// an end anchor of an open chain takes its segment with it. an interior
+/// anchor merges its two segments into one that keeps their outer handles,
+/// stretched by how much longer the merged segment is, so the overall
+/// shape is roughly kept. in a closed chain anchor 0 is interior too
+/// (merging the last and the first segment).
+pub fn delete_anchor(beziers: &mut Vec<CubicBez>, k: usize, closed: bool) {
+    let n = beziers.len();
+    if n == 0 || k > n {
+        return;
+    }
+
+    if !closed && (k == 0 || k == n) {
+        if k == 0 {
+            beziers.remove(0);
+        } else {
+            beziers.pop();
+        }
+        return;
+    }
+    if n == 1 {
+        // a closed chain of one segment has nothing to merge with
+        beziers.clear();
+        return;
+    }
+
+    // in a closed chain anchor 0 and anchor n are the same point
+    let (before, after) = if k == 0 || k == n { (n - 1, 0) } else { (k - 1, k) };
+    let (a, b) = (beziers[before], beziers[after]);
+    let (len_a, len_b) = (a.p0.distance(a.p3), b.p0.distance(b.p3));
+    let total = len_a + len_b;
+    let stretch = |len: f64| if len > 0.0 { total / len } else { 1.0 };
+    let merged = CubicBez {
+        p0: a.p0,
+        p1: a.p0 + (a.p1 - a.p0) * stretch(len_a),
+        p2: b.p3 + (b.p2 - b.p3) * stretch(len_b),
+        p3: b.p3,
+    };
+
+    if before < after {
+        beziers[before] = merged;
+        beziers.remove(after);
+    } else {
+        // wrapped around: the merged segment becomes the new first one
+        beziers[0] = merged;
+        beziers.pop();
+    }
+}
+
+/// move every point of the chain by `delta`
+pub fn translate(beziers: &mut [CubicBez], delta: Vec2) {
+    for bez in beziers {
+        bez.p0 += delta;
+        bez.p1 += delta;
+        bez.p2 += delta;
+        bez.p3 += delta;
+    }
+}
+
+/// laplacian smoothing of the interior anchors within `radius` of `center`.
+///
+/// each one is pulled towards the midpoint of its two neighboring anchors,
+/// by `strength` (0..=1) scaled down linearly towards the edge of the
+/// radius. its handles travel along with it. the two ends never move.
+pub fn smooth_anchors(beziers: &mut [CubicBez], center: Point, radius: f64, strength: f64) {
+    if radius <= 0.0 {
+        return;
+    }
+
+    // compute every move from the anchors as they are now,
+    // so the result doesn't depend on the iteration order
+    let moves: Vec<(usize, Vec2)> = (1..beziers.len())
+        .filter_map(|i| {
+            let anchor = beziers[i].p0;
+            let dist = anchor.distance(center);
+            if dist > radius {
+                return None;
+            }
+            let target = beziers[i - 1].p0.midpoint(beziers[i].p3);
+            let weight = strength.clamp(0.0, 1.0) * (1.0 - dist / radius);
+            Some((i, (target - anchor) * weight))
+        })
+        .collect();
+
+    for (i, delta) in moves {
+        let (before, after) = beziers.split_at_mut(i);
+        let prev = &mut before[i - 1];
+        let cur = &mut after[0];
+        prev.p2 += delta;
+        prev.p3 += delta;
+        cur.p0 += delta;
+        cur.p1 += delta;
+    }
+}
+
+/// catmull-rom style re-tangenting: every interior anchor gets collinear
+/// handles along the direction from its previous to its next anchor, each
+/// a third of the distance to the neighbor on its side. the anchors stay
+/// where they are; on an open chain so do the outer handles of the ends.
+/// `closed` also smooths the anchor where the chain meets its start.
+pub fn retangent_smooth(beziers: &mut [CubicBez], closed: bool) {
+    let n = beziers.len();
+    if n == 0 {
+        return;
+    }
+    // anchor k sits between segment k-1 (ending there) and segment k
+    let interior = if closed { 0..n } else { 1..n };
+    let handles: Vec<(usize, Point, Point)> = interior
+        .filter_map(|k| {
+            let (inc, out) = ((k + n - 1) % n, k);
+            let (prev, anchor, next) = (beziers[inc].p0, beziers[out].p0, beziers[out].p3);
+            let dir = next - prev;
+            let len = dir.hypot();
+            if len < 1e-9 {
+                return None;
+            }
+            let dir = dir / len;
+            let p2 = anchor - dir * (anchor.distance(prev) / 3.0);
+            let p1 = anchor + dir * (anchor.distance(next) / 3.0);
+            Some((k, p2, p1))
+        })
+        .collect();
+
+    for (k, p2, p1) in handles {
+        beziers[(k + n - 1) % n].p2 = p2;
+        beziers[k].p1 = p1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// a wobbly stroke, the kind of input the fit drifts away from at the ends
+    fn wavy_stroke() -> Vec<Point> {
+        (0..=60)
+            .map(|i| {
+                let x = i as f64 * 3.0;
+                Point::new(x + 0.37, (x * 0.07).sin() * 25.0 + 0.61)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fit_curve_starts_and_ends_on_the_raw_ends() {
+        let raw = wavy_stroke();
+        for tol in [0.5, 4.0, 30.0] {
+            let (fit, _) = fit_curve(&raw, tol, STRAIGHT_ANGLE_TOL);
+            let (first, last) = (fit.first().unwrap(), fit.last().unwrap());
+            assert!(first.p0.distance(raw[0]) < 1e-6, "tol {tol}: starts at {:?}", first.p0);
+            assert!(last.p3.distance(raw[raw.len() - 1]) < 1e-6, "tol {tol}: ends at {:?}", last.p3);
+        }
+    }
+
+    #[test]
+    fn sample_even_spaces_points_along_the_whole_chain() {
+        // an L, 60 across and 45 down: 105 long
+        let corner = Point::new(60.0, 0.0);
+        let chain = [
+            straight_cubic(Point::ZERO, corner),
+            straight_cubic(corner, Point::new(60.0, 45.0)),
+        ];
+        let points = sample_even(&chain, 10.0);
+        assert_eq!(points.len(), 11);
+        for (i, p) in points.iter().enumerate() {
+            // the spacing carries on round the corner
+            let d = 10.0 * i as f64;
+            let expected = if d <= 60.0 { Point::new(d, 0.0) } else { Point::new(60.0, d - 60.0) };
+            assert!(p.distance(expected) < 1e-2, "{i}: {p:?}");
+        }
+        assert!(sample_even(&chain, 0.0).is_empty());
+    }
+
+    #[test]
+    fn fit_segment_count_cuts_an_arc_into_equal_pieces() {
+        // half a circle of radius 100 around the origin, a point every degree
+        let raw: Vec<Point> = (0..=180)
+            .map(|deg| {
+                let a = (deg as f64).to_radians();
+                Point::new(100.0 * a.cos(), 100.0 * a.sin())
+            })
+            .collect();
+        let fit = fit_segment_count(&raw, 3);
+        assert_eq!(fit.len(), 3);
+        assert_eq!(fit[0].p0, raw[0]);
+        assert_eq!(fit[2].p3, raw[180]);
+        // cut every 60°, and the pieces meet there
+        assert!(fit[0].p3.distance(raw[60]) < 1e-6, "{:?}", fit[0].p3);
+        assert!(fit[1].p3.distance(raw[120]) < 1e-6, "{:?}", fit[1].p3);
+        assert_eq!(fit[0].p3, fit[1].p0);
+        assert_eq!(fit[1].p3, fit[2].p0);
+        // and each piece follows its 60° of the circle
+        for bez in &fit {
+            for t in [0.25, 0.5, 0.75] {
+                let r = bez.eval(t).to_vec2().hypot();
+                assert!((r - 100.0).abs() < 0.1, "radius {r}");
+            }
+        }
+    }
+
+    /// straight segments through `anchors`
+    fn polyline(anchors: &[Point]) -> Vec<CubicBez> {
+        anchors.windows(2).map(|w| straight_cubic(w[0], w[1])).collect()
+    }
+
+    #[test]
+    fn remove_collinear_anchors_merges_a_straight_run() {
+        // a line from 0 to 100 with extra anchors, one a hair off
+        let anchors = [(0.0, 0.0), (20.0, 0.0), (45.0, 0.01), (70.0, 0.0), (100.0, 0.0)];
+        let line = polyline(&anchors.map(Point::from));
+        let merged = remove_collinear_anchors(&line, false, STRAIGHT_ANGLE_TOL, |_| false);
+        assert_eq!(merged, vec![straight_cubic(Point::ZERO, Point::new(100.0, 0.0))]);
+
+        // a kept anchor splits it in two
+        let kept = remove_collinear_anchors(&line, false, STRAIGHT_ANGLE_TOL, |k| k == 2);
+        assert_eq!(kept.len(), 2);
+        assert_eq!(kept[0].p3, Point::new(45.0, 0.01));
+
+        // a real corner stays
+        let bend = polyline(&[(0.0, 0.0), (50.0, 0.0), (50.0, 50.0)].map(Point::from));
+        assert_eq!(remove_collinear_anchors(&bend, false, STRAIGHT_ANGLE_TOL, |_| false), bend);
+    }
+
+    #[test]
+    fn retangent_smooth_points_handles_from_neighbor_to_neighbor() {
+        let anchors = [(0.0, 0.0), (30.0, 0.0), (60.0, 30.0), (90.0, 30.0)].map(Point::from);
+        let mut chain = polyline(&anchors);
+        let before = chain.clone();
+        retangent_smooth(&mut chain, false);
+
+        for k in 1..3 {
+            // catmull-rom: along previous -> next, a third of the way to each
+            let (prev, anchor, next) = (anchors[k - 1], anchors[k], anchors[k + 1]);
+            let dir = (next - prev).normalize();
+            let back = anchor - dir * anchor.distance(prev) / 3.0;
+            let ahead = anchor + dir * anchor.distance(next) / 3.0;
+            assert!(chain[k - 1].p2.distance(back) < 1e-9, "anchor {k}");
+            assert!(chain[k].p1.distance(ahead) < 1e-9, "anchor {k}");
+        }
+        // open: the end handles stay
+        assert_eq!(chain[0].p1, before[0].p1);
+        assert_eq!(chain[2].p2, before[2].p2);
+
+        // closed: the start is smoothed between the last and second anchor
+        let mut square = rect_cubics(Rect::new(0.0, 0.0, 30.0, 30.0));
+        retangent_smooth(&mut square, true);
+        let (prev, start, next) = (square[3].p0, square[0].p0, square[0].p3);
+        let dir = (next - prev).normalize();
+        assert!(square[0].p1.distance(start + dir * 10.0) < 1e-9);
+        assert!(square[3].p2.distance(start - dir * 10.0) < 1e-9);
+    }
+
+    #[test]
+    fn fit_curve_needs_two_points() {
+        assert!(fit_curve(&[], 1.0, STRAIGHT_ANGLE_TOL).0.is_empty());
+        assert!(fit_curve(&[Point::new(1.0, 2.0)], 1.0, STRAIGHT_ANGLE_TOL).0.is_empty());
+    }
+
+    #[test]
+    fn fit_curve_straightens_the_arms_of_an_l() {
+        // along the top, then down: a right angle at (200, 0)
+        let raw: Vec<Point> = (0..=100)
+            .map(|i| Point::new(i as f64 * 2.0, 0.0))
+            .chain((1..=100).map(|i| Point::new(200.0, i as f64 * 2.0)))
+            .collect();
+        let (fit, corners) = fit_curve(&raw, 1.0, STRAIGHT_ANGLE_TOL);
+        assert!(fit.len() > 1);
+
+        // the ends of the arms are exact lines along them
+        let (first, last) = (fit[0], fit[fit.len() - 1]);
+        assert_eq!(first, straight_cubic(first.p0, first.p3));
+        assert_eq!(last, straight_cubic(last.p0, last.p3));
+        assert!(first.p3.y.abs() < 1e-6 && first.p3.x < 200.0);
+        assert!((last.p0.x - 200.0).abs() < 1e-6 && last.p0.y > 0.0);
+
+        // and their inner anchors are corners
+        assert!(corners.contains(&1));
+        assert!(corners.contains(&(fit.len() - 1)));
+        for &k in &corners {
+            let straight = |bez: CubicBez| bez == straight_cubic(bez.p0, bez.p3);
+            assert!(straight(fit[k - 1]) || straight(fit[k]), "corner {k}");
+        }
+    }
+}
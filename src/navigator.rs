@@ -0,0 +1,115 @@
+use eframe::egui::{self, Color32, Context, Pos2, Rect, Sense, Stroke, Vec2};
+use kurbo::Rect as KRect;
+
+use crate::Shaper;
+
+/// size (screen px) of the navigator's map
+const NAVIGATOR_SIZE: Vec2 = Vec2::new(160.0, 120.0);
+
+/// room (map px) left around the content inside the map
+const NAVIGATOR_PADDING: f32 = 6.0;
+
+/// maps the world rect `frame` into `map` (screen), keeping the aspect ratio
+/// and centering it
+struct MapTransform {
+    frame: KRect,
+    map: Rect,
+    scale: f32,
+}
+
+impl MapTransform {
+    fn new(frame: KRect, map: Rect) -> Self {
+        let inner = map.shrink(NAVIGATOR_PADDING);
+        let (w, h) = (
+            frame.width().max(1e-6) as f32,
+            frame.height().max(1e-6) as f32,
+        );
+        let scale = (inner.width() / w).min(inner.height() / h);
+        MapTransform { frame, map, scale }
+    }
+
+    fn to_map(&self, x: f64, y: f64) -> Pos2 {
+        let center = self.frame.center();
+        self.map.center() + Vec2::new((x - center.x) as f32, (y - center.y) as f32) * self.scale
+    }
+
+    fn rect_to_map(&self, r: KRect) -> Rect {
+        Rect::from_two_pos(self.to_map(r.x0, r.y0), self.to_map(r.x1, r.y1))
+    }
+}
+
+impl Shaper {
+    /// the part of the world (world space) visible in `canvas` (screen space)
+    pub fn viewport_world(&self, canvas: Rect) -> KRect {
+        let (min, max) = (
+            self.screen_to_world(canvas.min),
+            self.screen_to_world(canvas.max),
+        );
+        KRect::new(min.x as f64, min.y as f64, max.x as f64, max.y as f64)
+    }
+
+    /// small map in the bottom left corner: every shape's bounding box and
+    /// the visible part of the `canvas` as a rectangle, which can be dragged
+    /// to pan around.
+    pub fn show_navigator_window(&mut self, ctx: &Context, canvas: Rect) {
+        let viewport = self.viewport_world(canvas);
+
+        // everything drawn plus what's on screen. frozen while dragging,
+        // otherwise the map would shift under the pointer as the viewport moves
+        let frame = self.navigator_drag_frame.unwrap_or_else(|| {
+            self.shapes
+                .iter()
+                .filter_map(|shape| shape.bounding_box())
+                .fold(viewport, |acc, bbox| acc.union(bbox))
+        });
+
+        egui::Window::new("Navigator")
+            .anchor(egui::Align2::LEFT_BOTTOM, egui::Vec2::new(10.0, -10.0))
+            .collapsible(false)
+            .title_bar(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let (response, painter) = ui.allocate_painter(NAVIGATOR_SIZE, Sense::drag());
+                let map = MapTransform::new(frame, response.rect);
+
+                painter.rect_filled(response.rect, 0.0, self.canvas_background);
+                for bbox in self.shapes.iter().filter_map(|shape| shape.bounding_box()) {
+                    painter.rect_stroke(
+                        map.rect_to_map(bbox),
+                        0.0,
+                        Stroke::new(1.0, Color32::GRAY),
+                        egui::StrokeKind::Middle,
+                    );
+                }
+
+                let view_rect = map.rect_to_map(viewport);
+                painter.rect_filled(
+                    view_rect,
+                    0.0,
+                    Color32::from_rgba_unmultiplied(0, 120, 255, 30),
+                );
+                painter.rect_stroke(
+                    view_rect,
+                    0.0,
+                    Stroke::new(1.5, Color32::from_rgb(0, 120, 255)),
+                    egui::StrokeKind::Middle,
+                );
+
+                if response.drag_started() {
+                    self.navigator_drag_frame = Some(frame);
+                }
+                if response.dragged() {
+                    // moving the viewport right moves the content left
+                    let world_delta = response.drag_delta() / map.scale;
+                    self.pan_offset -= world_delta * self.zoom;
+                    self.clamp_pan(canvas);
+                    ui.ctx().set_cursor_icon(egui::CursorIcon::Grabbing);
+                } else if response.hovered() {
+                    ui.ctx().set_cursor_icon(egui::CursorIcon::Grab);
+                }
+                if response.drag_stopped() {
+                    self.navigator_drag_frame = None;
+                }
+            });
+    }
+}
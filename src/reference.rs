@@ -0,0 +1,97 @@
+// a raster image shown behind the drawing to trace over. it's only a
+// backdrop: not a shape, not hit-tested and not exported.
+
+use eframe::egui::{self, Color32, Painter, Pos2, Rect, TextureHandle, TextureOptions, Vec2};
+
+use crate::Shaper;
+
+pub struct ReferenceImage {
+    texture: TextureHandle,
+    /// image size in pixels
+    size: Vec2,
+    /// world position of the image's top left corner
+    pub origin: Pos2,
+    /// world units per image pixel
+    pub scale: f32,
+    /// 0 = invisible, 1 = fully opaque
+    pub opacity: f32,
+}
+
+impl ReferenceImage {
+    /// decode the image at `path` (PNG) and upload it as a texture.
+    /// it starts at the world origin, one image pixel per world unit, half
+    /// transparent so the strokes on top stand out.
+    pub fn load(ctx: &egui::Context, path: &str) -> Result<Self, String> {
+        let image = image::open(path).map_err(|e| e.to_string())?.to_rgba8();
+        let size = [image.width() as usize, image.height() as usize];
+        let pixels = egui::ColorImage::from_rgba_unmultiplied(size, image.as_raw());
+        let texture = ctx.load_texture("reference image", pixels, TextureOptions::LINEAR);
+        Ok(ReferenceImage {
+            texture,
+            size: Vec2::new(size[0] as f32, size[1] as f32),
+            origin: Pos2::ZERO,
+            scale: 1.0,
+            opacity: 0.5,
+        })
+    }
+
+    /// where the image lies in world space
+    pub fn world_rect(&self) -> Rect {
+        Rect::from_min_size(self.origin, self.size * self.scale)
+    }
+
+    pub fn paint(&self, painter: &Painter, app: &Shaper) {
+        let world = self.world_rect();
+        let screen = Rect::from_two_pos(
+            app.world_to_screen(world.min),
+            app.world_to_screen(world.max),
+        );
+        let uv = Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0));
+        painter.image(
+            self.texture.id(),
+            screen,
+            uv,
+            Color32::WHITE.gamma_multiply(self.opacity),
+        );
+    }
+}
+
+impl Shaper {
+    /// load / remove the reference image and place it, for the settings window
+    pub fn reference_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("File:");
+            ui.text_edit_singleline(&mut self.reference_path);
+        });
+        ui.horizontal(|ui| {
+            if ui.button("Load").clicked() {
+                match ReferenceImage::load(ui.ctx(), &self.reference_path) {
+                    Ok(reference) => {
+                        self.reference = Some(reference);
+                        self.reference_status = None;
+                    }
+                    Err(e) => self.reference_status = Some(format!("error: {e}")),
+                }
+            }
+            if self.reference.is_some() && ui.button("Remove").clicked() {
+                self.reference = None;
+            }
+        });
+        if let Some(status) = &self.reference_status {
+            ui.label(status);
+        }
+
+        if let Some(reference) = &mut self.reference {
+            ui.add(egui::Slider::new(&mut reference.opacity, 0.0..=1.0).text("Opacity"));
+            ui.add(
+                egui::Slider::new(&mut reference.scale, 0.01..=10.0)
+                    .logarithmic(true)
+                    .text("Scale"),
+            );
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut reference.origin.x).prefix("x: "));
+                ui.add(egui::DragValue::new(&mut reference.origin.y).prefix("y: "));
+            });
+        }
+    }
+}
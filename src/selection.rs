@@ -0,0 +1,273 @@
+use std::collections::{HashMap, HashSet};
+
+use kurbo::Point as KPoint;
+
+use crate::Shaper;
+
+/// identifies a single point of a shape:
+/// `app.shapes[shape_idx].beziers[bez_idx]` and one of its p0..=p3
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub struct PointId {
+    pub shape_idx: usize,
+    pub bez_idx: usize,
+    pub ctrl_idx: usize, // 0..=3
+}
+
+impl Shaper {
+    /// world position of the point `id` refers to,
+    /// or `None` if the id went stale.
+    pub fn point_pos(&self, id: PointId) -> Option<KPoint> {
+        let bez = self.shapes.get(id.shape_idx)?.beziers.get(id.bez_idx)?;
+        match id.ctrl_idx {
+            0 => Some(bez.p0),
+            1 => Some(bez.p1),
+            2 => Some(bez.p2),
+            3 => Some(bez.p3),
+            _ => None,
+        }
+    }
+
+    /// how many shapes are selected (that still exist), for the UI to
+    /// tell which actions apply
+    pub fn selected_shape_count(&self) -> usize {
+        self.selected_shapes.iter().filter(|&&idx| idx < self.shapes.len()).count()
+    }
+
+    /// how many points are selected (that still exist)
+    pub fn selected_point_count(&self) -> usize {
+        self.selected_points.iter().filter(|&&id| self.point_pos(id).is_some()).count()
+    }
+
+    /// deselect everything, shapes and points
+    pub fn clear_selection(&mut self) {
+        self.selected_shapes.clear();
+        self.selected_points.clear();
+    }
+
+    /// select every shape (no points)
+    pub fn select_all(&mut self) {
+        self.selected_shapes = (0..self.shapes.len()).collect();
+        self.selected_points.clear();
+    }
+
+    /// make `id` the only selected point, and its shape the only selected shape
+    pub fn select_single_point(&mut self, id: PointId) {
+        self.selected_shapes = HashSet::from([id.shape_idx]);
+        self.selected_points = HashSet::from([id]);
+    }
+
+    /// make `shape_idx` the only selected shape (and drop any selected points)
+    pub fn select_single_shape(&mut self, shape_idx: usize) {
+        self.selected_shapes = HashSet::from([shape_idx]);
+        self.selected_points.clear();
+    }
+
+    /// add `shape_idx` to the selection, or remove it if it was already selected
+    pub fn toggle_shape_selection(&mut self, shape_idx: usize) {
+        if !self.selected_shapes.remove(&shape_idx) {
+            self.selected_shapes.insert(shape_idx);
+        }
+    }
+
+    /// select every anchor (the p0/p3 ends of the segments) of a shape
+    pub fn select_all_points_in_shape(&mut self, shape_idx: usize) {
+        let Some(shape) = self.shapes.get(shape_idx) else {
+            return;
+        };
+        self.selected_shapes = HashSet::from([shape_idx]);
+        // p3 of a segment is p0 of the next one, so only the last segment
+        // of each open subpath contributes its end point (a closed one
+        // ends on its start, p0 of its first segment)
+        let subpath_ends: Vec<usize> = if shape.closed {
+            Vec::new()
+        } else {
+            shape.subpath_ranges().iter().map(|r| r.end - 1).collect()
+        };
+        self.selected_points = (0..shape.beziers.len())
+            .map(|bez_idx| PointId {
+                shape_idx,
+                bez_idx,
+                ctrl_idx: 0,
+            })
+            .chain(subpath_ends.into_iter().map(|bez_idx| PointId {
+                shape_idx,
+                bez_idx,
+                ctrl_idx: 3,
+            }))
+            .collect();
+    }
+
+    /// the anchor `id` belongs to (a handle belongs to the anchor at its
+    /// end of the segment), in one canonical form: p0 of the segment
+    /// starting there, p3 only for the end of an open subpath
+    pub fn anchor_id(&self, id: PointId) -> Option<PointId> {
+        let shape = self.shapes.get(id.shape_idx)?;
+        if id.bez_idx >= shape.beziers.len() {
+            return None;
+        }
+        let (bez_idx, ctrl_idx) = match id.ctrl_idx {
+            0 | 1 => (id.bez_idx, 0),
+            _ => match shape.next_segment(id.bez_idx) {
+                Some(next) => (next, 0),
+                None => (id.bez_idx, 3),
+            },
+        };
+        Some(PointId { bez_idx, ctrl_idx, ..id })
+    }
+
+    /// whether `id` is a locked anchor (its handles never are)
+    pub fn is_locked(&self, id: PointId) -> bool {
+        matches!(id.ctrl_idx, 0 | 3)
+            && self.anchor_id(id).is_some_and(|anchor| self.locked_points.contains(&anchor))
+    }
+
+    /// lock the anchors of the selected points, or unlock them
+    /// if they all are locked already
+    pub fn toggle_lock_selected_points(&mut self) {
+        let anchors: HashSet<PointId> =
+            self.selected_points.iter().filter_map(|&id| self.anchor_id(id)).collect();
+        if anchors.is_subset(&self.locked_points) {
+            self.locked_points.retain(|id| !anchors.contains(id));
+        } else {
+            self.locked_points.extend(anchors);
+        }
+    }
+
+    /// drop the selected and locked points of `shapes[shape_idx]`, after
+    /// its segments were rebuilt (refit, anchors deleted, ...) and the
+    /// old point ids don't mean anything anymore
+    pub fn forget_points(&mut self, shape_idx: usize) {
+        self.selected_points.retain(|id| id.shape_idx != shape_idx);
+        self.locked_points.retain(|id| id.shape_idx != shape_idx);
+    }
+
+    /// rebuild `selected_shapes`, `selected_points` and `locked_points` after
+    /// a structural change to `self.shapes` (delete, reorder, ...).
+    ///
+    /// `old_to_new` maps an old shape index to its new index, or to `None`
+    /// if the shape was removed. indices missing from the map stay as they are.
+    pub fn remap_selection(&mut self, old_to_new: &HashMap<usize, Option<usize>>) {
+        let remap = |idx: usize| -> Option<usize> {
+            match old_to_new.get(&idx) {
+                Some(new_idx) => *new_idx,
+                None => Some(idx),
+            }
+        };
+
+        self.selected_shapes = self
+            .selected_shapes
+            .iter()
+            .filter_map(|&shape_idx| remap(shape_idx))
+            .collect();
+
+        self.selected_points = self
+            .selected_points
+            .iter()
+            .filter_map(|p| {
+                remap(p.shape_idx).map(|shape_idx| PointId { shape_idx, ..*p })
+            })
+            .collect();
+        self.locked_points = self
+            .locked_points
+            .iter()
+            .filter_map(|p| {
+                remap(p.shape_idx).map(|shape_idx| PointId { shape_idx, ..*p })
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use eframe::egui::Color32;
+    use kurbo::Point as KPoint;
+
+    use super::PointId;
+    use crate::Shaper;
+
+    /// `n` horizontal lines, one below the other
+    fn app_with_lines(n: usize) -> Shaper {
+        let mut app = Shaper::default();
+        for i in 0..n {
+            let y = i as f64 * 10.0;
+            app.add_line(KPoint::new(0.0, y), KPoint::new(100.0, y), 2.0, Color32::BLACK);
+        }
+        app
+    }
+
+    fn point(shape_idx: usize, ctrl_idx: usize) -> PointId {
+        PointId {
+            shape_idx,
+            bez_idx: 0,
+            ctrl_idx,
+        }
+    }
+
+    #[test]
+    fn removing_a_selected_shape_shifts_the_others_down() {
+        let mut app = app_with_lines(4);
+        app.selected_shapes = HashSet::from([0, 1, 3]);
+        app.selected_points = HashSet::from([point(1, 0), point(3, 3)]);
+        app.locked_points = HashSet::from([point(1, 3), point(3, 0)]);
+
+        app.remove_shape(1);
+
+        assert_eq!(app.selected_shapes, HashSet::from([0, 2]));
+        assert_eq!(app.selected_points, HashSet::from([point(2, 3)]));
+        assert_eq!(app.locked_points, HashSet::from([point(2, 0)]));
+    }
+
+    #[test]
+    fn removing_an_unselected_shape_keeps_the_selection_on_the_same_shapes() {
+        let mut app = app_with_lines(3);
+        app.selected_shapes = HashSet::from([0, 2]);
+        app.selected_points = HashSet::from([point(2, 0)]);
+
+        app.remove_shape(1);
+
+        assert_eq!(app.selected_shapes, HashSet::from([0, 1]));
+        assert_eq!(app.selected_points, HashSet::from([point(1, 0)]));
+    }
+
+    #[test]
+    fn select_all_points_selects_every_anchor_once() {
+        let mut app = app_with_lines(1);
+        let square = crate::model::rect_cubics(kurbo::Rect::new(0.0, 0.0, 10.0, 10.0));
+        let mut closed = crate::Shape::new(2.0, Color32::BLACK);
+        closed.set_beziers(square.clone());
+        closed.closed = true;
+        app.add_shape(closed);
+        // two open subpaths: three sides of the square, and of one next to it
+        let mut open = crate::Shape::new(2.0, Color32::BLACK);
+        let moved = kurbo::Affine::translate((20.0, 0.0));
+        let sides = square[..3].iter().copied();
+        open.set_beziers(sides.clone().chain(sides.map(|bez| moved * bez)).collect());
+        open.subpath_starts = vec![3];
+        app.add_shape(open);
+
+        // a line has 2 anchors, the closed square 4, the open sides 4 each
+        for (shape_idx, anchors) in [(0, 2), (1, 4), (2, 8)] {
+            app.select_all_points_in_shape(shape_idx);
+            assert_eq!(app.selected_point_count(), anchors, "shape {shape_idx}");
+            let positions: HashSet<(u64, u64)> = app
+                .selected_points
+                .iter()
+                .map(|&id| app.point_pos(id).unwrap())
+                .map(|pos| (pos.x.to_bits(), pos.y.to_bits()))
+                .collect();
+            assert_eq!(positions.len(), anchors, "shape {shape_idx} has a point twice");
+        }
+    }
+
+    #[test]
+    fn indices_missing_from_the_map_stay() {
+        let mut app = app_with_lines(3);
+        app.selected_shapes = HashSet::from([0, 1, 2]);
+
+        app.remap_selection(&HashMap::from([(1, None), (2, Some(1))]));
+
+        assert_eq!(app.selected_shapes, HashSet::from([0, 1]));
+    }
+}
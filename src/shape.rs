@@ -1,327 +1,1060 @@
 use eframe::egui::{
-    epaint::{CubicBezierShape, PathShape}, Color32, Painter, Pos2, Stroke, Rect
+    self, epaint::{CubicBezierShape, Mesh, PathShape}, Align2, Color32, FontId, Painter, Pos2, Stroke, Rect
 };
-use kurbo::{CubicBez, Point as KPoint, Vec2};
-use simplify_rs::{Point as SrPoint, simplify};
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use kurbo::{Affine, BezPath, CubicBez, Point as KPoint, Rect as KRect, Vec2};
+
+use crate::model;
+
+/// thinnest a stroke is ever rendered (screen px), so thin shapes don't
+/// vanish when zoomed far out. purely a rendering floor: `thickness`
+/// itself (and so the export) is untouched.
+pub const MIN_SCREEN_STROKE_WIDTH: f32 = 1.0;
+
+/// source of `Shape::geometry_version`s. global rather than per shape, so a
+/// shape that replaces another at the same index never reuses a version.
+static NEXT_GEOMETRY_VERSION: AtomicU64 = AtomicU64::new(1);
+
+fn next_geometry_version() -> u64 {
+    NEXT_GEOMETRY_VERSION.fetch_add(1, Ordering::Relaxed)
+}
+
+/// how the rest of the chain follows when one point is moved
+/// with `Shape::set_ctrl`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HandleMode {
+    /// every point moves on its own, anchors leave their handles behind
+    Free,
+    /// anchors take their handles along, handles move on their own
+    Carry,
+    /// like `Carry`, and moving a handle turns the one on the other side
+    /// of the anchor so both stay in line (keeping its length): a smooth corner
+    Smooth,
+}
+
+/// which parts of a filled shape count as inside, where its outline
+/// crosses itself or subpaths overlap. the same as SVG's `fill-rule`
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum FillRule {
+    /// inside wherever the outline winds around the point at all
+    #[default]
+    NonZero,
+    /// inside where it winds around an odd number of times,
+    /// so overlaps cut holes
+    EvenOdd,
+}
+
+impl FillRule {
+    pub fn is_inside(self, winding: i32) -> bool {
+        match self {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+        }
+    }
+}
+
+/// what a point of the handles is drawn as, see `Shape::draw_handles`
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HandleShape {
+    Square,
+    Circle,
+}
+
+impl HandleShape {
+    pub fn label(self) -> &'static str {
+        match self {
+            HandleShape::Square => "Square",
+            HandleShape::Circle => "Circle",
+        }
+    }
+
+    /// a marker `radius` (screen px, half the side for a square) around `center`
+    pub fn paint(self, painter: &Painter, center: Pos2, radius: f32, color: Color32) {
+        match self {
+            HandleShape::Square => {
+                let rect = Rect::from_center_size(center, egui::Vec2::splat(radius * 2.0));
+                painter.rect_filled(rect, 0.0, color);
+            }
+            HandleShape::Circle => {
+                painter.circle_filled(center, radius, color);
+            }
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct Shape {
+    /// user facing name, e.g. "Shape 3" (see `Shaper::add_shape`)
+    pub name: String,
+
     /// raw points collected during the current drag
     pub current_stroke: Vec<Pos2>,
 
     /// history of all raw strokes, for later re-fit again
     pub raw_strokes: Vec<Vec<Pos2>>,
 
-    /// All “fitted” Bézier segments (one CubicBez per segment)
+    /// All “fitted” Bézier segments (one CubicBez per segment).
+    /// change them through `beziers_mut`/`set_beziers` (or the methods
+    /// below), so `geometry_version` moves along
     pub beziers: Vec<CubicBez>,
 
+    /// changes whenever `beziers` may have, so anything derived from the
+    /// geometry (flattened outlines, spatial indices, ...) can remember the
+    /// version it was computed for and tell when it went stale
+    geometry_version: u64,
+
+    /// indices into `beziers` where a new, disconnected subpath starts
+    /// (ascending, the one at 0 is implied). empty for the usual single
+    /// chain. `closed` applies to every subpath.
+    pub subpath_starts: Vec<usize>,
+
+    /// anchors the fit made corners of (see `model::straighten_near_lines`),
+    /// as the index of the segment starting there. `HandleMode::Smooth`
+    /// leaves their other handle alone. `set_beziers` forgets them.
+    pub corners: Vec<usize>,
+
     pub thickness: f32,
 
     pub stroke_color: Color32,
+
+    /// whether the last segment's p3 connects back to the first p0
+    pub closed: bool,
+
+    /// interior color of a closed shape (`TRANSPARENT` = no fill)
+    pub fill_color: Color32,
+    /// what the interior is, see `FillRule`
+    pub fill_rule: FillRule,
 }
 
 impl Shape {
     pub fn new(thickness: f32, stroke_color: Color32) -> Self {
         Shape {
+            name: String::new(),
             current_stroke: Vec::new(),
             raw_strokes: Vec::new(),
             beziers: Vec::new(),
+            geometry_version: next_geometry_version(),
+            subpath_starts: Vec::new(),
+            corners: Vec::new(),
             thickness: thickness,
             stroke_color: stroke_color,
+            closed: false,
+            fill_color: Color32::TRANSPARENT,
+            fill_rule: FillRule::NonZero,
         }
     }
 
-    /// take a completed raw stroke (`&[Pos2]`), run `simplify-rs` on it,
-    /// and append each resulting `[SrPoint;4]` as a `kurbo::CubicBez`.
-    pub fn fit_curve_and_store(&mut self, raw: &[Pos2], bzr_tol: f64) {
-        // Convert Pos2 → simplify_rs::Point (which is { x: f64, y: f64 })
-        let sr_points: Vec<SrPoint> = raw
+    #[allow(dead_code)] // for caches of derived geometry
+    pub fn geometry_version(&self) -> u64 {
+        self.geometry_version
+    }
+
+    /// mutable access to the segments; counts as a change of the geometry
+    pub fn beziers_mut(&mut self) -> &mut Vec<CubicBez> {
+        self.geometry_version = next_geometry_version();
+        &mut self.beziers
+    }
+
+    /// replace all segments (and forget the `corners`)
+    pub fn set_beziers(&mut self, beziers: Vec<CubicBez>) {
+        *self.beziers_mut() = beziers;
+        self.corners.clear();
+    }
+
+    /// take a completed raw stroke (`&[Pos2]`), fit it with
+    /// `model::fit_curve` and append the resulting segments and corners.
+    pub fn fit_curve_and_store(&mut self, raw: &[Pos2], bzr_tol: f64, straight_tol: f64) {
+        let raw: Vec<KPoint> = raw
             .iter()
-            .map(|&p| SrPoint {
-                x: p.x as f64,
-                y: p.y as f64,
-            })
+            .map(|p| KPoint::new(p.x as f64, p.y as f64))
             .collect();
+        let (fit, corners) = model::fit_curve(&raw, bzr_tol, straight_tol);
+        let offset = self.beziers.len();
+        self.corners.extend(corners.into_iter().map(|k| k + offset));
+        self.beziers_mut().extend(fit);
+    }
+
+    /// like `fit_curve_and_store`, but always fits exactly `n` segments
+    /// (`model::fit_segment_count`) instead of going by a tolerance.
+    pub fn fit_with_segment_count(&mut self, raw: &[Pos2], n: usize) {
+        let raw: Vec<KPoint> = raw
+            .iter()
+            .map(|p| KPoint::new(p.x as f64, p.y as f64))
+            .collect();
+        self.beziers_mut().extend(model::fit_segment_count(&raw, n));
+    }
+
+    /// method to be called whenever `self.bezier_tolerance` changes.
+    /// it throws away all old Béziers and re‐creates them from every raw stroke.
+    /// shapes without raw strokes (e.g. imported ones) are left alone.
+    pub fn refit_all_strokes(&mut self, bzr_tol: f64, straight_tol: f64) {
+        if self.raw_strokes.is_empty() {
+            return;
+        }
+        self.beziers_mut().clear();
+        self.subpath_starts.clear();
+        self.corners.clear();
+        let raw_strokes: Vec<Vec<Pos2>> = self.raw_strokes.iter().cloned().collect();
+        for raw in &raw_strokes {
+            // every raw stroke is its own subpath
+            self.start_subpath();
+            self.fit_curve_and_store(raw, bzr_tol, straight_tol);
+        }
+        // the new fit doesn't necessarily end exactly on its start again
+        if self.closed {
+            self.close();
+        }
+    }
+
+    /// make the segments added next a new subpath (nothing to do while
+    /// there are no segments yet, the first subpath starts at 0 anyway)
+    pub fn start_subpath(&mut self) {
+        if !self.beziers.is_empty() {
+            self.subpath_starts.push(self.beziers.len());
+        }
+    }
+
+    /// remove the last subpath, and its raw stroke if every subpath has one
+    pub fn pop_subpath(&mut self) {
+        let Some(last) = self.subpath_ranges().pop() else {
+            return;
+        };
+        if self.raw_strokes.len() == self.subpath_ranges().len() {
+            self.raw_strokes.pop();
+        }
+        self.beziers_mut().truncate(last.start);
+        self.subpath_starts.pop();
+        self.corners.retain(|&k| k < last.start);
+    }
 
-        if sr_points.len() < 2 {
-            return; // nothing to fit
+    /// the index range into `beziers` of every subpath, in order
+    /// (just `0..len` for a single chain, nothing while empty)
+    pub fn subpath_ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges = Vec::with_capacity(self.subpath_starts.len() + 1);
+        let mut start = 0;
+        for &next in &self.subpath_starts {
+            if next > start && next < self.beziers.len() {
+                ranges.push(start..next);
+                start = next;
+            }
+        }
+        if start < self.beziers.len() {
+            ranges.push(start..self.beziers.len());
         }
+        ranges
+    }
 
-        // tolerance (in screen units) for the maximum deviation
-        let tol = bzr_tol;
+    /// the segment before `bez_idx` in its subpath, wrapping around on
+    /// closed shapes. `None` at the start of an open subpath.
+    pub fn prev_segment(&self, bez_idx: usize) -> Option<usize> {
+        let range = self.subpath_ranges().into_iter().find(|r| r.contains(&bez_idx))?;
+        if bez_idx > range.start {
+            Some(bez_idx - 1)
+        } else if self.closed {
+            Some(range.end - 1)
+        } else {
+            None
+        }
+    }
 
-        // → Vec<[SrPoint;4]>: each [P0,P1,P2,P3] is a cubic in simplify-rs
-        let flat: Vec<SrPoint> = simplify(&sr_points, tol);
+    /// the segment after `bez_idx` in its subpath, wrapping around on
+    /// closed shapes. `None` at the end of an open subpath.
+    pub fn next_segment(&self, bez_idx: usize) -> Option<usize> {
+        let range = self.subpath_ranges().into_iter().find(|r| r.contains(&bez_idx))?;
+        if bez_idx + 1 < range.end {
+            Some(bez_idx + 1)
+        } else if self.closed {
+            Some(range.start)
+        } else {
+            None
+        }
+    }
 
-        // turn flat Vec<SrPoint> into Vec<[SrPoint; 4]>
-        let beziers_rs: Vec<[SrPoint; 4]> = flat
-            .chunks_exact(4)
-            .map(|chunk| {
-                [
-                    chunk[0].clone(),
-                    chunk[1].clone(),
-                    chunk[2].clone(),
-                    chunk[3].clone(),
-                ]
+    /// move point `ctrl_idx` (0..=3) of segment `bez_idx` to `p`. the chain
+    /// always stays connected: an anchor (p0/p3) also moves the end of the
+    /// neighboring segment it's shared with. what the handles do depends
+    /// on `mode`, see `HandleMode`. out of range indices are ignored.
+    pub fn set_ctrl(&mut self, bez_idx: usize, ctrl_idx: usize, p: KPoint, mode: HandleMode) {
+        if bez_idx >= self.beziers.len() {
+            return;
+        }
+        let (prev, next) = (self.prev_segment(bez_idx), self.next_segment(bez_idx));
+        let carry = mode != HandleMode::Free;
+        // a corner's handles don't turn with each other
+        let smooth = mode == HandleMode::Smooth;
+        let smooth_p0 = smooth && !self.corners.contains(&bez_idx);
+        let smooth_p3 = smooth && next.is_some_and(|i| !self.corners.contains(&i));
+        let beziers = self.beziers_mut();
+        match ctrl_idx {
+            0 => {
+                let delta = p - beziers[bez_idx].p0;
+                beziers[bez_idx].p0 = p;
+                if carry {
+                    beziers[bez_idx].p1 += delta;
+                }
+                if let Some(i) = prev {
+                    beziers[i].p3 = p;
+                    if carry {
+                        beziers[i].p2 += delta;
+                    }
+                }
+            }
+            1 => {
+                beziers[bez_idx].p1 = p;
+                if let (true, Some(i)) = (smooth_p0, prev) {
+                    let anchor = beziers[bez_idx].p0;
+                    beziers[i].p2 = opposite_handle(anchor, p, beziers[i].p2);
+                }
+            }
+            2 => {
+                beziers[bez_idx].p2 = p;
+                if let (true, Some(i)) = (smooth_p3, next) {
+                    let anchor = beziers[bez_idx].p3;
+                    beziers[i].p1 = opposite_handle(anchor, p, beziers[i].p1);
+                }
+            }
+            3 => {
+                let delta = p - beziers[bez_idx].p3;
+                beziers[bez_idx].p3 = p;
+                if carry {
+                    beziers[bez_idx].p2 += delta;
+                }
+                if let Some(i) = next {
+                    beziers[i].p0 = p;
+                    if carry {
+                        beziers[i].p1 += delta;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// remove anchor `k`, see `model::delete_anchor`. the `corners` after it
+    /// move down with their anchors
+    pub fn delete_anchor(&mut self, k: usize) {
+        let (n, closed) = (self.beziers.len(), self.closed);
+        model::delete_anchor(self.beziers_mut(), k, closed);
+        // in a closed chain deleting anchor 0 (or n) merges the last segment
+        // into the first, so the last one's start becomes anchor 0
+        let wrapped = closed && (k == 0 || k == n);
+        let len = self.beziers.len();
+        self.corners = self
+            .corners
+            .iter()
+            .filter_map(|&c| match c {
+                _ if wrapped => (c != 0).then_some(if c + 1 == n { 0 } else { c }),
+                _ if c == k => None,
+                _ if c > k => Some(c - 1),
+                _ => Some(c),
             })
+            .filter(|&c| c < len)
             .collect();
+    }
+
+    /// replace segment `bez_idx` with `seg`, moving the shared ends of the
+    /// neighboring segments along (their handles stay where they are)
+    pub fn set_segment(&mut self, bez_idx: usize, seg: CubicBez) {
+        if bez_idx >= self.beziers.len() {
+            return;
+        }
+        let (prev, next) = (self.prev_segment(bez_idx), self.next_segment(bez_idx));
+        let beziers = self.beziers_mut();
+        beziers[bez_idx] = seg;
+        if let Some(i) = prev {
+            beziers[i].p3 = seg.p0;
+        }
+        if let Some(i) = next {
+            beziers[i].p0 = seg.p3;
+        }
+    }
 
-        // convert each [SrPoint;4] → kurbo::CubicBez, then store it
-        for bez in beziers_rs {
-            let (p0, p1, p2, p3) = (
-                // cast each simplify_rs::Point back into egui::Pos2 (f32)
-                Pos2::new(bez[0].x as f32, bez[0].y as f32),
-                Pos2::new(bez[1].x as f32, bez[1].y as f32),
-                Pos2::new(bez[2].x as f32, bez[2].y as f32),
-                Pos2::new(bez[3].x as f32, bez[3].y as f32),
-            );
+    /// union of the bounding boxes of all fitted segments (world space).
+    /// `None` while the shape has no segments yet.
+    pub fn bounding_box(&self) -> Option<KRect> {
+        model::bounding_box(&self.beziers)
+    }
 
-            // build a kurbo::CubicBez (fields are (p0,p1,p2,p3), each a kurbo::Point)
-            let seg = CubicBez {
-                p0: KPoint::new(p0.x as f64, p0.y as f64),
-                p1: KPoint::new(p1.x as f64, p1.y as f64),
-                p2: KPoint::new(p2.x as f64, p2.y as f64),
-                p3: KPoint::new(p3.x as f64, p3.y as f64),
-            };
-            self.beziers.push(seg);
+    /// the fitted segments as one kurbo path, one subpath each
+    /// (closed if the shape is)
+    pub fn to_bez_path(&self) -> BezPath {
+        let mut path = BezPath::new();
+        for range in self.subpath_ranges() {
+            path.extend(model::to_bez_path(&self.beziers[range], self.closed));
         }
+        path
     }
 
-    #[allow(dead_code)]
-    /// method to be called whenever `self.bezier_tolerance` changes.
-    /// it throws away all old Béziers and re‐creates them from every raw stroke.
-    pub fn refit_all_strokes(&mut self, bzr_tol: f64) {
-        self.beziers.clear();
-        let raw_strokes: Vec<Vec<Pos2>> = self.raw_strokes.iter().cloned().collect();
-        for raw in &raw_strokes {
-            self.fit_curve_and_store(raw, bzr_tol);
+    /// the outline as polylines within `tol` (world units) of the curves,
+    /// one per subpath, see `model::flatten`
+    pub fn flatten_subpaths(&self, tol: f64) -> Vec<Vec<KPoint>> {
+        self.subpath_ranges()
+            .into_iter()
+            .map(|range| model::flatten(&self.beziers[range], self.closed, tol))
+            .collect()
+    }
+
+    /// how often the outline (taken as closed) winds around `p` (world
+    /// space). `p` is inside if a `FillRule` says so for this number
+    pub fn winding(&self, p: KPoint) -> i32 {
+        let path: BezPath = if self.closed {
+            self.to_bez_path()
+        } else {
+            // every subpath gets its closing edge
+            self.subpath_ranges()
+                .into_iter()
+                .flat_map(|range| model::to_bez_path(&self.beziers[range], true))
+                .collect()
+        };
+        kurbo::Shape::winding(&path, p)
+    }
+
+    /// are the two ends of the path within `tol` (world units) of each other?
+    pub fn ends_meet(&self, tol: f64) -> bool {
+        match (self.beziers.first(), self.beziers.last()) {
+            (Some(first), Some(last)) => first.p0.distance(last.p3) <= tol,
+            _ => false,
         }
     }
 
-    pub fn draw_beziers(&self, painter: &Painter, app: &crate::Shaper) {
-        // we'll accumulate _all_ screen‐space points here:
-        let mut all_points: Vec<Pos2> = Vec::new();
-
-        // 1) loop each fitted CubicBez segment:
-        for (seg_idx, bzr) in self.beziers.iter().enumerate() {
-            // 1a) convert the four Kurbo control points into screen‐space Pos2:
-            let (w0, w1, w2, w3) = (bzr.p0, bzr.p1, bzr.p2, bzr.p3);
-            let s0 = app.world_to_screen(Pos2::new(w0.x as f32, w0.y as f32));
-            let s1 = app.world_to_screen(Pos2::new(w1.x as f32, w1.y as f32));
-            let s2 = app.world_to_screen(Pos2::new(w2.x as f32, w2.y as f32));
-            let s3 = app.world_to_screen(Pos2::new(w3.x as f32, w3.y as f32));
-
-            // 1b) build a temporary CubicBezierShape:
-            let bez_shape = CubicBezierShape {
-                points: [s0, s1, s2, s3],
-                closed: false,
-                stroke: Default::default(),
-                fill: Color32::TRANSPARENT,
-            };
-
-            // 1c) flatten this one cubic into straight‐line PathShapes:
-            //     - tol: Some(0.5) means “max error ~0.5px” (tweak for more/less fidelity)
-            //     - eps:  None   means “use the default epsilon internally”
-            let tol: Option<f32> = Some(0.5);
-            let eps: Option<f32> = None;
-            let mut sub_paths: Vec<PathShape> = bez_shape.to_path_shapes(tol, eps);
-
-            // 1d) each `PathShape` contains a `Vec<Pos2>` in `.points`.
-            //     if there are multiple PathShapes (rare—only when the curve intersects itself),
-            //     we stitch them all together in order. But we must avoid duplicating the joint
-            //     point between segment N and segment N+1. So:
-            for path_shape in sub_paths.drain(..) {
-                if seg_idx > 0 {
-                    // for every segment after the first, drop the very first point to avoid duplication:
-                    if let Some((_, tail)) = path_shape.points.split_first() {
-                        all_points.extend_from_slice(tail);
+    /// close the shape, snapping the end of every subpath onto its start
+    /// (the last handle moves along with it).
+    pub fn close(&mut self) {
+        if self.beziers.is_empty() {
+            return;
+        }
+        let ranges = self.subpath_ranges();
+        let beziers = self.beziers_mut();
+        for range in ranges {
+            let start = beziers[range.start].p0;
+            let last = &mut beziers[range.end - 1];
+            let delta = start - last.p3;
+            last.p3 = start;
+            last.p2 += delta;
+        }
+        self.closed = true;
+    }
+
+    /// move the whole shape (fitted segments and raw strokes) by `delta`
+    pub fn translate(&mut self, delta: Vec2) {
+        model::translate(self.beziers_mut(), delta);
+        let raw_delta = eframe::egui::vec2(delta.x as f32, delta.y as f32);
+        for stroke in &mut self.raw_strokes {
+            for p in stroke {
+                *p += raw_delta;
+            }
+        }
+    }
+
+    /// apply `affine` to the whole shape (fitted segments and raw strokes)
+    pub fn transform(&mut self, affine: Affine) {
+        for bez in self.beziers_mut() {
+            *bez = affine * *bez;
+        }
+        for stroke in &mut self.raw_strokes {
+            for p in stroke {
+                let q = affine * KPoint::new(p.x as f64, p.y as f64);
+                *p = Pos2::new(q.x as f32, q.y as f32);
+            }
+        }
+    }
+
+    /// `bez_idx:ctrl_idx` next to every point, for debugging the path
+    /// structure. an anchor two segments share is labelled by the one it
+    /// starts; only the end of an open subpath gets a `:3` label.
+    pub fn draw_point_labels(&self, painter: &Painter, app: &crate::Shaper) {
+        let font = FontId::monospace(10.0);
+        let color = Color32::from_gray(60);
+        let label = |p: KPoint, text: String| {
+            let pos = app.world_to_screen(Pos2::new(p.x as f32, p.y as f32));
+            let at = pos + eframe::egui::vec2(5.0, -5.0);
+            painter.text(at, Align2::LEFT_BOTTOM, text, font.clone(), color);
+        };
+        for range in self.subpath_ranges() {
+            for i in range.clone() {
+                let bez = self.beziers[i];
+                label(bez.p0, format!("{i}:0"));
+                label(bez.p1, format!("{i}:1"));
+                label(bez.p2, format!("{i}:2"));
+            }
+            if !self.closed {
+                let last = range.end - 1;
+                label(self.beziers[last].p3, format!("{last}:3"));
+            }
+        }
+    }
+
+    /// the outline flattened to screen space, one polyline per subpath so
+    /// disjoint parts don't get joined by a line. closing is left to the
+    /// caller (the end of a closed subpath sits on its start already).
+    fn screen_polylines(&self, app: &crate::Shaper) -> Vec<Vec<Pos2>> {
+        let mut polylines: Vec<Vec<Pos2>> = Vec::new();
+
+        for range in self.subpath_ranges() {
+            // we'll accumulate all screen‐space points of this subpath here:
+            let mut all_points: Vec<Pos2> = Vec::new();
+
+            // 1) loop each fitted CubicBez segment:
+            for (seg_idx, bzr) in self.beziers[range].iter().enumerate() {
+                // 1a) convert the four Kurbo control points into screen‐space Pos2:
+                let (w0, w1, w2, w3) = (bzr.p0, bzr.p1, bzr.p2, bzr.p3);
+                let s0 = app.world_to_screen(Pos2::new(w0.x as f32, w0.y as f32));
+                let s1 = app.world_to_screen(Pos2::new(w1.x as f32, w1.y as f32));
+                let s2 = app.world_to_screen(Pos2::new(w2.x as f32, w2.y as f32));
+                let s3 = app.world_to_screen(Pos2::new(w3.x as f32, w3.y as f32));
+
+                // 1b) build a temporary CubicBezierShape:
+                let bez_shape = CubicBezierShape {
+                    points: [s0, s1, s2, s3],
+                    closed: false,
+                    stroke: Default::default(),
+                    fill: Color32::TRANSPARENT,
+                };
+
+                // 1c) flatten this one cubic into straight‐line PathShapes:
+                //     - tol: Some(0.5) means “max error ~0.5px” (tweak for more/less fidelity)
+                //     - eps:  None   means “use the default epsilon internally”
+                let tol: Option<f32> = Some(0.5);
+                let eps: Option<f32> = None;
+                let mut sub_paths: Vec<PathShape> = bez_shape.to_path_shapes(tol, eps);
+
+                // 1d) each `PathShape` contains a `Vec<Pos2>` in `.points`.
+                //     if there are multiple PathShapes (rare—only when the curve intersects itself),
+                //     we stitch them all together in order. But we must avoid duplicating the joint
+                //     point between segment N and segment N+1. So:
+                for path_shape in sub_paths.drain(..) {
+                    if seg_idx > 0 {
+                        // for every segment after the first, drop the very first point to avoid duplication:
+                        if let Some((_, tail)) = path_shape.points.split_first() {
+                            all_points.extend_from_slice(tail);
+                        }
+                    } else {
+                        // for the first segment, take all points:
+                        all_points.extend(path_shape.points.iter());
                     }
-                } else {
-                    // for the first segment, take all points:
-                    all_points.extend(path_shape.points.iter());
                 }
             }
+            polylines.push(all_points);
         }
+        polylines
+    }
 
-        // now `all_points` is one continuous polyline in screen space. Stroke it once:
-        let stroke_width = self.thickness * app.zoom;
+    pub fn draw_beziers(&self, painter: &Painter, app: &crate::Shaper) {
+        let polylines = self.screen_polylines(app);
+        let points: usize = polylines.iter().map(Vec::len).sum();
+        app.flattened_points.set(app.flattened_points.get() + points);
+
+        // fill the interior first, so the stroke is drawn over its edge.
+        // egui only fills convex paths, so build the mesh ourselves
+        if self.closed && self.fill_color != Color32::TRANSPARENT {
+            painter.add(fill_mesh(&polylines, self.fill_rule, self.fill_color));
+        }
+
+        // now every polyline is one continuous line in screen space. Stroke each once:
+        let stroke_width = (self.thickness * app.zoom).max(MIN_SCREEN_STROKE_WIDTH);
         let stroke = Stroke::new(stroke_width, self.stroke_color);
-        painter.line(all_points, stroke);
+        for points in polylines {
+            if app.hq_strokes {
+                painter.add(stroke_mesh(&points, stroke_width, self.stroke_color, self.closed));
+            } else if self.closed {
+                painter.add(eframe::egui::Shape::closed_line(points, stroke));
+            } else {
+                painter.line(points, stroke);
+            }
+        }
     }
 
-    /// draw the *raw* strokes in thin green
+    /// draw the *raw* strokes, as thin lines in `original_stroke_color`
     pub fn draw_raw(&self, painter: &Painter, app: &crate::Shaper) {
         for segment in &self.raw_strokes {
             for window in segment.windows(2) {
                 let a = app.world_to_screen(window[0]);
                 let b = app.world_to_screen(window[1]);
-                painter.line_segment([a, b], Stroke::new(1.0 * app.zoom, Color32::GREEN));
+                painter.line_segment(
+                    [a, b],
+                    Stroke::new(app.original_stroke_width, app.original_stroke_color),
+                );
             }
         }
     }
 
-    /// draw control‐point handles (filled circles & red connecting lines)
+    /// draw control‐point handles (filled circles & red connecting lines),
+    /// the arms always below the points
+    /// the handle arms, the anchors (p0/p3) as `app.anchor_shape` and the
+    /// control points (p1/p2) as `app.control_shape`. all of it is the
+    /// same size on screen at any zoom
     pub fn draw_handles(&self, painter: &Painter, app: &crate::Shaper) {
-        let handle_border_radius = (app.handle_radius + 1.0) * app.zoom;
-        let handle_radius = app.handle_radius * app.zoom;
-        let p_color = app.p_color;
-        let cp_color = app.cp_color;
-        let p_border_color = app.p_border_color;
+        let radius = app.handle_radius;
+        let screen = |k: KPoint| app.world_to_screen(Pos2::new(k.x as f32, k.y as f32));
+        let arm = Stroke::new(app.handle_arm_thicknes, app.handle_arm_color);
+
+        // all the arms first: drawn per segment, the next segment's arm
+        // would cover the anchor it shares with this one
         for bez in &self.beziers {
-            let k0 = bez.p0;
-            let k1 = bez.p1;
-            let k2 = bez.p2;
-            let k3 = bez.p3;
-            let p0 = app.world_to_screen(Pos2::new(k0.x as f32, k0.y as f32));
-            let p1 = app.world_to_screen(Pos2::new(k1.x as f32, k1.y as f32));
-            let p2 = app.world_to_screen(Pos2::new(k2.x as f32, k2.y as f32));
-            let p3 = app.world_to_screen(Pos2::new(k3.x as f32, k3.y as f32));
-
-            painter.line_segment(
-                [p0, p1],
-                Stroke::new(app.handle_arm_thicknes * app.zoom, app.handle_arm_color),
-            );
-            // painter.line_segment([p1, p2], Stroke::new(app.handle_arm_thicknes * app.zoom, app.handle_arm_color)); // line connecting the 2 control points to one another (off for now)
-            painter.line_segment(
-                [p3, p2],
-                Stroke::new(app.handle_arm_thicknes * app.zoom, app.handle_arm_color),
-            );
-
-            // simple one color filled circle for all points
-            // painter.circle_filled(p0, handle_radius, p_color);
-            // painter.circle_filled(p1, handle_radius, cp_color);
-            // painter.circle_filled(p2, handle_radius, cp_color);
-            // painter.circle_filled(p3, handle_radius, p_color);
-
-            //alternatively:
-            // the control points as circles
-            // and the points themselves as squares
-
-            // first draw a rect slightly bigger (1 pixel)
-            // bigger than the actual rect
-            let p0_rect = Rect {
-                min: Pos2 { x: p0.x - handle_border_radius, y: p0.y - handle_border_radius },
-                max: Pos2 { x: p0.x + handle_border_radius, y: p0.y + handle_border_radius },
-            };
-            painter.rect_filled(p0_rect, 0.0, p_border_color);
-
-            let p3_rect = Rect {
-                min: Pos2 { x: p3.x - handle_border_radius, y: p3.y - handle_border_radius },
-                max: Pos2 { x: p3.x + handle_border_radius, y: p3.y + handle_border_radius },
-            };
-            painter.rect_filled(p3_rect, 0.0, p_border_color);
-
-
-
-
-            let p0_rect = Rect {
-                min: Pos2 { x: p0.x - handle_radius, y: p0.y - handle_radius },
-                max: Pos2 { x: p0.x + handle_radius, y: p0.y + handle_radius },
-            };
-            painter.rect_filled(p0_rect, 0.0, p_color);
-
-            let p3_rect = Rect {
-                min: Pos2 { x: p3.x - handle_radius, y: p3.y - handle_radius },
-                max: Pos2 { x: p3.x + handle_radius, y: p3.y + handle_radius },
-            };
-            painter.rect_filled(p3_rect, 0.0, p_color);
-
-
-            // control points:
-            // same for the control points
-            // first draw the border 1 pixel
-            // bigger and then the points
-            painter.circle_filled(p1, handle_border_radius, p_border_color);
-            painter.circle_filled(p2, handle_border_radius, p_border_color);
-
-            painter.circle_filled(p1, handle_radius, cp_color);
-            painter.circle_filled(p2, handle_radius, cp_color);
+            let (p0, p1, p2, p3) = (screen(bez.p0), screen(bez.p1), screen(bez.p2), screen(bez.p3));
+            painter.line_segment([p0, p1], arm);
+            // painter.line_segment([p1, p2], arm); // line connecting the 2 control points to one another (off for now)
+            painter.line_segment([p3, p2], arm);
+        }
+
+        // every point on a border 1 pixel bigger than itself
+        for bez in &self.beziers {
+            for (k, shape, color) in [
+                (bez.p0, app.anchor_shape, app.p_color),
+                (bez.p3, app.anchor_shape, app.p_color),
+                (bez.p1, app.control_shape, app.cp_color),
+                (bez.p2, app.control_shape, app.cp_color),
+            ] {
+                shape.paint(painter, screen(k), radius + 1.0, app.p_border_color);
+                shape.paint(painter, screen(k), radius, color);
+            }
         }
     }
 
     pub fn draw_overlay_beziers(&self, painter: &Painter, app: &crate::Shaper) {
-        // we'll accumulate _all_ screen‐space points here:
-        let mut all_points: Vec<Pos2> = Vec::new();
-
-
-
-        // 1) loop each fitted CubicBez segment:
-        for (seg_idx, bzr) in self.beziers.iter().enumerate() {
-            // 1a) convert the four Kurbo control points into screen‐space Pos2:
-            let (w0, w1, w2, w3) = (bzr.p0, bzr.p1, bzr.p2, bzr.p3);
-            let s0 = app.world_to_screen(Pos2::new(w0.x as f32, w0.y as f32));
-            let s1 = app.world_to_screen(Pos2::new(w1.x as f32, w1.y as f32));
-            let s2 = app.world_to_screen(Pos2::new(w2.x as f32, w2.y as f32));
-            let s3 = app.world_to_screen(Pos2::new(w3.x as f32, w3.y as f32));
-
-            // 1b) build a temporary CubicBezierShape:
-            let bez_shape = CubicBezierShape {
-                points: [s0, s1, s2, s3],
-                closed: false,
-                stroke: Default::default(),
-                fill: Color32::TRANSPARENT,
-            };
-
-            // 1c) flatten this one cubic into straight‐line PathShapes:
-            //     - tol: Some(0.5) means “max error ~0.5px” (tweak for more/less fidelity)
-            //     - eps:  None   means “use the default epsilon internally”
-            let tol: Option<f32> = Some(0.5);
-            let eps: Option<f32> = None;
-            let mut sub_paths: Vec<PathShape> = bez_shape.to_path_shapes(tol, eps);
-
-            // 1d) each `PathShape` contains a `Vec<Pos2>` in `.points`.
-            //     if there are multiple PathShapes (rare—only when the curve intersects itself),
-            //     we stitch them all together in order. But we must avoid duplicating the joint
-            //     point between segment N and segment N+1. So:
-            for path_shape in sub_paths.drain(..) {
-                if seg_idx > 0 {
-                    // for every segment after the first, drop the very first point to avoid duplication:
-                    if let Some((_, tail)) = path_shape.points.split_first() {
-                        all_points.extend_from_slice(tail);
-                    }
+        let polylines = self.screen_polylines(app);
+
+        // a thin line down the middle of the stroke, per subpath
+        let mut stroke_width = (self.thickness / 3.0) * app.zoom;
+        stroke_width = stroke_width
+            .min(app.overlay_beziers_thickness)
+            .max(MIN_SCREEN_STROKE_WIDTH);
+        let stroke = Stroke::new(stroke_width, Color32::WHITE);
+        for points in polylines {
+            if self.closed {
+                painter.add(eframe::egui::Shape::closed_line(points, stroke));
+            } else {
+                painter.line(points, stroke);
+            }
+        }
+    }
+}
+
+/// where the handle `other` goes so it's in line with `handle` on the
+/// opposite side of `anchor`, keeping its own length. a handle sitting on
+/// the anchor (no direction) leaves `other` alone.
+fn opposite_handle(anchor: KPoint, handle: KPoint, other: KPoint) -> KPoint {
+    let dir = anchor - handle;
+    let len = dir.hypot();
+    if len < 1e-9 {
+        return other;
+    }
+    anchor + dir * ((other - anchor).hypot() / len)
+}
+
+/// how far (in half widths) a miter may stick out at a sharp corner
+/// before it gets cut off
+const MITER_LIMIT: f32 = 4.0;
+
+/// width (screen px) of the fade to transparent along both edges of a
+/// `stroke_mesh`, which is what makes it look anti-aliased
+const STROKE_FEATHER: f32 = 1.0;
+
+/// a `width` wide stroke along the screen space polyline `points` as one
+/// mesh: a quad strip with mitered joins, plus a feathered edge on both
+/// sides. unlike egui's `line` the joints of thick strokes don't overlap
+/// or leave gaps, and the corners stay sharp (up to `MITER_LIMIT`).
+fn stroke_mesh(points: &[Pos2], width: f32, color: Color32, closed: bool) -> Mesh {
+    let mut pts: Vec<Pos2> = Vec::with_capacity(points.len());
+    for &p in points {
+        if pts.last().is_none_or(|&last: &Pos2| last.distance(p) > 1e-3) {
+            pts.push(p);
+        }
+    }
+    // a closed polyline ends on its start again, the wrap joins them
+    if closed && pts.len() > 2 && pts[0].distance(pts[pts.len() - 1]) <= 1e-3 {
+        pts.pop();
+    }
+
+    let mut mesh = Mesh::default();
+    let n = pts.len();
+    if n < 2 {
+        return mesh;
+    }
+
+    let half = width * 0.5;
+    let faded = Color32::TRANSPARENT;
+    let normal_of = |a: Pos2, b: Pos2| (b - a).normalized().rot90();
+
+    for i in 0..n {
+        let prev = if i > 0 { Some(i - 1) } else if closed { Some(n - 1) } else { None };
+        let next = if i + 1 < n { Some(i + 1) } else if closed { Some(0) } else { None };
+
+        // miter: the average of the normals of both edges, stretched so the
+        // stroke keeps its width along each of them
+        let offset = match (prev, next) {
+            (Some(a), Some(b)) => {
+                let (n_in, n_out) = (normal_of(pts[a], pts[i]), normal_of(pts[i], pts[b]));
+                let avg = (n_in + n_out).normalized();
+                let cos = avg.dot(n_in);
+                if avg == eframe::egui::Vec2::ZERO {
+                    // turns right back on itself
+                    n_in
+                } else if cos > 1.0 / MITER_LIMIT {
+                    avg / cos
                 } else {
-                    // for the first segment, take all points:
-                    all_points.extend(path_shape.points.iter());
+                    // too sharp, cut the miter off
+                    avg * MITER_LIMIT
                 }
             }
+            (None, Some(b)) => normal_of(pts[i], pts[b]),
+            (Some(a), None) => normal_of(pts[a], pts[i]),
+            (None, None) => unreachable!("n >= 2"),
+        };
+
+        let p = pts[i];
+        mesh.colored_vertex(p + offset * (half + STROKE_FEATHER), faded);
+        mesh.colored_vertex(p + offset * half, color);
+        mesh.colored_vertex(p - offset * half, color);
+        mesh.colored_vertex(p - offset * (half + STROKE_FEATHER), faded);
+    }
+
+    let edges = if closed { n } else { n - 1 };
+    for i in 0..edges {
+        let (a, b) = ((i * 4) as u32, (((i + 1) % n) * 4) as u32);
+        // outer feather, the stroke itself, inner feather
+        for band in 0..3 {
+            mesh.add_triangle(a + band, a + band + 1, b + band + 1);
+            mesh.add_triangle(a + band, b + band + 1, b + band);
         }
+    }
+    mesh
+}
 
-        // now `all_points` is one continuous polyline in screen space. Stroke it once:
-        let mut stroke_width = (self.thickness / 3.0) * app.zoom;
-        stroke_width = stroke_width.min(app.overlay_beziers_thickness);
-        let stroke = Stroke::new(stroke_width, Color32::WHITE);
-        painter.line(all_points, stroke);
+/// an edge of a filled outline, `top` above `bottom` on screen (smaller y).
+/// `dir` is +1 if the outline runs down along it, -1 if up
+#[derive(Clone, Copy)]
+struct FillEdge {
+    top: Pos2,
+    bottom: Pos2,
+    dir: i32,
+}
+
+impl FillEdge {
+    fn x_at(&self, y: f32) -> f32 {
+        self.top.x + (self.bottom.x - self.top.x) * (y - self.top.y) / (self.bottom.y - self.top.y)
+    }
+
+    /// the y where `self` and `other` cross, if they do (not at an end)
+    fn crossing_y(&self, other: &FillEdge) -> Option<f32> {
+        let (y0, y1) = (self.top.y.max(other.top.y), self.bottom.y.min(other.bottom.y));
+        if y0 >= y1 {
+            return None;
+        }
+        // which one is further right flips somewhere in between
+        let d0 = self.x_at(y0) - other.x_at(y0);
+        let d1 = self.x_at(y1) - other.x_at(y1);
+        (d0 * d1 < 0.0).then(|| y0 + (y1 - y0) * d0 / (d0 - d1))
     }
 }
 
-// keeping this for maybe later use if needed. previous implementations of the
-// rendering algorithm used this function to make up a quad of 2 triangles.
-// but that egui rasterises triangles drawn onto the painter, so the final
-// shape was not smooth at all. Opted for the built in egui cubic bezier render method
-#[allow(dead_code)]
-fn bezier_tangent(bzr: CubicBez, t: f64) -> Vec2 {
-    let u = 1.0_f64 - t;
-    let tt = t * t;
-    let uu = u * u;
-
-    let p0 = bzr.p0;
-    let p1 = bzr.p1;
-    let p2 = bzr.p2;
-    let p3 = bzr.p3;
-
-    let mut tangent: Vec2 = Vec2 {
-        x: -3.0 * uu * p0.x + 3.0 * uu * p1.x - 6.0 * u * t * p1.x + 6.0 * u * t * p2.x
-            - 3.0 * tt * p2.x
-            + 3.0 * tt * p3.x,
-        y: -3.0 * uu * p0.y + 3.0 * uu * p1.y - 6.0 * u * t * p1.y + 6.0 * u * t * p2.y
-            - 3.0 * tt * p2.y
-            + 3.0 * tt * p3.y,
-    };
-
-    // normalize the tangent vector to get the direction
-    let length: f64 = (tangent.x * tangent.x + tangent.y * tangent.y).sqrt();
-    tangent.x /= length;
-    tangent.y /= length;
-
-    tangent
+/// the interior of `polylines` (each one closed, the closing edge implied)
+/// by `rule`, as a mesh of trapezoids. the plane is cut into horizontal
+/// slabs at every vertex and every crossing of two edges: inside a slab
+/// no edges start, end or cross, so they can be sorted left to right and
+/// the winding number counted from one to the next. subpaths are counted
+/// together, so overlaps and holes follow the rule too.
+fn fill_mesh(polylines: &[Vec<Pos2>], rule: FillRule, color: Color32) -> Mesh {
+    let mut edges: Vec<FillEdge> = Vec::new();
+    for points in polylines.iter().filter(|points| points.len() >= 3) {
+        for (i, &a) in points.iter().enumerate() {
+            let b = points[(i + 1) % points.len()];
+            // horizontal edges don't change the winding of anything
+            if a.y < b.y {
+                edges.push(FillEdge { top: a, bottom: b, dir: 1 });
+            } else if a.y > b.y {
+                edges.push(FillEdge { top: b, bottom: a, dir: -1 });
+            }
+        }
+    }
+    edges.sort_by(|a, b| a.top.y.total_cmp(&b.top.y));
+
+    // the slab boundaries
+    let mut ys: Vec<f32> = edges.iter().flat_map(|e| [e.top.y, e.bottom.y]).collect();
+    for (i, a) in edges.iter().enumerate() {
+        // sorted by top, so the rest start below `a` once one does
+        for b in edges[i + 1..].iter().take_while(|b| b.top.y < a.bottom.y) {
+            ys.extend(a.crossing_y(b));
+        }
+    }
+    ys.sort_by(f32::total_cmp);
+    ys.dedup();
+
+    let mut mesh = Mesh::default();
+    let mut next_edge = 0;
+    let mut active: Vec<FillEdge> = Vec::new();
+    let mut crossing: Vec<(f32, FillEdge)> = Vec::new();
+    for slab in ys.windows(2) {
+        let (y0, y1) = (slab[0], slab[1]);
+        while next_edge < edges.len() && edges[next_edge].top.y <= y0 {
+            active.push(edges[next_edge]);
+            next_edge += 1;
+        }
+        active.retain(|e| e.bottom.y > y0);
+
+        // left to right through the middle of the slab
+        let mid = (y0 + y1) / 2.0;
+        crossing.clear();
+        crossing.extend(active.iter().map(|e| (e.x_at(mid), *e)));
+        crossing.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut winding = 0;
+        for pair in crossing.windows(2) {
+            let ((_, left), (_, right)) = (pair[0], pair[1]);
+            winding += left.dir;
+            if !rule.is_inside(winding) {
+                continue;
+            }
+            let base = mesh.vertices.len() as u32;
+            mesh.colored_vertex(Pos2::new(left.x_at(y0), y0), color);
+            mesh.colored_vertex(Pos2::new(right.x_at(y0), y0), color);
+            mesh.colored_vertex(Pos2::new(right.x_at(y1), y1), color);
+            mesh.colored_vertex(Pos2::new(left.x_at(y1), y1), color);
+            mesh.add_triangle(base, base + 1, base + 2);
+            mesh.add_triangle(base, base + 2, base + 3);
+        }
+    }
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODES: [HandleMode; 3] = [HandleMode::Free, HandleMode::Carry, HandleMode::Smooth];
+
+    /// three segments in a row along the x axis, handles a third of the way in
+    fn chain(closed: bool) -> Shape {
+        let mut shape = Shape::new(2.0, Color32::BLACK);
+        shape.set_beziers(
+            (0..3)
+                .map(|i| {
+                    let x = 30.0 * i as f64;
+                    CubicBez::new((x, 0.0), (x + 10.0, 5.0), (x + 20.0, -5.0), (x + 30.0, 0.0))
+                })
+                .collect(),
+        );
+        shape.closed = closed;
+        if closed {
+            // bring the end round to the start so the chain is a loop
+            shape.set_ctrl(2, 3, KPoint::ZERO, HandleMode::Free);
+        }
+        shape
+    }
+
+    fn assert_near(a: KPoint, b: KPoint) {
+        assert!((a - b).hypot() < 1e-9, "{a:?} != {b:?}");
+    }
+
+    /// true when `a` and `b` point in opposite directions away from `anchor`
+    fn in_line(anchor: KPoint, a: KPoint, b: KPoint) -> bool {
+        (a - anchor).cross(b - anchor).abs() < 1e-9 && (a - anchor).dot(b - anchor) < 0.0
+    }
+
+    /// whether one of the triangles of `mesh` has `p` inside
+    fn covers(mesh: &Mesh, p: Pos2) -> bool {
+        mesh.indices.chunks(3).any(|tri| {
+            let [a, b, c] = [0, 1, 2].map(|i| mesh.vertices[tri[i] as usize].pos);
+            let side = |u: Pos2, v: Pos2| (v - u).x * (p - u).y - (v - u).y * (p - u).x;
+            let (d0, d1, d2) = (side(a, b), side(b, c), side(c, a));
+            (d0 >= 0.0 && d1 >= 0.0 && d2 >= 0.0) || (d0 <= 0.0 && d1 <= 0.0 && d2 <= 0.0)
+        })
+    }
+
+    #[test]
+    fn an_imported_figure_eight_fills_its_overlap_by_the_fill_rule() {
+        // two loops of one path, going round the same way: the square
+        // they share is inside both
+        let src = r#"<path d="M0 0 H100 V100 H0 Z M50 50 H150 V150 H50 Z"/>"#;
+        let mut shapes = Vec::new();
+        crate::io::svg::import_svg(src, &mut shapes).unwrap();
+        let polylines: Vec<Vec<Pos2>> = shapes[0]
+            .flatten_subpaths(0.1)
+            .into_iter()
+            .map(|points| points.iter().map(|p| Pos2::new(p.x as f32, p.y as f32)).collect())
+            .collect();
+
+        let (overlap, single) = (Pos2::new(75.0, 75.0), Pos2::new(25.0, 25.0));
+        let nonzero = fill_mesh(&polylines, FillRule::NonZero, Color32::RED);
+        assert!(covers(&nonzero, overlap) && covers(&nonzero, single));
+        let evenodd = fill_mesh(&polylines, FillRule::EvenOdd, Color32::RED);
+        assert!(!covers(&evenodd, overlap) && covers(&evenodd, single));
+    }
+
+    #[test]
+    fn every_subpath_is_drawn_as_its_own_polyline() {
+        let app = crate::Shaper::default();
+        let mut shape = Shape::new(2.0, Color32::BLACK);
+        for y in [0.0, 50.0] {
+            shape.start_subpath();
+            shape.beziers_mut().push(model::straight_cubic(
+                KPoint::new(0.0, y),
+                KPoint::new(100.0, y),
+            ));
+        }
+
+        let polylines = shape.screen_polylines(&app);
+        assert_eq!(polylines.len(), 2);
+        for (points, y) in polylines.iter().zip([0.0, 50.0]) {
+            // no line across from one subpath to the other
+            let start = app.world_to_screen(Pos2::new(0.0, y));
+            let end = app.world_to_screen(Pos2::new(100.0, y));
+            assert_eq!(points.first(), Some(&start));
+            assert!(points.last().unwrap().distance(end) < 1e-3);
+            assert!(points.iter().all(|p| (p.y - start.y).abs() < 1e-3));
+        }
+    }
+
+    #[test]
+    fn moving_an_anchor_moves_the_shared_end() {
+        let delta = Vec2::new(3.0, 4.0);
+        for mode in MODES {
+            let carry = mode != HandleMode::Free;
+            let before = chain(false);
+            let b = before.beziers.clone();
+
+            let mut shape = before.clone();
+            shape.set_ctrl(1, 0, b[1].p0 + delta, mode);
+            let s = &shape.beziers;
+            assert_near(s[1].p0, b[1].p0 + delta);
+            assert_near(s[0].p3, b[1].p0 + delta);
+            let moved = if carry { delta } else { Vec2::ZERO };
+            assert_near(s[1].p1, b[1].p1 + moved);
+            assert_near(s[0].p2, b[0].p2 + moved);
+            assert_eq!((s[1].p2, s[1].p3), (b[1].p2, b[1].p3), "{mode:?}");
+
+            let mut shape = before.clone();
+            shape.set_ctrl(1, 3, b[1].p3 + delta, mode);
+            let s = &shape.beziers;
+            assert_near(s[1].p3, b[1].p3 + delta);
+            assert_near(s[2].p0, b[1].p3 + delta);
+            assert_near(s[1].p2, b[1].p2 + moved);
+            assert_near(s[2].p1, b[2].p1 + moved);
+            assert_eq!((s[1].p0, s[1].p1), (b[1].p0, b[1].p1), "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn moving_a_handle_turns_the_other_one_only_when_smooth() {
+        let p = KPoint::new(40.0, 20.0);
+        for mode in MODES {
+            let smooth = mode == HandleMode::Smooth;
+            let before = chain(false);
+            let b = before.beziers.clone();
+
+            let mut shape = before.clone();
+            shape.set_ctrl(1, 1, p, mode);
+            let s = &shape.beziers;
+            assert_eq!(s[1].p1, p);
+            assert_eq!((s[1].p0, s[0].p3), (b[1].p0, b[0].p3));
+            if smooth {
+                assert!(in_line(s[1].p0, s[1].p1, s[0].p2));
+                let len = (b[0].p2 - b[0].p3).hypot();
+                assert!(((s[0].p2 - s[0].p3).hypot() - len).abs() < 1e-9);
+            } else {
+                assert_eq!(s[0].p2, b[0].p2, "{mode:?}");
+            }
+
+            let mut shape = before.clone();
+            shape.set_ctrl(1, 2, p, mode);
+            let s = &shape.beziers;
+            assert_eq!(s[1].p2, p);
+            assert_eq!((s[1].p3, s[2].p0), (b[1].p3, b[2].p0));
+            if smooth {
+                assert!(in_line(s[1].p3, s[1].p2, s[2].p1));
+                let len = (b[2].p1 - b[2].p0).hypot();
+                assert!(((s[2].p1 - s[2].p0).hypot() - len).abs() < 1e-9);
+            } else {
+                assert_eq!(s[2].p1, b[2].p1, "{mode:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn smooth_leaves_corners_alone() {
+        let mut shape = chain(false);
+        shape.corners = vec![1, 2];
+        let b = shape.beziers.clone();
+        shape.set_ctrl(1, 1, KPoint::new(40.0, 20.0), HandleMode::Smooth);
+        shape.set_ctrl(1, 2, KPoint::new(50.0, 20.0), HandleMode::Smooth);
+        assert_eq!((shape.beziers[0], shape.beziers[2]), (b[0], b[2]));
+    }
+
+    #[test]
+    fn deleting_an_anchor_moves_the_corners_after_it_down() {
+        let mut shape = chain(false);
+        shape.corners = vec![1, 2];
+        shape.delete_anchor(1);
+        assert_eq!(shape.corners, vec![1]);
+        assert_eq!(shape.beziers[1].p0, KPoint::new(60.0, 0.0));
+
+        // the last segment's start becomes the first one's
+        let mut shape = chain(true);
+        shape.corners = vec![0, 2];
+        shape.delete_anchor(0);
+        assert_eq!(shape.corners, vec![0]);
+        assert_eq!(shape.beziers[0].p0, KPoint::new(60.0, 0.0));
+    }
+
+    #[test]
+    fn open_ends_have_no_neighbor() {
+        let delta = Vec2::new(3.0, 4.0);
+        for mode in MODES {
+            let before = chain(false);
+            let b = before.beziers.clone();
+            let mut shape = before.clone();
+            shape.set_ctrl(0, 0, b[0].p0 + delta, mode);
+            shape.set_ctrl(0, 1, KPoint::new(-5.0, 5.0), mode);
+            shape.set_ctrl(2, 3, b[2].p3 + delta, mode);
+            shape.set_ctrl(2, 2, KPoint::new(95.0, 5.0), mode);
+            assert_eq!(shape.beziers[1], b[1], "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn closed_shapes_wrap_around() {
+        let delta = Vec2::new(3.0, 4.0);
+        for mode in MODES {
+            let carry = mode != HandleMode::Free;
+            let moved = if carry { delta } else { Vec2::ZERO };
+            let before = chain(true);
+            let b = before.beziers.clone();
+
+            // the first anchor is also the end of the last segment
+            let mut shape = before.clone();
+            shape.set_ctrl(0, 0, b[0].p0 + delta, mode);
+            let s = &shape.beziers;
+            assert_near(s[2].p3, b[0].p0 + delta);
+            assert_near(s[2].p2, b[2].p2 + moved);
+            assert_eq!(s[1], b[1]);
+
+            // and the other way round
+            let mut shape = before.clone();
+            shape.set_ctrl(2, 3, b[2].p3 + delta, mode);
+            let s = &shape.beziers;
+            assert_near(s[0].p0, b[2].p3 + delta);
+            assert_near(s[0].p1, b[0].p1 + moved);
+            assert_eq!(s[1], b[1]);
+
+            let mut shape = before.clone();
+            shape.set_ctrl(0, 1, KPoint::new(10.0, 20.0), mode);
+            let s = &shape.beziers;
+            if mode == HandleMode::Smooth {
+                assert!(in_line(s[0].p0, s[0].p1, s[2].p2));
+            } else {
+                assert_eq!(s[2].p2, b[2].p2, "{mode:?}");
+            }
+
+            let mut shape = before.clone();
+            shape.set_ctrl(2, 2, KPoint::new(10.0, 20.0), mode);
+            let s = &shape.beziers;
+            if mode == HandleMode::Smooth {
+                assert!(in_line(s[2].p3, s[2].p2, s[0].p1));
+            } else {
+                assert_eq!(s[0].p1, b[0].p1, "{mode:?}");
+            }
+        }
+    }
 }
@@ -0,0 +1,766 @@
+use std::collections::HashMap;
+
+use eframe::egui::Color32;
+use kurbo::{Affine, CubicBez, Line, Point as KPoint, Rect};
+
+use crate::selection::PointId;
+use crate::{Shape, Shaper, model};
+
+/// how `Shaper::array_along_path` spreads the copies over the path
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ArraySpacing {
+    /// from one end of the path to the other (once around a closed one)
+    Fit,
+    /// this far apart (world units) from the start of the path, as many
+    /// as fit on it
+    Fixed(f64),
+}
+
+impl Shaper {
+    /// laplacian smoothing of the anchors of `shapes[shape_idx]` that lie
+    /// within `radius` (world units) of `center`, see `model::smooth_anchors`.
+    /// every subpath on its own, the ends of one never move towards another
+    pub fn smooth_region(&mut self, shape_idx: usize, center: KPoint, radius: f64, strength: f64) {
+        if let Some(shape) = self.shapes.get_mut(shape_idx) {
+            for range in shape.subpath_ranges() {
+                model::smooth_anchors(&mut shape.beziers_mut()[range], center, radius, strength);
+            }
+        }
+    }
+
+    /// cut every shape the straight line `a`-`b` (world) crosses at the
+    /// crossings, each piece becoming its own open shape (with the style of
+    /// the original, but no fill and no raw strokes, those don't fit the
+    /// pieces anymore). subpaths are cut one by one, a piece never runs from
+    /// one into the next; the subpaths the line misses stay together in one
+    /// shape, as they were. returns how many shapes were cut.
+    pub fn cut_shapes_with_line(&mut self, a: KPoint, b: KPoint) -> usize {
+        let line = Line::new(a, b);
+        let mut cut_count = 0;
+        let mut new_shapes: Vec<Shape> = Vec::with_capacity(self.shapes.len());
+        let mut index_map: HashMap<usize, Option<usize>> = HashMap::new();
+        // shapes left with fewer subpaths, their old point ids are stale
+        let mut shrunk: Vec<usize> = Vec::new();
+
+        for (old_idx, shape) in std::mem::take(&mut self.shapes).into_iter().enumerate() {
+            let ranges = shape.subpath_ranges();
+            let cuts: Vec<Vec<(usize, f64)>> = ranges
+                .iter()
+                .map(|range| model::line_crossings(&shape.beziers[range.clone()], line))
+                .collect();
+            if cuts.iter().all(Vec::is_empty) {
+                index_map.insert(old_idx, Some(new_shapes.len()));
+                new_shapes.push(shape);
+                continue;
+            }
+
+            cut_count += 1;
+            let mut pieces: Vec<Vec<CubicBez>> = Vec::new();
+            let mut uncut = shape.clone();
+            uncut.set_beziers(Vec::new());
+            uncut.subpath_starts.clear();
+            uncut.raw_strokes.clear();
+            for (range, cuts) in ranges.into_iter().zip(&cuts) {
+                let subpath = &shape.beziers[range];
+                if cuts.is_empty() {
+                    uncut.start_subpath();
+                    uncut.beziers_mut().extend_from_slice(subpath);
+                } else {
+                    pieces.extend(model::cut_chain(subpath, shape.closed, cuts));
+                }
+            }
+
+            // what the line missed keeps the index (and so the selection)
+            if uncut.beziers.is_empty() {
+                index_map.insert(old_idx, None);
+            } else {
+                index_map.insert(old_idx, Some(new_shapes.len()));
+                shrunk.push(new_shapes.len());
+                new_shapes.push(uncut);
+            }
+            for (i, piece) in pieces.into_iter().enumerate() {
+                let mut part = Shape::new(shape.thickness, shape.stroke_color);
+                part.name = format!("{} ({})", shape.name, i + 1);
+                part.set_beziers(piece);
+                new_shapes.push(part);
+            }
+        }
+
+        self.shapes = new_shapes;
+        self.remap_selection(&index_map);
+        for idx in shrunk {
+            self.forget_points(idx);
+        }
+        cut_count
+    }
+
+    /// the in-between of `shapes[a]` and `shapes[b]` at `t` (0 = a, 1 = b):
+    /// control points, thickness and colors are interpolated. both need the
+    /// same number of segments (see `refit_segment_count`), `None` otherwise.
+    pub fn interpolate_shapes(&self, a: usize, b: usize, t: f64) -> Option<Shape> {
+        let (sa, sb) = (self.shapes.get(a)?, self.shapes.get(b)?);
+        let beziers = model::lerp_chains(&sa.beziers, &sb.beziers, t)?;
+
+        let tf = t as f32;
+        let mut shape = Shape::new(
+            sa.thickness + (sb.thickness - sa.thickness) * tf,
+            sa.stroke_color.lerp_to_gamma(sb.stroke_color, tf),
+        );
+        shape.set_beziers(beziers);
+        shape.closed = sa.closed && sb.closed;
+        if shape.closed {
+            shape.fill_color = sa.fill_color.lerp_to_gamma(sb.fill_color, tf);
+            shape.fill_rule = sa.fill_rule;
+        }
+        Some(shape)
+    }
+
+    /// re-fit `shapes[shape_idx]` to exactly `n` segments, from its current
+    /// outline (so it works for shapes without raw strokes too).
+    pub fn refit_segment_count(&mut self, shape_idx: usize, n: usize) {
+        // fine enough that the refit doesn't visibly lose detail
+        const FLATTEN_TOL: f64 = 0.1;
+
+        let Some(shape) = self.shapes.get_mut(shape_idx) else {
+            return;
+        };
+        // the outline would run across the gaps between subpaths
+        if !shape.subpath_starts.is_empty() {
+            return;
+        }
+        let outline = model::flatten(&shape.beziers, shape.closed, FLATTEN_TOL);
+        let refit = model::fit_segment_count(&outline, n);
+        if refit.is_empty() {
+            return;
+        }
+        shape.set_beziers(refit);
+        // the old point ids of this shape don't exist anymore
+        self.forget_points(shape_idx);
+    }
+
+    /// delete every selected point. anchors are removed (merging the
+    /// segments around them, see `Shape::delete_anchor`), handles are put
+    /// back on the chord of their segment. locked anchors stay, the locks
+    /// of the anchors left are kept. shapes left without segments are
+    /// removed. clears `selected_points`.
+    pub fn delete_selected_points(&mut self) {
+        // handles first, they don't change any indices
+        let mut anchors: Vec<(usize, usize)> = Vec::new();
+        for id in std::mem::take(&mut self.selected_points) {
+            if self.is_locked(id) {
+                continue;
+            }
+            let Some(shape) = self.shapes.get_mut(id.shape_idx) else {
+                continue;
+            };
+            let (seg_count, closed) = (shape.beziers.len(), shape.closed);
+            let single_chain = shape.subpath_starts.is_empty();
+            let Some(bez) = shape.beziers_mut().get_mut(id.bez_idx) else {
+                continue;
+            };
+            match id.ctrl_idx {
+                1 => bez.p1 = bez.p0.lerp(bez.p3, 1.0 / 3.0),
+                2 => bez.p2 = bez.p0.lerp(bez.p3, 2.0 / 3.0),
+                // merging across subpaths isn't supported (yet)
+                _ if !single_chain => {}
+                _ => {
+                    // anchors by their index along the chain;
+                    // the end of a closed chain is its start
+                    let k = id.bez_idx + usize::from(id.ctrl_idx == 3);
+                    let k = if closed && k == seg_count { 0 } else { k };
+                    anchors.push((id.shape_idx, k));
+                }
+            }
+        }
+
+        // highest anchor first, so the lower indices stay valid
+        anchors.sort_unstable_by(|a, b| b.cmp(a));
+        anchors.dedup();
+        // the anchors after a deleted one shift down, their locks are
+        // found again by position afterwards
+        let mut locked_at: Vec<(usize, Vec<KPoint>)> = Vec::new();
+        for &(shape_idx, _) in &anchors {
+            if locked_at.last().is_none_or(|(idx, _)| *idx != shape_idx) {
+                locked_at.push((shape_idx, self.locked_positions(shape_idx)));
+            }
+        }
+        let mut emptied: Vec<usize> = Vec::new();
+        for (shape_idx, k) in anchors {
+            let shape = &mut self.shapes[shape_idx];
+            shape.delete_anchor(k);
+            if shape.beziers.is_empty() && !emptied.contains(&shape_idx) {
+                emptied.push(shape_idx);
+            }
+        }
+
+        for (shape_idx, positions) in locked_at {
+            self.forget_points(shape_idx);
+            self.lock_anchors_at(shape_idx, &positions);
+        }
+
+        // sorted high to low already, like the anchors
+        for shape_idx in emptied {
+            self.remove_shape(shape_idx);
+        }
+    }
+
+    /// give every selected shape the style of `shapes[src]`: stroke
+    /// thickness and color, fill color and rule. the geometry stays
+    pub fn copy_style_from(&mut self, src: usize) {
+        let Some(source) = self.shapes.get(src) else {
+            return;
+        };
+        let (thickness, stroke_color) = (source.thickness, source.stroke_color);
+        let (fill_color, fill_rule) = (source.fill_color, source.fill_rule);
+        for &idx in &self.selected_shapes {
+            if let Some(shape) = self.shapes.get_mut(idx) {
+                shape.thickness = thickness;
+                shape.stroke_color = stroke_color;
+                shape.fill_color = fill_color;
+                shape.fill_rule = fill_rule;
+            }
+        }
+    }
+
+    /// remove every selected shape
+    pub fn delete_selected_shapes(&mut self) {
+        // highest index first, so the remaining indices stay valid
+        let mut selected: Vec<usize> = self.selected_shapes.iter().copied().collect();
+        selected.sort_unstable_by(|a, b| b.cmp(a));
+        for shape_idx in selected {
+            self.remove_shape(shape_idx);
+        }
+    }
+
+    /// merge the selected shapes into one shape, each becoming a subpath
+    /// (in drawing order, see `Shape::subpath_starts`). the result takes the
+    /// name, style and place of the bottom-most one, and is selected.
+    /// it's only closed if all of them were. raw strokes are kept if every
+    /// shape has one per subpath, otherwise a refit would drop the others.
+    pub fn combine_selected_into_subpaths(&mut self) {
+        let mut indices: Vec<usize> = self
+            .selected_shapes
+            .iter()
+            .copied()
+            .filter(|&idx| idx < self.shapes.len())
+            .collect();
+        if indices.len() < 2 {
+            return;
+        }
+        indices.sort_unstable();
+
+        let bottom = indices[0];
+        let mut combined = Shape::new(self.shapes[bottom].thickness, self.shapes[bottom].stroke_color);
+        combined.name = self.shapes[bottom].name.clone();
+        combined.fill_color = self.shapes[bottom].fill_color;
+        combined.fill_rule = self.shapes[bottom].fill_rule;
+        combined.closed = indices.iter().all(|&idx| self.shapes[idx].closed);
+
+        let mut keep_raw = true;
+        for &idx in &indices {
+            let shape = &self.shapes[idx];
+            if shape.beziers.is_empty() {
+                continue;
+            }
+            let offset = combined.beziers.len();
+            if offset > 0 {
+                combined.subpath_starts.push(offset);
+            }
+            combined
+                .subpath_starts
+                .extend(shape.subpath_starts.iter().map(|start| start + offset));
+            combined.corners.extend(shape.corners.iter().map(|k| k + offset));
+            combined.beziers_mut().extend_from_slice(&shape.beziers);
+            combined.raw_strokes.extend(shape.raw_strokes.iter().cloned());
+            keep_raw &= shape.raw_strokes.len() == shape.subpath_ranges().len();
+        }
+        if !keep_raw {
+            combined.raw_strokes.clear();
+        }
+
+        // the others go, highest first so the lower indices stay valid
+        for &idx in &indices {
+            self.forget_points(idx);
+        }
+        for &idx in indices[1..].iter().rev() {
+            self.remove_shape(idx);
+        }
+        self.shapes[bottom] = combined;
+        self.select_single_shape(bottom);
+    }
+
+    /// make every anchor of `shapes[shape_idx]` smooth without moving any
+    /// of them (see `model::retangent_smooth`), one subpath at a time.
+    /// turns a jagged fit into a flowing curve.
+    pub fn beautify_shape(&mut self, shape_idx: usize) {
+        let Some(shape) = self.shapes.get_mut(shape_idx) else {
+            return;
+        };
+        let (ranges, closed) = (shape.subpath_ranges(), shape.closed);
+        let beziers = shape.beziers_mut();
+        for range in ranges {
+            model::retangent_smooth(&mut beziers[range], closed);
+        }
+    }
+
+    /// add `count` copies of `shapes[shape_idx]` spread along the outline of
+    /// `shapes[path_idx]` by arc length, each centered (bounding box) on its
+    /// spot. with `align` they are turned to follow the path's direction,
+    /// the original's orientation counting as pointing right.
+    /// returns the indices of the copies.
+    pub fn array_along_path(
+        &mut self,
+        shape_idx: usize,
+        path_idx: usize,
+        count: usize,
+        spacing: ArraySpacing,
+        align: bool,
+    ) -> Vec<usize> {
+        let (Some(shape), Some(path)) = (self.shapes.get(shape_idx), self.shapes.get(path_idx))
+        else {
+            return Vec::new();
+        };
+        let Some(center) = shape.bounding_box().map(|bbox| bbox.center()) else {
+            return Vec::new();
+        };
+
+        let length = model::chain_length(&path.beziers);
+        let distances: Vec<f64> = match spacing {
+            // a closed path's end is its start, so that spot isn't taken twice
+            ArraySpacing::Fit if path.closed => (0..count)
+                .map(|i| length * i as f64 / count as f64)
+                .collect(),
+            ArraySpacing::Fit if count == 1 => vec![0.0],
+            ArraySpacing::Fit => (0..count)
+                .map(|i| length * i as f64 / (count - 1) as f64)
+                .collect(),
+            ArraySpacing::Fixed(step) => (0..count).map(|i| step * i as f64).collect(),
+        };
+
+        let mut copies = Vec::new();
+        for (p, tangent) in model::frames_at(&path.beziers, &distances) {
+            let mut copy = shape.clone();
+            copy.name.clear();
+            let mut affine = Affine::translate(p - center);
+            if align {
+                affine = Affine::rotate_about(tangent.atan2(), p) * affine;
+            }
+            copy.transform(affine);
+            copies.push(copy);
+        }
+        copies.into_iter().map(|copy| self.add_shape(copy)).collect()
+    }
+
+    /// put p1/p2 of the segment at 1/3 and 2/3 of the way from p0 to p3,
+    /// so it's visually a straight line (but still a cubic)
+    pub fn straighten_segment(&mut self, shape_idx: usize, bez_idx: usize) {
+        if let Some(bez) = self
+            .shapes
+            .get_mut(shape_idx)
+            .and_then(|shape| shape.beziers_mut().get_mut(bez_idx))
+        {
+            *bez = model::straight_cubic(bez.p0, bez.p3);
+        }
+    }
+
+    /// clean up the straight runs of a shape: remove every anchor whose two
+    /// segments are nearly straight and continue each other within
+    /// `angle_tol` radians, merging them into one straight segment (see
+    /// `model::remove_collinear_anchors`). unlike a refit nothing else
+    /// moves. locked anchors stay. returns how many anchors were removed.
+    pub fn remove_redundant_anchors(&mut self, shape_idx: usize, angle_tol: f64) -> usize {
+        let Some(shape) = self.shapes.get(shape_idx) else {
+            return 0;
+        };
+        let locked = |bez_idx: usize| {
+            let id = PointId {
+                shape_idx,
+                bez_idx,
+                ctrl_idx: 0,
+            };
+            self.locked_points.contains(&id)
+        };
+        let mut beziers: Vec<CubicBez> = Vec::with_capacity(shape.beziers.len());
+        let mut subpath_starts: Vec<usize> = Vec::new();
+        for range in shape.subpath_ranges() {
+            if !beziers.is_empty() {
+                subpath_starts.push(beziers.len());
+            }
+            let start = range.start;
+            beziers.extend(model::remove_collinear_anchors(
+                &shape.beziers[range],
+                shape.closed,
+                angle_tol,
+                |k| locked(start + k),
+            ));
+        }
+        let removed = shape.beziers.len() - beziers.len();
+        if removed == 0 {
+            return 0;
+        }
+
+        // the anchors left didn't move, find the locked ones again by position
+        let locked_at = self.locked_positions(shape_idx);
+        self.forget_points(shape_idx);
+        let shape = &mut self.shapes[shape_idx];
+        shape.set_beziers(beziers);
+        shape.subpath_starts = subpath_starts;
+        self.lock_anchors_at(shape_idx, &locked_at);
+        removed
+    }
+
+    /// where the locked anchors of `shapes[shape_idx]` are, to find them
+    /// again with `lock_anchors_at` after its segments were rebuilt
+    fn locked_positions(&self, shape_idx: usize) -> Vec<KPoint> {
+        self.locked_points
+            .iter()
+            .filter(|id| id.shape_idx == shape_idx)
+            .filter_map(|&id| self.point_pos(id))
+            .collect()
+    }
+
+    /// lock the anchors of `shapes[shape_idx]` that sit exactly at one of
+    /// `positions` (see `locked_positions`)
+    fn lock_anchors_at(&mut self, shape_idx: usize, positions: &[KPoint]) {
+        let Some(shape) = self.shapes.get(shape_idx) else {
+            return;
+        };
+        for &pos in positions {
+            let anchor = (0..shape.beziers.len())
+                .find(|&i| shape.beziers[i].p0 == pos)
+                .map(|bez_idx| (bez_idx, 0))
+                .or_else(|| {
+                    // the end of an open subpath
+                    (0..shape.beziers.len())
+                        .find(|&i| shape.beziers[i].p3 == pos)
+                        .map(|bez_idx| (bez_idx, 3))
+                });
+            if let Some((bez_idx, ctrl_idx)) = anchor {
+                self.locked_points.insert(PointId {
+                    shape_idx,
+                    bez_idx,
+                    ctrl_idx,
+                });
+            }
+        }
+    }
+
+    /// recompute the handles of the anchor `id` belongs to (a handle
+    /// belongs to the anchor at its end of the segment) from the two
+    /// neighboring anchors: both arms point along the Catmull-Rom tangent
+    /// (next - prev), each a third of its segment's chord long. at the end
+    /// of an open subpath the one handle points at the neighbor instead.
+    /// undoes lumpy manual handle edits without refitting the stroke.
+    pub fn auto_handles(&mut self, id: PointId) {
+        let Some(shape) = self.shapes.get_mut(id.shape_idx) else {
+            return;
+        };
+        if id.bez_idx >= shape.beziers.len() {
+            return;
+        }
+        // the segments coming into / going out of the anchor
+        let (incoming, outgoing) = match id.ctrl_idx {
+            0 | 1 => (shape.prev_segment(id.bez_idx), Some(id.bez_idx)),
+            _ => (Some(id.bez_idx), shape.next_segment(id.bez_idx)),
+        };
+        let beziers = shape.beziers_mut();
+        let anchor = match outgoing {
+            Some(o) => beziers[o].p0,
+            None => beziers[id.bez_idx].p3,
+        };
+        let prev = incoming.map(|i| beziers[i].p0);
+        let next = outgoing.map(|o| beziers[o].p3);
+        let tangent = match (prev, next) {
+            (Some(prev), Some(next)) => next - prev,
+            (Some(prev), None) => anchor - prev,
+            (None, Some(next)) => next - anchor,
+            (None, None) => return,
+        };
+        // neighbors on top of each other: no direction to go by
+        let len = tangent.hypot();
+        if len < 1e-9 {
+            return;
+        }
+        let dir = tangent / len;
+        if let (Some(i), Some(prev)) = (incoming, prev) {
+            beziers[i].p2 = anchor - dir * (anchor.distance(prev) / 3.0);
+        }
+        if let (Some(o), Some(next)) = (outgoing, next) {
+            beziers[o].p1 = anchor + dir * (anchor.distance(next) / 3.0);
+        }
+    }
+
+    /// add `curr_shape` as a new shape, with the stroke being drawn fit
+    /// with `tol` and `straight_tol` (see `model::fit_curve`) as its last
+    /// subpath (the strokes before it already are, see the drawing tool's
+    /// compound strokes). `curr_shape` starts over
+    /// either way (keeping its style). returns the new shape's index,
+    /// `None` if there was nothing to add.
+    pub fn commit_current_shape(&mut self, tol: f64, straight_tol: f64) -> Option<usize> {
+        let fresh = Shape::new(self.curr_shape.thickness, self.curr_shape.stroke_color);
+        let mut shape = std::mem::replace(&mut self.curr_shape, fresh);
+        let stroke = std::mem::take(&mut shape.current_stroke);
+        if stroke.len() >= 2 {
+            shape.start_subpath();
+            shape.fit_curve_and_store(&stroke, tol, straight_tol);
+            shape.raw_strokes.push(stroke);
+        }
+        if shape.beziers.is_empty() {
+            return None;
+        }
+        Some(self.add_shape(shape))
+    }
+
+    /// add a shape made of `beziers` (world space) with the given stroke,
+    /// going through `add_shape` so it gets a name. returns its index.
+    /// the shape has no raw strokes, so refitting leaves it alone.
+    pub fn add_shape_from_beziers(
+        &mut self,
+        beziers: Vec<CubicBez>,
+        thickness: f32,
+        color: Color32,
+    ) -> usize {
+        let mut shape = Shape::new(thickness, color);
+        shape.set_beziers(beziers);
+        self.add_shape(shape)
+    }
+
+    /// a single straight segment from `a` to `b`, see `add_shape_from_beziers`
+    #[allow(dead_code)] // for embedding/scripting, lines are drawn into `curr_shape`
+    pub fn add_line(&mut self, a: KPoint, b: KPoint, thickness: f32, color: Color32) -> usize {
+        self.add_shape_from_beziers(vec![model::straight_cubic(a, b)], thickness, color)
+    }
+
+    /// a closed rectangle outline, see `add_shape_from_beziers`
+    pub fn add_rect(&mut self, rect: Rect, thickness: f32, color: Color32) -> usize {
+        let idx = self.add_shape_from_beziers(model::rect_cubics(rect), thickness, color);
+        self.shapes[idx].closed = true;
+        idx
+    }
+
+    /// a closed axis aligned ellipse outline, see `add_shape_from_beziers`
+    pub fn add_ellipse(
+        &mut self,
+        center: KPoint,
+        rx: f64,
+        ry: f64,
+        thickness: f32,
+        color: Color32,
+    ) -> usize {
+        let idx = self.add_shape_from_beziers(model::ellipse_cubics(center, rx, ry), thickness, color);
+        self.shapes[idx].closed = true;
+        idx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    /// one shape of two open subpaths: a zigzag along y = 0, and one along y = 50
+    fn two_zigzags() -> Shaper {
+        let zigzag = |y: f64| -> Vec<CubicBez> {
+            let anchors: Vec<KPoint> = (0..5)
+                .map(|i| KPoint::new(i as f64 * 20.0, y + if i % 2 == 0 { 0.0 } else { 10.0 }))
+                .collect();
+            anchors.windows(2).map(|w| model::straight_cubic(w[0], w[1])).collect()
+        };
+        let mut shape = Shape::new(2.0, Color32::BLACK);
+        shape.set_beziers(zigzag(0.0).into_iter().chain(zigzag(50.0)).collect());
+        shape.subpath_starts = vec![4];
+        let mut app = Shaper::default();
+        app.add_shape(shape);
+        app
+    }
+
+    #[test]
+    fn line_rect_and_ellipse_come_out_the_size_asked_for() {
+        let mut app = Shaper::default();
+        let line = app.add_line(KPoint::new(0.0, 0.0), KPoint::new(30.0, 40.0), 3.0, Color32::RED);
+        let rect = app.add_rect(Rect::new(10.0, 20.0, 110.0, 70.0), 2.0, Color32::BLACK);
+        let ellipse = app.add_ellipse(KPoint::new(50.0, 50.0), 40.0, 20.0, 2.0, Color32::BLACK);
+        assert_eq!((line, rect, ellipse), (0, 1, 2));
+
+        let line = &app.shapes[0];
+        let segment = model::straight_cubic(KPoint::ZERO, KPoint::new(30.0, 40.0));
+        assert_eq!(line.beziers, vec![segment]);
+        assert!(!line.closed);
+        assert_eq!((line.thickness, line.stroke_color), (3.0, Color32::RED));
+
+        let rect = &app.shapes[1];
+        assert!(rect.closed);
+        assert_eq!(rect.beziers.len(), 4);
+        assert_eq!(rect.bounding_box(), Some(Rect::new(10.0, 20.0, 110.0, 70.0)));
+
+        let ellipse = &app.shapes[2];
+        assert!(ellipse.closed);
+        let bbox = ellipse.bounding_box().unwrap();
+        for (got, want) in [(bbox.x0, 10.0), (bbox.y0, 30.0), (bbox.x1, 90.0), (bbox.y1, 70.0)] {
+            assert!((got - want).abs() < 1e-6, "{bbox:?}");
+        }
+        // every shape gets a name of its own
+        assert!(app.shapes.iter().all(|shape| !shape.name.is_empty()));
+    }
+
+    #[test]
+    fn straightening_a_segment_puts_its_handles_on_the_line() {
+        let mut shape = Shape::new(2.0, Color32::BLACK);
+        shape.set_beziers(vec![
+            CubicBez::new((0.0, 0.0), (10.0, 20.0), (20.0, 20.0), (30.0, 0.0)),
+            CubicBez::new((30.0, 0.0), (40.0, -20.0), (60.0, 30.0), (70.0, 10.0)),
+            CubicBez::new((70.0, 10.0), (80.0, -10.0), (90.0, 20.0), (100.0, 0.0)),
+        ]);
+        let mut app = Shaper::default();
+        app.add_shape(shape);
+        let before = app.shapes[0].beziers.clone();
+
+        app.straighten_segment(0, 1);
+        let after = &app.shapes[0].beziers;
+        let bez = after[1];
+        assert_eq!((bez.p0, bez.p3), (before[1].p0, before[1].p3));
+        let line = bez.p3 - bez.p0;
+        for handle in [bez.p1, bez.p2] {
+            assert!(line.cross(handle - bez.p0).abs() < 1e-9, "{handle:?} is off the line");
+        }
+        assert_eq!(bez, model::straight_cubic(bez.p0, bez.p3));
+        // the neighbors are left as they were
+        assert_eq!((after[0], after[2]), (before[0], before[2]));
+
+        // a new segment drags the neighbors' shared ends along
+        let shape = &mut app.shapes[0];
+        shape.set_segment(1, model::straight_cubic(KPoint::new(30.0, 5.0), KPoint::new(70.0, 15.0)));
+        assert_eq!(shape.beziers[0].p3, KPoint::new(30.0, 5.0));
+        assert_eq!(shape.beziers[2].p0, KPoint::new(70.0, 15.0));
+        assert_eq!(shape.beziers[0].p2, before[0].p2);
+    }
+
+    #[test]
+    fn beautify_smooths_every_anchor_without_moving_it() {
+        let mut app = two_zigzags();
+        let before = app.shapes[0].beziers.clone();
+        app.beautify_shape(0);
+        let after = &app.shapes[0].beziers;
+
+        for (a, b) in before.iter().zip(after) {
+            assert_eq!((a.p0, a.p3), (b.p0, b.p3));
+        }
+        for range in app.shapes[0].subpath_ranges() {
+            // the open ends keep their handles
+            assert_eq!(after[range.start].p0, before[range.start].p0);
+            assert_eq!(after[range.start].p1, before[range.start].p1);
+            assert_eq!(after[range.end - 1].p2, before[range.end - 1].p2);
+            // the handles either side of an interior anchor are in line
+            for k in range.start + 1..range.end {
+                let (anchor, back, ahead) = (after[k].p0, after[k - 1].p2, after[k].p1);
+                assert!((back - anchor).cross(ahead - anchor).abs() < 1e-9, "anchor {k}");
+                assert!((back - anchor).dot(ahead - anchor) < 0.0, "anchor {k}");
+            }
+        }
+    }
+
+    #[test]
+    fn smoothing_leaves_the_subpath_ends_alone() {
+        let mut app = two_zigzags();
+        let before = app.shapes[0].beziers.clone();
+
+        // a brush over everything
+        app.smooth_region(0, KPoint::new(40.0, 25.0), 1000.0, 1.0);
+
+        let after = &app.shapes[0].beziers;
+        assert_eq!(after[3].p3, before[3].p3, "end of the first subpath moved");
+        assert_eq!(after[4].p0, before[4].p0, "start of the second subpath moved");
+        assert_ne!(after[1].p0, before[1].p0, "interior anchors should be smoothed");
+    }
+
+    #[test]
+    fn cutting_keeps_pieces_inside_their_subpath() {
+        let mut app = two_zigzags();
+        app.selected_shapes.insert(0);
+
+        // a vertical line through the first zigzag only
+        let cut = app.cut_shapes_with_line(KPoint::new(30.0, -20.0), KPoint::new(30.0, 20.0));
+
+        assert_eq!(cut, 1);
+        // what the line missed (the second subpath) stays one shape,
+        // at the old index and still selected
+        assert_eq!(app.shapes.len(), 3);
+        assert_eq!(app.shapes[0].beziers.len(), 4);
+        assert!(app.shapes[0].subpath_starts.is_empty());
+        assert!(app.shapes[0].beziers.iter().all(|bez| bez.p0.y >= 40.0));
+        assert!(app.selected_shapes.contains(&0));
+        // the pieces of the first subpath stay on its side
+        for piece in &app.shapes[1..] {
+            assert!(piece.beziers.iter().all(|bez| bez.p0.y <= 10.0 && bez.p3.y <= 10.0));
+        }
+    }
+
+    #[test]
+    fn cutting_a_closed_shape_joins_only_within_each_subpath() {
+        let mut shape = Shape::new(2.0, Color32::BLACK);
+        let left = model::rect_cubics(Rect::new(0.0, 0.0, 40.0, 40.0));
+        let right = model::rect_cubics(Rect::new(100.0, 0.0, 140.0, 40.0));
+        shape.set_beziers(left.into_iter().chain(right).collect());
+        shape.subpath_starts = vec![4];
+        shape.closed = true;
+        let mut app = Shaper::default();
+        app.add_shape(shape);
+
+        // a horizontal line through both squares
+        app.cut_shapes_with_line(KPoint::new(-10.0, 20.0), KPoint::new(150.0, 20.0));
+
+        // two pieces per square, none spanning the gap between them
+        assert_eq!(app.shapes.len(), 4);
+        for piece in &app.shapes {
+            let xs: Vec<f64> = piece.beziers.iter().flat_map(|bez| [bez.p0.x, bez.p3.x]).collect();
+            let spans_gap = xs.iter().any(|&x| x < 50.0) && xs.iter().any(|&x| x > 50.0);
+            assert!(!spans_gap, "piece runs across subpaths: {:?}", piece.beziers);
+            assert!(!piece.closed);
+        }
+    }
+
+    #[test]
+    fn deleting_points_keeps_locked_anchors_and_their_locks() {
+        // a zigzag of four segments, anchors 0..=4
+        let mut app = two_zigzags();
+        app.shapes[0].subpath_starts.clear();
+        let beziers = app.shapes[0].beziers[..4].to_vec();
+        app.shapes[0].set_beziers(beziers);
+        let anchor = |k: usize| match k {
+            4 => PointId {
+                shape_idx: 0,
+                bez_idx: 3,
+                ctrl_idx: 3,
+            },
+            _ => PointId {
+                shape_idx: 0,
+                bez_idx: k,
+                ctrl_idx: 0,
+            },
+        };
+        let pos = |app: &Shaper, k: usize| app.point_pos(anchor(k)).unwrap();
+        let (locked_1, end) = (pos(&app, 1), pos(&app, 4));
+        let deleted = pos(&app, 2);
+        app.locked_points = HashSet::from([anchor(1), anchor(4)]);
+
+        app.selected_points = HashSet::from([anchor(1), anchor(2)]);
+        app.delete_selected_points();
+
+        // the locked one wasn't deleted, the other one was
+        let shape = &app.shapes[0];
+        assert_eq!(shape.beziers.len(), 3);
+        assert!(shape.beziers.iter().all(|bez| bez.p0 != deleted));
+        // and both locks are still on their anchors, at the new indices
+        assert_eq!(app.locked_points.len(), 2);
+        assert_eq!(pos(&app, 1), locked_1);
+        assert!(app.is_locked(anchor(1)));
+        let end_id = PointId {
+            shape_idx: 0,
+            bez_idx: 2,
+            ctrl_idx: 3,
+        };
+        assert_eq!(app.point_pos(end_id), Some(end));
+        assert!(app.is_locked(end_id));
+    }
+}
@@ -18,9 +18,20 @@ pub trait Tool {
 
     /// called after input, to let the tool draw any custom UI or decorations.
     /// draw in-progress strokes, pan hints, etc.
+    ///
+    /// egui only repaints on input, which already covers anything that follows
+    /// the pointer (brush circles, drag previews). only things that change on
+    /// their own need to ask for more frames, and should do so with
+    /// `ctx.request_repaint_after` rather than every frame. currently:
+    /// - selecting tool: marching ants, while something is selected
+    /// - drawing tool: one extra frame to hide the thickness flash
     fn paint(&mut self,  ctx: &Context, painter: &Painter, app: &crate::Shaper);
 
     
     // draw specific UI elements
     fn tool_ui(&mut self, ctx: &Context, app: &mut crate::Shaper);
+
+    /// called when another tool gets selected, before it handles any input.
+    /// finish (or drop) anything still in progress here
+    fn on_deactivate(&mut self, _app: &mut crate::Shaper) {}
 }
@@ -0,0 +1,83 @@
+use crate::Shaper;
+use crate::shape::FillRule;
+use crate::tool::Tool;
+use eframe::egui::color_picker::Alpha;
+use eframe::egui::{self, Align, Color32, Context, Layout, Painter, Response};
+use kurbo::Point as KPoint;
+
+/// how close (in screen pixels) the two ends of an open
+/// stroke have to be for the bucket to treat it as closed
+const CLOSE_TOL_PX: f32 = 12.0;
+
+pub struct BucketTool {
+    fill_color: Color32,
+    /// what the filled shapes get, see `FillRule`
+    fill_rule: FillRule,
+}
+
+impl BucketTool {
+    pub fn new() -> Self {
+        BucketTool {
+            fill_color: Color32::from_rgb(10, 118, 241),
+            fill_rule: FillRule::NonZero,
+        }
+    }
+}
+
+impl Tool for BucketTool {
+    fn handle_input(&mut self, ctx: &Context, response: &Response, app: &mut Shaper) {
+        // zoom / pan with the mouse wheel, see `Shaper::handle_scroll`
+        app.handle_scroll(ctx, response);
+
+        if response.hovered() {
+            ctx.set_cursor_icon(egui::CursorIcon::Cell);
+        }
+
+        // fill the topmost shape whose outline encloses the click.
+        // for now only single shapes are considered, not regions
+        // formed by several overlapping strokes
+        if ctx.input(|i| i.pointer.primary_pressed()) && response.hovered() {
+            if let Some(pos) = response.interact_pointer_pos() {
+                let world = app.screen_to_world(pos);
+                let mouse = KPoint::new(world.x as f64, world.y as f64);
+                let close_tol = app.screen_to_world_tol(CLOSE_TOL_PX);
+
+                for shape in app.shapes.iter_mut().rev() {
+                    // a stroke that ends where it started counts as closed.
+                    // inside by the rule it's about to get
+                    let closable = shape.closed || shape.ends_meet(close_tol);
+                    if closable && self.fill_rule.is_inside(shape.winding(mouse)) {
+                        shape.close();
+                        shape.fill_color = self.fill_color;
+                        shape.fill_rule = self.fill_rule;
+                        break;
+                    }
+                }
+            }
+        }
+    }
+
+    fn paint(&mut self, _ctx: &Context, _painter: &Painter, _app: &Shaper) {}
+
+    fn tool_ui(&mut self, ctx: &Context, _app: &mut Shaper) {
+        egui::TopBottomPanel::top("bucket settings")
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
+                    ui.horizontal(|ui| {
+                        egui::widgets::color_picker::color_edit_button_srgba(
+                            ui,
+                            &mut self.fill_color,
+                            Alpha::OnlyBlend,
+                        );
+                        ui.label("Fill Color:");
+                    });
+                    ui.separator();
+                    ui.radio_value(&mut self.fill_rule, FillRule::NonZero, "Nonzero")
+                        .on_hover_text("overlapping parts are filled");
+                    ui.radio_value(&mut self.fill_rule, FillRule::EvenOdd, "Even-odd")
+                        .on_hover_text("overlapping parts cut holes");
+                });
+            });
+    }
+}
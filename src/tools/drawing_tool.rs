@@ -1,118 +1,395 @@
 use crate::tool::Tool;
-use crate::{Shape, Shaper};
+use crate::{Shaper, model};
 use eframe::egui::color_picker::Alpha;
 use eframe::egui::{
-    self, Align, Color32, Context, Event, Layout, Painter, Response, SliderOrientation, Rect, Pos2
+    self, Align, Align2, Color32, Context, Event, FontId, Layout, Painter, Response, SliderOrientation, Rect, Pos2, Vec2
 };
-use egui::emath::Vec2;
+use kurbo::Point as KPoint;
+
+/// how a finished stroke is turned into segments
+#[derive(PartialEq)]
+enum FitMode {
+    /// as few segments as `bezier_tolerance` allows
+    Tolerance,
+    /// always exactly `segment_count` segments
+    SegmentCount,
+}
 
 pub struct DrawingTool {
+    fit_mode: FitMode,
     bezier_tolerance: f64,
+    /// how far (degrees) a fitted segment may bend and still become an
+    /// exact line, see `model::fit_curve`
+    straight_tol_deg: f64,
+
+    /// scale `bezier_tolerance` by how fast the stroke was drawn
+    /// (see `stroke_tolerance`)
+    adaptive_tolerance: bool,
+    /// the tolerance the last stroke was fit with, for the UI
+    last_tolerance: Option<f64>,
+    /// `ctx` time of every point in `curr_shape.current_stroke`
+    sample_times: Vec<f64>,
+    segment_count: usize,
     thickness: f32,
 
     /// Minimum pixel distance before we sample a new raw point
     sample_tol: f32,
 
+    /// most raw points a stroke may have, see `thin_out`
+    max_stroke_points: usize,
+    /// the current/last stroke hit `max_stroke_points` and was thinned out
+    stroke_thinned: bool,
+
+    /// close a stroke that ends within `auto_close_px` (screen px) of
+    /// where it started
+    auto_close: bool,
+    auto_close_px: f32,
+
     drawing_color: Color32,
     
     is_drawing: bool,
+
+    /// the stroke was ended before the drag did (see `end_stroke`),
+    /// the rest of the drag is ignored
+    stroke_done: bool,
+
+    /// strokes pile up in `curr_shape` as subpaths of one shape until
+    /// Enter (or another tool), instead of each becoming its own shape
+    compound: bool,
+    /// every stroke in `curr_shape` so far was closed, see `auto_close`
+    all_loops: bool,
+
+    /// shift has been held since the current drag started
+    straight_line: bool,
+    /// straight lines start/end on nearby anchors and segment midpoints,
+    /// see `Shaper::nearest_snap_target`
+    snap_lines: bool,
+
+    /// `ctx` time until which the brush is flashed at the cursor
+    /// after the thickness slider changed
+    flash_until: Option<f64>,
+}
+
+/// how close (screen px) a straight line's end has to come to an
+/// anchor or segment midpoint to snap to it
+const SNAP_RADIUS_PX: f32 = 8.0;
+
+/// how long (seconds) the brush preview stays up after a thickness change
+const BRUSH_FLASH_SECS: f64 = 0.6;
+
+/// on-screen radius range (px) of the brush indicator, so it neither
+/// vanishes when zoomed far out nor covers the canvas when zoomed in.
+/// only the indicator is clamped, never `thickness`
+const BRUSH_MIN_RADIUS_PX: f32 = 2.0;
+const BRUSH_MAX_RADIUS_PX: f32 = 150.0;
+/// below this on-screen width (px) the stroke is barely visible, say so
+const TINY_BRUSH_PX: f32 = 1.0;
+
+/// drop every other point of `points`, keeping both ends. used to keep
+/// very long strokes (and the fit on them) responsive
+fn thin_out<T: Copy>(points: &mut Vec<T>) {
+    let Some(&last) = points.last() else {
+        return;
+    };
+    let mut i = 0;
+    points.retain(|_| {
+        i += 1;
+        i % 2 == 1
+    });
+    if i % 2 == 0 {
+        points.push(last);
+    }
 }
 
+/// a stroke has to be at least this many times `auto_close_px` long
+/// before it's closed, so a short dab doesn't turn into a loop
+const AUTO_CLOSE_MIN_LENGTH: f32 = 4.0;
+
+/// average stroke speeds (screen px per second) at and below / above which
+/// the adaptive tolerance bottoms / tops out
+const SLOW_STROKE_SPEED: f64 = 150.0;
+const FAST_STROKE_SPEED: f64 = 1500.0;
+/// what `bezier_tolerance` is multiplied by for slow / fast strokes
+const SLOW_STROKE_FACTOR: f64 = 0.5;
+const FAST_STROKE_FACTOR: f64 = 2.0;
+
 impl DrawingTool {
     pub fn new() -> Self {
         DrawingTool {
+            fit_mode: FitMode::Tolerance,
             bezier_tolerance: 10.0,
+            straight_tol_deg: model::STRAIGHT_ANGLE_TOL.to_degrees(),
+            adaptive_tolerance: false,
+            last_tolerance: None,
+            sample_times: Vec::new(),
+            segment_count: 4,
             thickness: 10.0,
             sample_tol: 2.0,
+            max_stroke_points: 2000,
+            stroke_thinned: false,
+            auto_close: false,
+            auto_close_px: 12.0,
             drawing_color: Color32::BLACK,
             is_drawing: false,
+            stroke_done: false,
+            compound: false,
+            all_loops: true,
+            straight_line: false,
+            snap_lines: true,
+            flash_until: None,
+        }
+    }
+
+    /// move the stroke being drawn into `curr_shape` as its last subpath:
+    /// a straight line to `end` (world) if shift was held, else fit.
+    fn add_stroke(&mut self, app: &mut Shaper, end: Option<Pos2>) {
+        let mut stroke = std::mem::take(&mut app.curr_shape.current_stroke);
+        if self.straight_line && stroke.len() >= 2 {
+            // ignore everything in between: one straight segment
+            // from where the drag started to where it ended
+            let first = stroke[0];
+            let last = match end {
+                Some(pos) => self.snap_end(app, pos).0,
+                None => stroke[stroke.len() - 1],
+            };
+            let shape = &mut app.curr_shape;
+            shape.start_subpath();
+            shape.beziers_mut().push(model::straight_cubic(
+                KPoint::new(first.x as f64, first.y as f64),
+                KPoint::new(last.x as f64, last.y as f64),
+            ));
+            shape.raw_strokes.push(vec![first, last]);
+            self.all_loops = false;
+        } else if !stroke.is_empty() {
+            // ended on its own start: snap the end onto it
+            let close = self.ends_at_start(&stroke, app.zoom);
+            if close {
+                let first = stroke[0];
+                *stroke.last_mut().unwrap() = first;
+            }
+            self.all_loops &= close;
+
+            // fit to Bézier chain, and store the raw stroke
+            app.curr_shape.start_subpath();
+            match self.fit_mode {
+                FitMode::Tolerance => {
+                    let tol = self.stroke_tolerance(&stroke, app.zoom);
+                    self.last_tolerance = Some(tol);
+                    let straight_tol = self.straight_tol_deg.to_radians();
+                    app.curr_shape.fit_curve_and_store(&stroke, tol, straight_tol)
+                }
+                FitMode::SegmentCount => app
+                    .curr_shape
+                    .fit_with_segment_count(&stroke, self.segment_count),
+            }
+            app.curr_shape.raw_strokes.push(stroke);
+        }
+    }
+
+    /// a drag started at `world` (none if egui lost the pointer): start
+    /// a new stroke there. `shift` makes it a straight line
+    fn begin_stroke(&mut self, app: &mut Shaper, world: Option<Pos2>, shift: bool, time: f64) {
+        self.stroke_done = false;
+        self.straight_line = shift;
+        app.curr_shape.current_stroke.clear();
+        self.sample_times.clear();
+        self.stroke_thinned = false;
+        // the live preview is drawn with curr_shape's style,
+        // so it has to match what the committed shape will get
+        app.curr_shape.stroke_color = self.drawing_color;
+        app.curr_shape.thickness = self.thickness;
+        if let Some(world) = world {
+            // app.curr_shape only ever holds finished strokes (as subpaths) at this point
+            let world_pos = if self.straight_line {
+                self.snap_end(app, world).0
+            } else {
+                world
+            };
+            app.curr_shape.current_stroke.push(world_pos);
+            self.sample_times.push(time);
+        }
+    }
+
+    /// the drag moved on to `world`: sample it into the stroke if it's
+    /// far enough from the last point
+    fn extend_stroke(&mut self, app: &mut Shaper, world_pos: Pos2, shift: bool, time: f64) {
+        if self.stroke_done {
+            return;
+        }
+        self.straight_line &= shift;
+        let should_add = match app.curr_shape.current_stroke.last() {
+            Some(&last) => last.distance(world_pos) > (self.sample_tol / app.zoom), // make sample_tol take into account the zoom level
+            None => true,
+        };
+        if should_add {
+            app.curr_shape.current_stroke.push(world_pos);
+            self.sample_times.push(time);
+            // over the cap: halve the resolution of what's there so
+            // far, and keep going (the ends are always kept)
+            if app.curr_shape.current_stroke.len() > self.max_stroke_points {
+                thin_out(&mut app.curr_shape.current_stroke);
+                thin_out(&mut self.sample_times);
+                self.stroke_thinned = true;
+            }
+            self.is_drawing = true;
+        }
+    }
+
+    /// the drag ended at `end` (world): finish the stroke and, unless
+    /// drawing a compound shape, commit it
+    fn release_stroke(&mut self, app: &mut Shaper, end: Option<Pos2>) {
+        // unless it was ended already, see `end_stroke`
+        if !self.stroke_done {
+            self.add_stroke(app, end);
+            if !self.compound {
+                self.commit_shape(app);
+            }
+        }
+        self.is_drawing = false;
+        self.straight_line = false;
+    }
+
+    /// end the stroke being drawn right away instead of on release
+    /// (Enter, the window losing focus, switching tools), so it isn't lost
+    fn end_stroke(&mut self, app: &mut Shaper) {
+        if !self.is_drawing {
+            return;
+        }
+        self.add_stroke(app, None);
+        self.is_drawing = false;
+        self.straight_line = false;
+        self.stroke_done = true;
+    }
+
+    /// add `curr_shape` (all strokes so far) as a new shape, closed if
+    /// every stroke was
+    fn commit_shape(&mut self, app: &mut Shaper) {
+        if self.all_loops && !app.curr_shape.beziers.is_empty() {
+            app.curr_shape.close();
         }
+        app.commit_current_shape(self.bezier_tolerance, self.straight_tol_deg.to_radians());
+        self.all_loops = true;
+    }
+
+    /// `stroke` (world space) ends where it started, see `auto_close`
+    fn ends_at_start(&self, stroke: &[Pos2], zoom: f32) -> bool {
+        let (Some(first), Some(last)) = (stroke.first(), stroke.last()) else {
+            return false;
+        };
+        let length: f32 = stroke.windows(2).map(|w| w[0].distance(w[1])).sum();
+        self.auto_close
+            && stroke.len() > 2
+            && first.distance(*last) * zoom <= self.auto_close_px
+            && length * zoom >= self.auto_close_px * AUTO_CLOSE_MIN_LENGTH
+    }
+
+    /// where a straight line's end at `screen` goes (world space): onto
+    /// the nearest snap target if `snap_lines` is on and one is in reach.
+    /// the flag says whether it snapped
+    fn line_end(&self, app: &Shaper, screen: Pos2) -> (Pos2, bool) {
+        self.snap_end(app, app.screen_to_world(screen))
+    }
+
+    /// `line_end` for a point already in world space
+    fn snap_end(&self, app: &Shaper, world: Pos2) -> (Pos2, bool) {
+        let target = app.nearest_snap_target(
+            KPoint::new(world.x as f64, world.y as f64),
+            app.screen_to_world_tol(SNAP_RADIUS_PX),
+        );
+        match target {
+            Some(p) if self.snap_lines => (Pos2::new(p.x as f32, p.y as f32), true),
+            _ => (world, false),
+        }
+    }
+
+    /// the fit tolerance for `stroke` (world space): with
+    /// `adaptive_tolerance` on, fast strokes get a higher one (smoother),
+    /// slow, deliberate strokes a lower one (more detail).
+    fn stroke_tolerance(&self, stroke: &[Pos2], zoom: f32) -> f64 {
+        if !self.adaptive_tolerance {
+            return self.bezier_tolerance;
+        }
+        let (Some(first), Some(last)) = (self.sample_times.first(), self.sample_times.last())
+        else {
+            return self.bezier_tolerance;
+        };
+        let duration = last - first;
+        if duration <= 0.0 {
+            return self.bezier_tolerance;
+        }
+        let length: f32 = stroke.windows(2).map(|w| w[0].distance(w[1])).sum();
+        let speed = (length * zoom) as f64 / duration;
+        let t = ((speed - SLOW_STROKE_SPEED) / (FAST_STROKE_SPEED - SLOW_STROKE_SPEED)).clamp(0.0, 1.0);
+        self.bezier_tolerance * (SLOW_STROKE_FACTOR + (FAST_STROKE_FACTOR - SLOW_STROKE_FACTOR) * t)
     }
 }
 
 impl Tool for DrawingTool {
     fn handle_input(&mut self, ctx: &Context, response: &Response, app: &mut Shaper) {
-        // handle zooming  in and out first
-        if let Some(pointer_pos) = response.hover_pos() {
-            let scroll_delta = ctx.input(|i| i.smooth_scroll_delta.y);
-            if scroll_delta != 0.0 {
-                // convert world position before zoom
-                let old_world_pos = app.screen_to_world(pointer_pos);
-
-                // apply zoom
-                let zoom_delta = (scroll_delta * 0.009).exp();
-                app.zoom *= zoom_delta;
-                app.zoom = app.zoom.clamp(app.min_zoom, app.max_zoom);
-
-                // convert world position after zoom
-                let new_world_pos = app.screen_to_world(pointer_pos);
-
-                // adjust pan offset to keep pointer position stable
-                // convert Pos2 difference directly to Vec2
-                let world_delta = Vec2::new(
-                    new_world_pos.x - old_world_pos.x,
-                    new_world_pos.y - old_world_pos.y,
-                );
-                app.pan_offset += world_delta * app.zoom;
+        // handle zooming (and scroll panning) first
+        app.handle_scroll(ctx, response);
 
-                // percentage calculation:
-                app.calc_zoom_level();
-            }
+        // crosshair over the canvas (egui puts the cursor
+        // back to the default one every frame on its own)
+        if response.hovered() || response.dragged() {
+            ctx.set_cursor_icon(egui::CursorIcon::Crosshair);
         }
 
         // begin raw stroke
+        // shift has to be held for the whole drag to get a straight line;
+        // letting go of it at any point falls back to the freehand fit
+        let shift = ctx.input(|i| i.modifiers.shift);
+
+        let time = ctx.input(|i| i.time);
+        let pointer = response.interact_pointer_pos().map(|pos| app.screen_to_world(pos));
+
         if response.drag_started() {
-            app.curr_shape.current_stroke.clear();
-            if let Some(pos) = response.interact_pointer_pos() {
-                // app.curr_shape is reset on drag end every time. No need to reset it on drag start.
-                let world_pos = app.screen_to_world(pos);
-                app.curr_shape.current_stroke.push(world_pos);
-            }
+            self.begin_stroke(app, pointer, shift, time);
         }
 
-        if response.dragged() {
-            if let Some(pos) = response.interact_pointer_pos() {
-                let world_pos = app.screen_to_world(pos);
-                let should_add = match app.curr_shape.current_stroke.last() {
-                    Some(&last) => last.distance(world_pos) > (self.sample_tol / app.zoom), // make sample_tol take into account the zoom level
-                    None => true,
-                };
-                if should_add {
-                    app.curr_shape.current_stroke.push(world_pos);
-                    self.is_drawing = true;
-                }
+        if let (true, Some(world_pos)) = (response.dragged(), pointer) {
+            self.extend_stroke(app, world_pos, shift, time);
+        }
+
+        // Enter finishes the stroke without letting go, and losing
+        // focus does too (the release might never arrive). compound
+        // strokes only become a shape on Enter
+        let (enter, focused) = ctx.input(|i| (i.key_pressed(egui::Key::Enter), i.focused));
+        if enter || !focused {
+            self.end_stroke(app);
+            if enter || !self.compound {
+                self.commit_shape(app);
             }
         }
 
         if response.drag_stopped() {
-            if !app.curr_shape.current_stroke.is_empty() {
-                // store raw stroke
-                app.curr_shape
-                    .raw_strokes
-                    .push(app.curr_shape.current_stroke.clone());
-
-                // fit to Bézier chain
-                let stroke = app.curr_shape.current_stroke.clone();
-                app.curr_shape
-                    .fit_curve_and_store(&stroke, self.bezier_tolerance);
-
-                // push shape and reset
-                app.shapes.push(app.curr_shape.clone());
-                app.curr_shape = Shape::new(self.thickness, self.drawing_color);
-            }
-            self.is_drawing = false;
+            self.release_stroke(app, pointer);
         }
 
         // event: allow “delete last stroke” via Backspace/Delete:
-        for event in &ctx.input(|i: &egui::InputState| i.events.clone()) {
+        // (not while typing in a text field)
+        let events = if ctx.wants_keyboard_input() {
+            Vec::new()
+        } else {
+            ctx.input(|i: &egui::InputState| i.events.clone())
+        };
+        for event in &events {
             if let Event::Key {
                 key, pressed: true, ..
             } = event
             {
                 match key {
+                    // a compound shape in the making loses its last stroke
+                    egui::Key::Delete | egui::Key::Backspace
+                        if !app.curr_shape.beziers.is_empty() =>
+                    {
+                        app.curr_shape.pop_subpath();
+                    }
                     egui::Key::Delete | egui::Key::Backspace => {
-                        if let Some(_) = app.shapes.pop() {
-                            //nothing to do actually
+                        // go through remove_shape so the selection doesn't
+                        // keep pointing at the removed shape
+                        if let Some(last_idx) = app.shapes.len().checked_sub(1) {
+                            app.remove_shape(last_idx);
                         }
                     }
                     _ => {}
@@ -122,16 +399,75 @@ impl Tool for DrawingTool {
     }
 
     fn paint(&mut self, ctx: &Context, painter: &Painter, app: &Shaper) {
+        let screen_width = self.thickness * app.zoom;
+        let brush_radius = (screen_width / 2.0).clamp(BRUSH_MIN_RADIUS_PX, BRUSH_MAX_RADIUS_PX);
+
+        // right after a thickness change the pointer is still over the
+        // slider (outside the canvas), so show the brush on top of everything
+        if let Some(until) = self.flash_until {
+            let now = ctx.input(|i| i.time);
+            if now < until {
+                if let Some(mouse_pos) = ctx.pointer_latest_pos() {
+                    let layer =
+                        egui::LayerId::new(egui::Order::Foreground, egui::Id::new("brush flash"));
+                    ctx.layer_painter(layer).circle_filled(
+                        mouse_pos,
+                        brush_radius,
+                        self.drawing_color.gamma_multiply(0.5),
+                    );
+                }
+                // nothing moves in between, one more frame to remove it is enough
+                ctx.request_repaint_after_secs((until - now) as f32);
+                return;
+            }
+            self.flash_until = None;
+        }
+
+        // where a straight line would start / end right now
+        let shift = ctx.input(|i| i.modifiers.shift);
+        let snapped = ctx
+            .input(|i| i.pointer.hover_pos())
+            .filter(|_| shift)
+            .map(|pos| self.line_end(app, pos))
+            .filter(|&(_, snapped)| snapped);
+        if let Some((target, _)) = snapped {
+            painter.circle_stroke(
+                app.world_to_screen(target),
+                SNAP_RADIUS_PX * 0.6,
+                egui::Stroke::new(1.5, Color32::from_rgb(0, 160, 255)),
+            );
+        }
+
         // draw a small circle to indicate the cursor position (pen size)
         if let Some(mouse_pos) = ctx.input(|i| i.pointer.hover_pos()) {
             
+            // zoomed out this far the stroke will hardly show up
+            if screen_width < TINY_BRUSH_PX {
+                painter.text(
+                    mouse_pos + Vec2::new(brush_radius + 6.0, 0.0),
+                    Align2::LEFT_CENTER,
+                    format!("brush {screen_width:.2} px on screen"),
+                    FontId::proportional(12.0),
+                    ctx.style().visuals.warn_fg_color,
+                );
+            }
+
             // this check statement might only be "more useful" 
             // for the rect indicator.
-            if !self.is_drawing {
+            if self.is_drawing || brush_radius >= BRUSH_MAX_RADIUS_PX {
+                // just a faint outline of the footprint while drawing,
+                // so the stroke underneath stays visible (and for a brush
+                // too big for the indicator, instead of a disc over it all)
+                painter.circle_stroke(
+                    mouse_pos,
+                    brush_radius,
+                    egui::Stroke::new(1.0, self.drawing_color.gamma_multiply(0.4)),
+                );
+            } else {
                 // circle indicator
                 painter.circle_filled(
                     mouse_pos,
-                    (self.thickness * app.zoom) / 2.0,
+                    brush_radius,
                     self.drawing_color,
                 );
     
@@ -146,17 +482,98 @@ impl Tool for DrawingTool {
         }
     }
 
+    fn on_deactivate(&mut self, app: &mut Shaper) {
+        self.end_stroke(app);
+        self.commit_shape(app);
+    }
+
     // slider for the value of the
     fn tool_ui(&mut self, ctx: &Context, app: &mut Shaper) {
         egui::TopBottomPanel::top("drawing settings")
             .resizable(false)
             .show(ctx, |ui| {
                 ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
-                    // slider for the tolerance of the drawing tool
-                    let tol = egui::Slider::new(&mut self.bezier_tolerance, 1.0..=100.0)
-                        .text("Tolerance")
+                    ui.radio_value(&mut self.fit_mode, FitMode::Tolerance, "Fit by tolerance");
+                    ui.radio_value(&mut self.fit_mode, FitMode::SegmentCount, "Fixed segments");
+
+                    match self.fit_mode {
+                        FitMode::Tolerance => {
+                            // slider for the tolerance of the drawing tool
+                            let tol = egui::Slider::new(&mut self.bezier_tolerance, 1.0..=100.0)
+                                .text("Tolerance")
+                                .orientation(SliderOrientation::Horizontal);
+                            ui.add(tol);
+                            ui.checkbox(&mut self.adaptive_tolerance, "Adapt to speed")
+                                .on_hover_text("fast strokes come out smoother, slow ones more detailed");
+                            if let (true, Some(tol)) = (self.adaptive_tolerance, self.last_tolerance) {
+                                ui.label(format!("last: {tol:.1}"));
+                            }
+                            ui.label("Straighten within");
+                            ui.add(
+                                egui::DragValue::new(&mut self.straight_tol_deg)
+                                    .range(0.0..=45.0)
+                                    .speed(0.1)
+                                    .suffix("°"),
+                            )
+                            .on_hover_text("segments bent less than this become exact lines");
+                        }
+                        FitMode::SegmentCount => {
+                            let count = egui::Slider::new(&mut self.segment_count, 1..=50)
+                                .text("Segments")
+                                .orientation(SliderOrientation::Horizontal);
+                            ui.add(count);
+                        }
+                    }
+
+                    // how far apart (screen px, so the same at any zoom) raw
+                    // points are captured: lower keeps more detail for the
+                    // fit, higher gives sparser, lighter input
+                    let density = egui::Slider::new(&mut self.sample_tol, 0.5..=20.0)
+                        .text("Capture density (px)")
+                        .logarithmic(true)
                         .orientation(SliderOrientation::Horizontal);
-                    ui.add(tol);
+                    ui.add(density);
+                    ui.add(
+                        egui::DragValue::new(&mut self.max_stroke_points)
+                            .range(100..=100_000)
+                            .prefix("max points: "),
+                    );
+                    if self.is_drawing {
+                        ui.label(format!("Points: {}", app.curr_shape.current_stroke.len()));
+                    }
+                    let compound = ui
+                        .checkbox(&mut self.compound, "Compound strokes")
+                        .on_hover_text(
+                            "strokes make up one shape until Enter, Backspace takes the last one back",
+                        );
+                    // turned off: what's there so far becomes a shape
+                    if compound.changed() && !self.compound && !self.is_drawing {
+                        self.commit_shape(app);
+                    }
+                    let strokes = app.curr_shape.subpath_ranges().len();
+                    if strokes > 0 {
+                        let plural = if strokes == 1 { "" } else { "s" };
+                        ui.label(format!("{strokes} stroke{plural}, Enter to finish"));
+                    }
+                    ui.checkbox(&mut self.snap_lines, "Snap lines").on_hover_text(
+                        "shift-drawn lines start and end on nearby anchors and midpoints",
+                    );
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.auto_close, "Close loops")
+                            .on_hover_text("close a stroke that ends near where it started");
+                        ui.add_enabled(
+                            self.auto_close,
+                            egui::DragValue::new(&mut self.auto_close_px)
+                                .range(1.0..=100.0)
+                                .suffix(" px"),
+                        );
+                    });
+                    if self.stroke_thinned {
+                        ui.colored_label(ui.visuals().warn_fg_color, "long stroke, thinned out")
+                            .on_hover_text(
+                                "the stroke went over the max points and was simplified while drawing",
+                            );
+                    }
 
                     // slider for thickness of curves
                     let width = egui::Slider::new(&mut self.thickness, 1.0..=100.0)
@@ -164,6 +581,7 @@ impl Tool for DrawingTool {
                         .orientation(SliderOrientation::Horizontal);
                     if ui.add(width).changed() {
                         app.curr_shape.thickness = self.thickness;
+                        self.flash_until = Some(ctx.input(|i| i.time) + BRUSH_FLASH_SECS);
                     }
 
                     // color picker for the stroke using 
@@ -183,3 +601,108 @@ impl Tool for DrawingTool {
             });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// drag through `points` (world) and let go at the last one
+    fn stroke(tool: &mut DrawingTool, app: &mut Shaper, points: &[Pos2], shift: bool) {
+        tool.begin_stroke(app, points.first().copied(), shift, 0.0);
+        for (i, &p) in points.iter().enumerate().skip(1) {
+            tool.extend_stroke(app, p, shift, i as f64 * 0.01);
+        }
+        tool.release_stroke(app, points.last().copied());
+    }
+
+    #[test]
+    fn drawing_a_stroke_adds_a_shape_along_it() {
+        let mut app = Shaper::default();
+        let mut tool = DrawingTool::new();
+        let points: Vec<Pos2> = (0..=50).map(|i| Pos2::new(i as f32 * 4.0, 0.0)).collect();
+        stroke(&mut tool, &mut app, &points, false);
+
+        assert_eq!(app.shapes.len(), 1);
+        let shape = &app.shapes[0];
+        assert!(!shape.beziers.is_empty());
+        assert_eq!(shape.beziers[0].p0, KPoint::new(0.0, 0.0));
+        assert_eq!(shape.beziers.last().unwrap().p3, KPoint::new(200.0, 0.0));
+        assert_eq!(shape.thickness, tool.thickness);
+        // nothing left over for the next stroke
+        assert!(app.curr_shape.beziers.is_empty());
+        assert!(app.curr_shape.current_stroke.is_empty());
+    }
+
+    #[test]
+    fn a_stroke_ended_mid_drag_is_kept_and_the_rest_of_the_drag_ignored() {
+        let mut app = Shaper::default();
+        let mut tool = DrawingTool::new();
+        tool.begin_stroke(&mut app, Some(Pos2::new(0.0, 0.0)), false, 0.0);
+        for i in 1..=20 {
+            tool.extend_stroke(&mut app, Pos2::new(i as f32 * 5.0, 0.0), false, 0.0);
+        }
+        // e.g. the window lost focus: Enter / focus loss end and commit it
+        tool.end_stroke(&mut app);
+        tool.commit_shape(&mut app);
+        assert_eq!(app.shapes.len(), 1);
+        assert_eq!(app.shapes[0].beziers.last().unwrap().p3, KPoint::new(100.0, 0.0));
+        assert!(app.curr_shape.beziers.is_empty());
+
+        // the drag goes on and ends, nothing more comes of it
+        tool.extend_stroke(&mut app, Pos2::new(200.0, 50.0), false, 0.0);
+        assert!(app.curr_shape.current_stroke.is_empty());
+        tool.release_stroke(&mut app, Some(Pos2::new(200.0, 50.0)));
+        assert_eq!(app.shapes.len(), 1);
+        assert!(!tool.is_drawing);
+    }
+
+    #[test]
+    fn thin_out_halves_the_points_and_keeps_both_ends() {
+        for len in [5, 6] {
+            let mut points: Vec<usize> = (0..len).collect();
+            thin_out(&mut points);
+            assert_eq!(points.first(), Some(&0));
+            assert_eq!(points.last(), Some(&(len - 1)));
+            assert!(points.len() <= len / 2 + 1, "{points:?}");
+        }
+    }
+
+    #[test]
+    fn a_very_long_stroke_stays_under_max_stroke_points() {
+        let mut app = Shaper::default();
+        let mut tool = DrawingTool::new();
+        let points: Vec<Pos2> = (0..10_000)
+            .map(|i| {
+                let x = i as f32 * 3.0;
+                Pos2::new(x, (x * 0.01).sin() * 50.0)
+            })
+            .collect();
+        tool.begin_stroke(&mut app, Some(points[0]), false, 0.0);
+        for (i, &p) in points.iter().enumerate().skip(1) {
+            tool.extend_stroke(&mut app, p, false, i as f64 * 0.001);
+            assert!(app.curr_shape.current_stroke.len() <= tool.max_stroke_points);
+        }
+        assert!(tool.stroke_thinned);
+        let stroke = &app.curr_shape.current_stroke;
+        assert_eq!((stroke[0], *stroke.last().unwrap()), (points[0], points[9_999]));
+        assert_eq!(tool.sample_times.len(), stroke.len());
+
+        tool.release_stroke(&mut app, points.last().copied());
+        let segments = app.shapes[0].beziers.len();
+        assert!((1..tool.max_stroke_points).contains(&segments), "{segments} segments");
+    }
+
+    #[test]
+    fn shift_draws_one_straight_segment_from_start_to_end() {
+        let mut app = Shaper::default();
+        let mut tool = DrawingTool::new();
+        let points = [Pos2::new(0.0, 0.0), Pos2::new(30.0, 40.0), Pos2::new(100.0, 0.0)];
+        stroke(&mut tool, &mut app, &points, true);
+
+        assert_eq!(app.shapes.len(), 1);
+        let beziers = &app.shapes[0].beziers;
+        assert_eq!(beziers.len(), 1);
+        assert_eq!(beziers[0].p0, KPoint::new(0.0, 0.0));
+        assert_eq!(beziers[0].p3, KPoint::new(100.0, 0.0));
+    }
+}
@@ -1,18 +1,18 @@
+use std::collections::HashSet;
+
 use crate::Shaper;
+use crate::hit_test::HitTestResult;
+use crate::model;
+use crate::selection::PointId;
+use crate::shape::HandleMode;
 use crate::tool::Tool;
-use eframe::egui::{self, Align, Context, Layout, Painter, Pos2, Response, Vec2};
-use kurbo::{Nearest, ParamCurveNearest, Point};
+use eframe::egui::{self, Align, Align2, Context, FontId, Layout, Painter, Pos2, Response, Vec2};
+use kurbo::{CubicBez, Point};
 
 /// A small enum to remember what the user clicked on (and is now dragging).
 /// Remember what we’re dragging: either one control handle (and its neighbors),
 /// or the entire segment.
 
-#[derive(PartialEq)]
-enum MoveMode {
-    MovePoint,
-    MoveControlPoints,
-}
-
 enum ActiveDrag {
     ControlPoint {
         // this variable is to track the index of the
@@ -48,14 +48,197 @@ impl Default for ActiveDrag {
     }
 }
 
+impl ActiveDrag {
+    /// move whatever this drag refers to by `delta` (world space),
+    /// relative to the original positions stored at drag start.
+    /// stale indices are ignored rather than panicking.
+    fn apply(&self, app: &mut Shaper, delta: Point, mode: HandleMode) {
+        let delta = delta.to_vec2();
+        match self {
+            ActiveDrag::ControlPoint {
+                shape_idx,
+                bez_idx,
+                ctrl_idx,
+                orig_pos,
+            } => {
+                // see `HandleMode` for what happens to the handles
+                if let Some(shape) = app.shapes.get_mut(*shape_idx) {
+                    shape.set_ctrl(*bez_idx, *ctrl_idx, *orig_pos + delta, mode);
+                }
+            }
+
+            ActiveDrag::CurveSegment {
+                shape_idx,
+                bez_idx,
+                orig_p0,
+                orig_p1,
+                orig_p2,
+                orig_p3,
+            } => {
+                // the whole segment moves, its neighbors stay connected
+                if let Some(shape) = app.shapes.get_mut(*shape_idx) {
+                    let seg = CubicBez::new(
+                        *orig_p0 + delta,
+                        *orig_p1 + delta,
+                        *orig_p2 + delta,
+                        *orig_p3 + delta,
+                    );
+                    shape.set_segment(*bez_idx, seg);
+                }
+            }
+
+            ActiveDrag::None => {
+                // clicking/dragging empty space—do nothing
+            }
+        }
+    }
+
+    /// whether this drag would move a locked anchor (see `Shaper::locked_points`).
+    /// handles of a locked anchor can still be dragged
+    fn moves_locked_anchor(&self, app: &Shaper) -> bool {
+        let (shape_idx, bez_idx, ends): (usize, usize, &[usize]) = match *self {
+            ActiveDrag::ControlPoint {
+                shape_idx,
+                bez_idx,
+                ctrl_idx,
+                ..
+            } => (shape_idx, bez_idx, &[ctrl_idx]),
+            ActiveDrag::CurveSegment {
+                shape_idx, bez_idx, ..
+            } => (shape_idx, bez_idx, &[0, 3]),
+            ActiveDrag::None => return false,
+        };
+        ends.iter().any(|&ctrl_idx| {
+            app.is_locked(PointId {
+                shape_idx,
+                bez_idx,
+                ctrl_idx,
+            })
+        })
+    }
+}
+
+/// angle step (degrees) a handle snaps to while shift is held
+const HANDLE_SNAP_STEP_DEG: f64 = 45.0;
+
+/// rotate `handle` about `anchor` onto the nearest multiple of
+/// `step_deg`, keeping its distance to the anchor.
+fn snap_angle_about(anchor: Point, handle: Point, step_deg: f64) -> Point {
+    let arm = handle - anchor;
+    let len = arm.hypot();
+    if len == 0.0 {
+        return handle;
+    }
+    let step = step_deg.to_radians();
+    let angle = (arm.atan2() / step).round() * step;
+    anchor + kurbo::Vec2::from_angle(angle) * len
+}
+
+/// angle of the handle arm in degrees, counter-clockwise
+/// as seen on screen (world y points down)
+fn handle_angle_deg(anchor: Point, handle: Point) -> f64 {
+    let arm = handle - anchor;
+    (-arm.y).atan2(arm.x).to_degrees()
+}
+
+/// the anchor (p0/p3) that point `id` belongs to, and the handles
+/// attached to it: the incoming p2 and/or the outgoing p1.
+/// a handle (p1/p2) belongs to the anchor at its end of the segment.
+fn anchor_arms(app: &Shaper, id: PointId) -> Option<(Point, Vec<Point>)> {
+    let shape = app.shapes.get(id.shape_idx)?;
+    let seg = shape.beziers.get(id.bez_idx)?;
+    // the segments coming into / going out of that anchor
+    let (anchor, incoming, outgoing) = match id.ctrl_idx {
+        0 | 1 => (seg.p0, shape.prev_segment(id.bez_idx), Some(id.bez_idx)),
+        _ => (seg.p3, Some(id.bez_idx), shape.next_segment(id.bez_idx)),
+    };
+    let arms = incoming
+        .map(|i| shape.beziers[i].p2)
+        .into_iter()
+        .chain(outgoing.map(|i| shape.beziers[i].p1))
+        .collect();
+    Some((anchor, arms))
+}
+
+/// find the control point or curve segment under `mouse` (world space),
+/// with its current geometry snapshotted for dragging. this is
+/// `hit_test_all`, except that the handles of the selected shapes always
+/// count: they're drawn in this tool even with "Show handles" off
+/// (see `Shaper::handles_shown`).
+fn pick(app: &Shaper, mouse: Point) -> ActiveDrag {
+    let hit = app.hit_test_with_handles(mouse, |shape_idx| app.handles_shown(shape_idx));
+    match hit {
+        HitTestResult::ControlPoint {
+            shape_idx,
+            bez_idx,
+            ctrl_idx,
+        } => {
+            let id = PointId {
+                shape_idx,
+                bez_idx,
+                ctrl_idx,
+            };
+            match app.point_pos(id) {
+                Some(orig_pos) => ActiveDrag::ControlPoint {
+                    shape_idx,
+                    bez_idx,
+                    ctrl_idx,
+                    orig_pos,
+                },
+                None => ActiveDrag::None,
+            }
+        }
+        HitTestResult::CurveSegment {
+            shape_idx,
+            bez_idx,
+            ..
+        } => match app.shapes.get(shape_idx).and_then(|shape| shape.beziers.get(bez_idx)) {
+            Some(bez) => ActiveDrag::CurveSegment {
+                shape_idx,
+                bez_idx,
+                orig_p0: bez.p0,
+                orig_p1: bez.p1,
+                orig_p2: bez.p2,
+                orig_p3: bez.p3,
+            },
+            None => ActiveDrag::None,
+        },
+        // a shape's body can't be dragged here
+        HitTestResult::ShapeBody { .. } | HitTestResult::None => ActiveDrag::None,
+    }
+}
+
+/// hover text of the buttons for the selected points, while there are none
+const NO_POINTS: &str = "select points first";
+
 pub struct EditingTool {
-    /// remember the pointer position at the start of drag
-    drag_start: Option<Pos2>,
+    /// remember the pointer position (world space) at the start of drag
+    drag_start: Option<Point>,
 
     /// When dragging, remember exactly what control/segment is “active”
     active_drag: ActiveDrag,
 
-    move_mode: MoveMode,
+    /// the dragged shape (index, segments) as it was at drag start,
+    /// what escape puts back
+    drag_backup: Option<(usize, Vec<CubicBez>)>,
+
+    /// what the handles do while a point is dragged
+    handle_mode: HandleMode,
+
+    /// the point under the pointer, if not dragging
+    hovered_point: Option<PointId>,
+
+    /// while shift-snapping a handle: its snapped position
+    /// (world space) and angle in degrees, for the label
+    snapped_angle: Option<(Point, f64)>,
+
+    /// the last segment clicked on (shape_idx, bez_idx),
+    /// what "Straighten segment" applies to
+    picked_segment: Option<(usize, usize)>,
+
+    /// how far (degrees) two segments may bend at an anchor for
+    /// "Remove collinear anchors" to still merge them
+    collinear_tol_deg: f64,
 }
 
 impl EditingTool {
@@ -64,239 +247,597 @@ impl EditingTool {
             drag_start: None,
 
             active_drag: ActiveDrag::None,
+            drag_backup: None,
             // selected_shape_index: -1,
             // selected_bezier_index: -1,
-            move_mode: MoveMode::MovePoint,
+            handle_mode: HandleMode::Carry,
+            hovered_point: None,
+            snapped_angle: None,
+            picked_segment: None,
+            collinear_tol_deg: model::STRAIGHT_ANGLE_TOL.to_degrees(),
         }
     }
+
+    /// press at `mouse` (world space): select what's under it and
+    /// start dragging it, unless it would move a locked anchor
+    fn begin_drag(&mut self, app: &mut Shaper, mouse: Point) {
+        self.drag_start = Some(mouse);
+        let mut found = pick(app, mouse);
+
+        // whatever was clicked becomes the selection
+        match &found {
+            ActiveDrag::ControlPoint {
+                shape_idx,
+                bez_idx,
+                ctrl_idx,
+                ..
+            } => app.select_single_point(PointId {
+                shape_idx: *shape_idx,
+                bez_idx: *bez_idx,
+                ctrl_idx: *ctrl_idx,
+            }),
+            ActiveDrag::CurveSegment { shape_idx, .. } => app.select_single_shape(*shape_idx),
+            ActiveDrag::None => app.clear_selection(),
+        }
+
+        self.picked_segment = match found {
+            ActiveDrag::CurveSegment {
+                shape_idx, bez_idx, ..
+            } => Some((shape_idx, bez_idx)),
+            _ => None,
+        };
+        // a locked anchor gets selected (to unlock it) but stays put
+        if found.moves_locked_anchor(app) {
+            found = ActiveDrag::None;
+        }
+        self.drag_backup = match found {
+            ActiveDrag::ControlPoint { shape_idx, .. }
+            | ActiveDrag::CurveSegment { shape_idx, .. } => app
+                .shapes
+                .get(shape_idx)
+                .map(|shape| (shape_idx, shape.beziers.clone())),
+            ActiveDrag::None => None,
+        };
+        self.active_drag = found;
+    }
+
+    /// the pointer moved to `mouse` (world space) while dragging: move what
+    /// was picked by as much as it moved since `begin_drag`. `snap` (shift)
+    /// snaps the angle of a dragged handle (p1/p2) around its anchor,
+    /// keeping the handle length
+    fn drag_to(&mut self, app: &mut Shaper, mouse: Point, snap: bool) {
+        let Some(start) = self.drag_start else {
+            return;
+        };
+        let mut delta = (mouse - start).to_point();
+
+        self.snapped_angle = None;
+        if let ActiveDrag::ControlPoint {
+            shape_idx,
+            bez_idx,
+            ctrl_idx: ctrl_idx @ (1 | 2),
+            orig_pos,
+        } = self.active_drag
+        {
+            let anchor = app.point_pos(PointId {
+                shape_idx,
+                bez_idx,
+                ctrl_idx: if ctrl_idx == 1 { 0 } else { 3 },
+            });
+            if let (true, Some(anchor)) = (snap, anchor) {
+                let target = orig_pos + delta.to_vec2();
+                let snapped = snap_angle_about(anchor, target, HANDLE_SNAP_STEP_DEG);
+                delta = (snapped - orig_pos).to_point();
+                self.snapped_angle = Some((snapped, handle_angle_deg(anchor, snapped)));
+            }
+        }
+
+        self.active_drag.apply(app, delta, self.handle_mode);
+    }
+
+    /// the drag is over, whatever moved stays where it is
+    fn end_drag(&mut self) {
+        self.drag_start = None;
+        self.active_drag = ActiveDrag::None;
+        self.drag_backup = None;
+        self.snapped_angle = None;
+    }
+
+    /// abort the current drag and put back the segments of the dragged
+    /// shape as they were at drag start. replaying the drag with a zero
+    /// delta isn't enough: in `HandleMode::Smooth` that turns the handle
+    /// on the other side of the anchor again.
+    fn cancel_drag(&mut self, app: &mut Shaper) {
+        let backup = self.drag_backup.take();
+        let shape = backup.and_then(|(idx, beziers)| Some((app.shapes.get_mut(idx)?, beziers)));
+        if let Some((shape, beziers)) = shape {
+            // not `set_beziers`, the shape keeps its corners
+            *shape.beziers_mut() = beziers;
+        }
+        self.active_drag = ActiveDrag::None;
+        self.drag_start = None;
+    }
 }
 
 impl Tool for EditingTool {
     fn handle_input(&mut self, ctx: &Context, response: &Response, app: &mut Shaper) {
-        if let Some(pointer_pos) = response.hover_pos() {
-            let scroll_delta = ctx.input(|i| i.smooth_scroll_delta.y);
-            if scroll_delta != 0.0 {
-                // convert world position before zoom
-                let old_world_pos = app.screen_to_world(pointer_pos);
-
-                // apply zoom
-                let zoom_delta = (scroll_delta * 0.009).exp();
-                app.zoom *= zoom_delta;
-                app.zoom = app.zoom.clamp(app.min_zoom, app.max_zoom);
-
-                // convert world position after zoom
-                let new_world_pos = app.screen_to_world(pointer_pos);
-
-                // adjust pan offset to keep pointer position stable
-                // convert Pos2 difference directly to Vec2
-                let world_delta = Vec2::new(
-                    new_world_pos.x - old_world_pos.x,
-                    new_world_pos.y - old_world_pos.y,
-                );
-                app.pan_offset += world_delta * app.zoom;
+        // zoom / pan with the mouse wheel, see `Shaper::handle_scroll`
+        app.handle_scroll(ctx, response);
+
+        let pointer = response
+            .interact_pointer_pos()
+            .map(|pos| app.screen_to_world(pos))
+            .map(|world| Point::new(world.x as f64, world.y as f64));
+        if let (true, Some(mouse)) = (response.drag_started(), pointer) {
+            self.begin_drag(app, mouse);
+        }
+
+        // "select connected": double-click a point or segment, or Ctrl+L,
+        // to select every anchor of its shape (e.g. to move it all at once)
+        let (double_clicked, select_connected) = ctx.input(|i| {
+            (
+                i.pointer.button_double_clicked(egui::PointerButton::Primary),
+                i.modifiers.command && i.key_pressed(egui::Key::L),
+            )
+        });
+        let double_click_pos = response
+            .interact_pointer_pos()
+            .filter(|_| double_clicked && response.hovered());
+        if let Some(pos) = double_click_pos {
+            let world = app.screen_to_world(pos);
+            if let ActiveDrag::ControlPoint { shape_idx, .. }
+            | ActiveDrag::CurveSegment { shape_idx, .. } =
+                pick(app, Point::new(world.x as f64, world.y as f64))
+            {
+                app.select_all_points_in_shape(shape_idx);
+            }
+        }
+        if select_connected && !ctx.wants_keyboard_input() {
+            let shape_idx = app
+                .selected_points
+                .iter()
+                .map(|id| id.shape_idx)
+                .chain(app.selected_shapes.iter().copied())
+                .min();
+            if let Some(shape_idx) = shape_idx {
+                app.select_all_points_in_shape(shape_idx);
+            }
+        }
 
-                // percentage calculation:
-                app.calc_zoom_level();
+        // escape aborts the drag: put the geometry back where it started.
+        // with no drag going on, it leaves a temporarily entered Edit
+        // tool (e.g. double-click in Selection) for the previous tool
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            if self.drag_start.is_some() {
+                self.cancel_drag(app);
+            } else if let Some(prev) = app.previous_tool.take() {
+                app.selected_points.clear();
+                app.selected_tool = prev;
+                return;
             }
         }
 
-        if response.drag_started() {
-            if let Some(mut pos2) = response.interact_pointer_pos() {
-                pos2 = app.screen_to_world(pos2);
-                self.drag_start = Some(pos2);
-                let mouse = Point::new(pos2.x as f64, pos2.y as f64);
-
-                // iterate shapes → beziers for control-point or curve hit
-                let mut found = ActiveDrag::None;
-                'outer: for (shape_idx, shape) in app.shapes.iter().enumerate() {
-                    // tolerance for point and curve (world space)
-                    let tol_point_ws: f64 = app.handle_radius as f64;
-                    let tol_curve_ws: f64 = app.overlay_beziers_thickness as f64;
-
-                    for (bez_idx, bez) in shape.beziers.iter().enumerate() {
-                        // control handles (p0..p3)
-                        let handles = [bez.p0, bez.p1, bez.p2, bez.p3];
-                        for (ctrl_i, &pt) in handles.iter().enumerate() {
-                            let dx = mouse.x - pt.x;
-                            let dy = mouse.y - pt.y;
-                            if (dx * dx + dy * dy).sqrt() <= tol_point_ws {
-                                found = ActiveDrag::ControlPoint {
-                                    shape_idx,
-                                    bez_idx,
-                                    ctrl_idx: ctrl_i,
-                                    orig_pos: pt,
-                                };
-                                break 'outer;
-                            }
-                        }
+        // Delete/Backspace removes the selected points (see `delete_selected_points`)
+        // (not while typing in a text field)
+        let delete = !ctx.wants_keyboard_input()
+            && ctx.input(|i| i.key_pressed(egui::Key::Delete) || i.key_pressed(egui::Key::Backspace));
+        if delete && self.drag_start.is_none() && !app.selected_points.is_empty() {
+            app.delete_selected_points();
+            self.hovered_point = None;
+            self.picked_segment = None;
+        }
 
-                        // 2b) curve‐itself: use `nearest(...)` and compare distance_sq
-                        // Kurbo’s `nearest(...)` returns a `Nearest { distance_sq, t }`
-                        // supply a small “accuracy” (1e-6) to get a precise t, then check if
-                        // dist² ≤ tol²:
-                        let nearest: Nearest = bez.nearest(mouse, 1e-6);
-                        if nearest.distance_sq <= tol_curve_ws * tol_curve_ws {
-                            // Click is ≤ tol pixels from the curve
-                            found = ActiveDrag::CurveSegment {
-                                shape_idx,
-                                bez_idx,
-                                orig_p0: bez.p0,
-                                orig_p1: bez.p1,
-                                orig_p2: bez.p2,
-                                orig_p3: bez.p3,
-                            };
-                            break 'outer;
-                        }
-                    }
-                }
+        // while dragging: move the single handle (+ neighbor) or full segment
+        if let (true, Some(mouse)) = (response.dragged(), pointer) {
+            let snap = ctx.input(|i| i.modifiers.shift);
+            self.drag_to(app, mouse, snap);
+        }
 
-                self.active_drag = found;
-            }
+        if response.drag_stopped() {
+            self.end_drag();
         }
 
-        // while dragging: compute delta and update either
-        // the single handle (+ neighbor) or full segment
-        if response.dragged() {
-            if let (Some(start_pos), Some(mut curr_pos)) =
-                (self.drag_start, response.interact_pointer_pos())
-            {
-                // start_pos is using self.drag_start which is already
-                // converted to world coordinates from drag_start
-                // but curr_pos needs converting of course
-                curr_pos = app.screen_to_world(curr_pos);
-
-                let delta_screen: Vec2 = curr_pos - start_pos;
-                let dx: f64 = delta_screen.x as f64;
-                let dy: f64 = delta_screen.y as f64;
-                let delta = Point::new(dx, dy);
-
-                match &self.active_drag {
-                    ActiveDrag::ControlPoint {
+        // precise cursor over points, grab over segments
+        self.hovered_point = None;
+        if self.drag_start.is_some() && !matches!(self.active_drag, ActiveDrag::None) {
+            ctx.set_cursor_icon(egui::CursorIcon::Grabbing);
+        } else if let Some(pos) = response.hover_pos() {
+            let world = app.screen_to_world(pos);
+            let hovered = pick(app, Point::new(world.x as f64, world.y as f64));
+            // can't be dragged: a locked anchor or a segment ending at one
+            let locked = hovered.moves_locked_anchor(app);
+            match hovered {
+                ActiveDrag::ControlPoint {
+                    shape_idx,
+                    bez_idx,
+                    ctrl_idx,
+                    ..
+                } => {
+                    ctx.set_cursor_icon(if locked {
+                        egui::CursorIcon::NotAllowed
+                    } else {
+                        egui::CursorIcon::Crosshair
+                    });
+                    self.hovered_point = Some(PointId {
                         shape_idx,
                         bez_idx,
                         ctrl_idx,
-                        orig_pos,
-                    } => {
-                        let shape: &mut crate::shape::Shape = &mut app.shapes[*shape_idx];
-                        // mutable reference to the segment we clicked
-                        let bez: &mut kurbo::CubicBez = &mut shape.beziers[*bez_idx];
-                        let new_pt: Point = Point::new(orig_pos.x + delta.x, orig_pos.y + delta.y);
-
-                        // move the chosen control handle
-                        /*
-                        (enhanced) dragging behavior:
-                        - when dragging an endpoint (p0 or p3), the connected control handles 
-                        - (p1 or p2 of the same segment, and p2 or p1 of the neighboring segment
-                        -  if present) are also moved by the same delta. this preserves the 
-                        - relative positions of the handles and ensures smooth curve editing.
-                        - 
-                        - when dragging a control handle (p1 or p2), only that handle is moved.
-                        - 
-                        - this behavior mimics professional vector editors, making it easier 
-                        - to maintain smooth transitions between connected Bézier segments.
-                        */
-                        match ctrl_idx {
-                            0 => {
-                                // move this start‐point
-                                let delta_vec: Point = Point::new(new_pt.x - bez.p0.x, new_pt.y - bez.p0.y);
-                                bez.p0 = new_pt;
-                                // also move the first control handle by the same delta
-                                bez.p1 = Point::new(bez.p1.x + delta_vec.x, bez.p1.y + delta_vec.y);
-                                // also update the previous segment’s p3 and p2, if they exist
-                                if *bez_idx > 0 {
-                                    let prev: &mut kurbo::CubicBez = &mut shape.beziers[*bez_idx - 1];
-                                    prev.p3 = new_pt;
-                                    prev.p2 = Point::new(prev.p2.x + delta_vec.x, prev.p2.y + delta_vec.y);
-                                }
-                            }
-                            1 => {
-                                // move this first handle
-                                bez.p1 = new_pt;
-                            }
-                            2 => {
-                                // move this second handle
-                                bez.p2 = new_pt;
-                            }
-                            3 => {
-                                // move this end‐point
-                                let delta_vec: Point = Point::new(new_pt.x - bez.p3.x, new_pt.y - bez.p3.y);
-                                bez.p3 = new_pt;
-                                // also move the second control handle by the same delta
-                                bez.p2 = Point::new(bez.p2.x + delta_vec.x, bez.p2.y + delta_vec.y);
-                                // also update the next segment’s p0 and p1, if they exist
-                                if *bez_idx + 1 < shape.beziers.len() {
-                                    let next: &mut kurbo::CubicBez = &mut shape.beziers[*bez_idx + 1];
-                                    next.p0 = new_pt;
-                                    next.p1 = Point::new(next.p1.x + delta_vec.x, next.p1.y + delta_vec.y);
-                                }
-                            }
-                            _ => unreachable!(),
-                        }
-                    }
+                    });
+                }
+                ActiveDrag::CurveSegment { .. } => ctx.set_cursor_icon(if locked {
+                    egui::CursorIcon::NotAllowed
+                } else {
+                    egui::CursorIcon::Grab
+                }),
+                ActiveDrag::None => {}
+            }
+        }
+    }
 
-                    ActiveDrag::CurveSegment {
-                        shape_idx,
-                        bez_idx,
-                        orig_p0,
-                        orig_p1,
-                        orig_p2,
-                        orig_p3,
-                    } => {
-                        let shape = &mut app.shapes[*shape_idx];
-
-                        // compute the new positions first:
-                        let new_p0 = Point::new(orig_p0.x + delta.x, orig_p0.y + delta.y);
-                        let new_p1 = Point::new(orig_p1.x + delta.x, orig_p1.y + delta.y);
-                        let new_p2 = Point::new(orig_p2.x + delta.x, orig_p2.y + delta.y);
-                        let new_p3 = Point::new(orig_p3.x + delta.x, orig_p3.y + delta.y);
-
-                        // mutably borrow the “current” segment, write all
-                        // four points, then drop it immediately.
-                        {
-                            let bez = &mut shape.beziers[*bez_idx];
-                            bez.p0 = new_p0;
-                            bez.p1 = new_p1;
-                            bez.p2 = new_p2;
-                            bez.p3 = new_p3;
-                        } // <-- `bez` goes out of scope/dropped here
-
-                        // now that `bez` is dropped, it's safe to borrow neighbors:
-                        if *bez_idx > 0 {
-                            let prev = &mut shape.beziers[*bez_idx - 1];
-                            prev.p3 = new_p0;
-                        }
-                        if *bez_idx + 1 < shape.beziers.len() {
-                            let next = &mut shape.beziers[*bez_idx + 1];
-                            next.p0 = new_p3;
-                        }
-                    }
+    fn paint(&mut self, _ctx: &Context, painter: &Painter, app: &Shaper) {
+        if app.show_point_labels {
+            for shape in app.selected_shapes.iter().filter_map(|&idx| app.shapes.get(idx)) {
+                shape.draw_point_labels(painter, app);
+            }
+        }
 
-                    ActiveDrag::None => {
-                        // clicking/dragging empty space—do nothing
-                    }
-                }
+        // highlight the selected point/s on top of the handles
+        let radius = app.handle_radius + 1.0;
+        for id in &app.selected_points {
+            if let Some(k) = app.point_pos(*id) {
+                let pos = app.world_to_screen(Pos2::new(k.x as f32, k.y as f32));
+                let shape = match id.ctrl_idx {
+                    0 | 3 => app.anchor_shape,
+                    _ => app.control_shape,
+                };
+                shape.paint(painter, pos, radius, app.selected_p_color);
             }
         }
 
-        // on drag end, clear state
-        if response.drag_stopped() {
-            self.drag_start = None;
-            self.active_drag = ActiveDrag::None;
+        // locked anchors get a ring around them
+        for &id in app.locked_points.iter().filter(|id| app.handles_shown(id.shape_idx)) {
+            if let Some(k) = app.point_pos(id) {
+                let pos = app.world_to_screen(Pos2::new(k.x as f32, k.y as f32));
+                painter.circle_stroke(pos, radius + 3.0, egui::Stroke::new(1.5, app.p_border_color));
+            }
         }
-    }
 
-    fn paint(&mut self,  _ctx: &Context, _painter: &Painter, _app: &Shaper) {}
+        // show both handle arms of the anchor being dragged (or hovered),
+        // labelled with their length and angle
+        let focus = match self.active_drag {
+            ActiveDrag::ControlPoint {
+                shape_idx,
+                bez_idx,
+                ctrl_idx,
+                ..
+            } => Some(PointId {
+                shape_idx,
+                bez_idx,
+                ctrl_idx,
+            }),
+            _ => self.hovered_point,
+        };
+        if let Some((anchor, arms)) = focus.and_then(|id| anchor_arms(app, id)) {
+            let a = app.world_to_screen(Pos2::new(anchor.x as f32, anchor.y as f32));
+            for handle in arms {
+                let h = app.world_to_screen(Pos2::new(handle.x as f32, handle.y as f32));
+                painter.line_segment([a, h], egui::Stroke::new(1.0, app.selected_p_color));
+                app.control_shape.paint(painter, h, radius, app.selected_p_color);
+                painter.text(
+                    h + Vec2::new(8.0, 8.0),
+                    Align2::LEFT_TOP,
+                    format!(
+                        "{:.1} px, {:.0}°",
+                        anchor.distance(handle),
+                        handle_angle_deg(anchor, handle)
+                    ),
+                    FontId::proportional(12.0),
+                    app.handle_arm_color,
+                );
+            }
+        }
 
-    fn tool_ui(&mut self, ctx: &Context, _app: &mut Shaper) {
+        // label the snapped handle with its angle
+        if let Some((handle, angle)) = self.snapped_angle {
+            let pos = app.world_to_screen(Pos2::new(handle.x as f32, handle.y as f32));
+            painter.text(
+                pos + Vec2::new(8.0, -8.0),
+                Align2::LEFT_BOTTOM,
+                format!("{:.0}°", angle),
+                FontId::proportional(12.0),
+                app.selected_p_color,
+            );
+        }
+    }
+
+    fn tool_ui(&mut self, ctx: &Context, app: &mut Shaper) {
         egui::TopBottomPanel::top("edit settings")
             .resizable(false)
             .show(ctx, |ui| {
                 ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
                     // let move_segment_checkbox = egui::Checkbox::new(move_segment, "Move Segment").indeterminate(false);
 
-                    ui.radio_value(&mut self.move_mode, MoveMode::MovePoint, "Move Point");
-                    ui.radio_value(&mut self.move_mode, MoveMode::MoveControlPoints, "Move Control Points");
+                    ui.radio_value(&mut self.handle_mode, HandleMode::Free, "Move Point");
+                    ui.radio_value(&mut self.handle_mode, HandleMode::Carry, "Move Control Points");
+                    ui.radio_value(&mut self.handle_mode, HandleMode::Smooth, "Smooth");
+
+                    // flatten the handles of the clicked segment onto its chord
+                    ui.separator();
+                    let straighten = egui::Button::new("Straighten segment");
+                    let straighten = ui
+                        .add_enabled(self.picked_segment.is_some(), straighten)
+                        .on_disabled_hover_text("click a segment first");
+                    if straighten.clicked() {
+                        if let Some((shape_idx, bez_idx)) = self.picked_segment {
+                            app.straighten_segment(shape_idx, bez_idx);
+                        }
+                    }
+
+                    // pin the selected anchors in place, or free them again
+                    let points = app.selected_point_count();
+                    let all_locked = points > 0
+                        && app.selected_points.iter().all(|&id| {
+                            app.anchor_id(id).is_some_and(|a| app.locked_points.contains(&a))
+                        });
+                    let lock_label = if all_locked { "Unlock points" } else { "Lock points" };
+                    let lock = egui::Button::new(lock_label);
+                    let lock = ui.add_enabled(points > 0, lock).on_disabled_hover_text(NO_POINTS);
+                    if lock.clicked() {
+                        app.toggle_lock_selected_points();
+                    }
+
+                    // smooth the handles of the selected anchors again
+                    let auto = egui::Button::new("Auto handles");
+                    let auto = ui.add_enabled(points > 0, auto).on_disabled_hover_text(NO_POINTS);
+                    if auto.clicked() {
+                        let ids: Vec<PointId> = app.selected_points.iter().copied().collect();
+                        for id in ids {
+                            app.auto_handles(id);
+                        }
+                    }
+
+                    // merge the straight runs of the shapes with selected points
+                    ui.separator();
+                    let shapes: HashSet<usize> =
+                        app.selected_points.iter().map(|id| id.shape_idx).collect();
+                    let merge = egui::Button::new("Remove collinear anchors");
+                    let merge = ui.add_enabled(points > 0, merge).on_disabled_hover_text(NO_POINTS);
+                    if merge.clicked() {
+                        let tol = self.collinear_tol_deg.to_radians();
+                        for shape_idx in shapes {
+                            app.remove_redundant_anchors(shape_idx, tol);
+                        }
+                        self.picked_segment = None;
+                    }
+                    ui.add(
+                        egui::DragValue::new(&mut self.collinear_tol_deg)
+                            .range(0.0..=45.0)
+                            .speed(0.1)
+                            .suffix("°"),
+                    );
                 });
             });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use eframe::egui::Color32;
+
+    use super::*;
+
+    #[test]
+    fn applying_a_drag_of_a_deleted_shape_does_nothing() {
+        let mut app = Shaper::default();
+        app.add_line(Point::new(0.0, 0.0), Point::new(100.0, 0.0), 2.0, Color32::BLACK);
+        app.show_handles = true;
+
+        // one drag on the end point, one on the curve
+        let on_point = pick(&app, Point::new(100.0, 0.0));
+        let on_curve = pick(&app, Point::new(50.0, 0.0));
+        assert!(matches!(on_point, ActiveDrag::ControlPoint { ctrl_idx: 3, .. }));
+        assert!(matches!(on_curve, ActiveDrag::CurveSegment { .. }));
+
+        app.remove_shape(0);
+        for drag in [on_point, on_curve] {
+            drag.apply(&mut app, Point::new(5.0, 5.0), HandleMode::Carry);
+            assert!(!drag.moves_locked_anchor(&app));
+        }
+        assert!(app.shapes.is_empty());
+    }
+
+    #[test]
+    fn cancelling_a_smooth_handle_drag_restores_the_corner() {
+        let mut app = Shaper::default();
+        app.add_line(Point::new(0.0, 0.0), Point::new(100.0, 0.0), 2.0, Color32::BLACK);
+        app.show_handles = true;
+        // a sharp corner at (100, 0): the arms point left and up
+        let shape = &mut app.shapes[0];
+        shape.set_ctrl(0, 3, Point::new(100.0, 0.0), HandleMode::Free);
+        shape.beziers_mut().push(CubicBez::new(
+            (100.0, 0.0),
+            (100.0, -30.0),
+            (100.0, -60.0),
+            (100.0, -100.0),
+        ));
+        shape.set_ctrl(0, 2, Point::new(70.0, 0.0), HandleMode::Free);
+        let before = app.shapes[0].beziers.clone();
+
+        let mut tool = EditingTool::new();
+        tool.handle_mode = HandleMode::Smooth;
+        tool.begin_drag(&mut app, Point::new(70.0, 0.0));
+        assert!(matches!(tool.active_drag, ActiveDrag::ControlPoint { ctrl_idx: 2, .. }));
+        tool.drag_to(&mut app, Point::new(70.0, 20.0), false);
+        assert_ne!(app.shapes[0].beziers[1].p1, before[1].p1);
+
+        tool.cancel_drag(&mut app);
+        assert_eq!(app.shapes[0].beziers, before);
+        assert!(matches!(tool.active_drag, ActiveDrag::None));
+    }
+
+    #[test]
+    fn pick_reaches_as_far_on_screen_at_any_zoom() {
+        let mut app = line_in_edit_tool();
+        app.selected_shapes.insert(0);
+        let picked = |app: &Shaper, x: f64, y: f64| match pick(app, Point::new(x, y)) {
+            ActiveDrag::ControlPoint { ctrl_idx, .. } => Some(ctrl_idx),
+            ActiveDrag::CurveSegment { .. } => Some(99),
+            ActiveDrag::None => None,
+        };
+        // (slop + handle radius) screen px for points, at least
+        // `min_pick_px` screen px for the curve, whatever the zoom
+        app.zoom = 4.0;
+        assert_eq!(picked(&app, 100.0, 1.0), Some(3));
+        assert_eq!(picked(&app, 50.0, 1.5), Some(99));
+        assert_eq!(picked(&app, 50.0, 2.0), None);
+
+        app.zoom = 0.25;
+        assert_eq!(picked(&app, 100.0, 15.0), Some(3));
+        assert_eq!(picked(&app, 50.0, 15.0), Some(99));
+        assert_eq!(picked(&app, 50.0, 18.0), None);
+    }
+
+    #[test]
+    fn pick_turns_what_was_hit_into_a_drag_of_it() {
+        let mut app = Shaper::default();
+        app.add_rect(kurbo::Rect::new(0.0, 0.0, 100.0, 100.0), 2.0, Color32::BLACK);
+        app.selected_tool = crate::ToolKind::Editing;
+        app.selected_shapes.insert(0);
+
+        for (x, y) in [(100.0, 0.0), (0.0, 100.0), (50.0, 0.0), (100.0, 60.0), (50.0, 50.0)] {
+            let mouse = Point::new(x, y);
+            let hit = app.hit_test_with_handles(mouse, |idx| app.handles_shown(idx));
+            match (hit, pick(&app, mouse)) {
+                (
+                    HitTestResult::ControlPoint { shape_idx, bez_idx, ctrl_idx },
+                    ActiveDrag::ControlPoint { shape_idx: s, bez_idx: b, ctrl_idx: c, orig_pos },
+                ) => {
+                    assert_eq!((s, b, c), (shape_idx, bez_idx, ctrl_idx));
+                    assert_eq!(orig_pos, mouse);
+                }
+                (
+                    HitTestResult::CurveSegment { shape_idx, bez_idx, .. },
+                    ActiveDrag::CurveSegment {
+                        shape_idx: s,
+                        bez_idx: b,
+                        orig_p0,
+                        orig_p1,
+                        orig_p2,
+                        orig_p3,
+                    },
+                ) => {
+                    assert_eq!((s, b), (shape_idx, bez_idx));
+                    let bez = app.shapes[s].beziers[b];
+                    assert_eq!(CubicBez::new(orig_p0, orig_p1, orig_p2, orig_p3), bez);
+                }
+                // the inside of the rect is a hit, but nothing to drag here
+                (HitTestResult::ShapeBody { .. }, ActiveDrag::None) => {
+                    assert_eq!(mouse, Point::new(50.0, 50.0));
+                }
+                (hit, _) => panic!("{hit:?} at {mouse:?} picked the wrong drag"),
+            }
+        }
+    }
+
+    /// a line from (0, 0) to (100, 0), being edited
+    fn line_in_edit_tool() -> Shaper {
+        let mut app = Shaper::default();
+        app.add_line(Point::new(0.0, 0.0), Point::new(100.0, 0.0), 2.0, Color32::BLACK);
+        app.selected_tool = crate::ToolKind::Editing;
+        app
+    }
+
+    /// press at `from`, drag to `to` and let go, like one mouse gesture
+    fn drag(tool: &mut EditingTool, app: &mut Shaper, from: Point, to: Point, snap: bool) {
+        tool.begin_drag(app, from);
+        tool.drag_to(app, to, snap);
+        tool.end_drag();
+    }
+
+    #[test]
+    fn dragging_the_curve_moves_the_segment() {
+        let mut app = line_in_edit_tool();
+        let mut tool = EditingTool::new();
+        drag(&mut tool, &mut app, Point::new(50.0, 0.0), Point::new(50.0, 10.0), false);
+
+        assert!(app.selected_shapes.contains(&0));
+        assert_eq!(tool.picked_segment, Some((0, 0)));
+        let bez = app.shapes[0].beziers[0];
+        assert_eq!((bez.p0, bez.p3), (Point::new(0.0, 10.0), Point::new(100.0, 10.0)));
+        assert!(matches!(tool.active_drag, ActiveDrag::None));
+        assert!(tool.drag_start.is_none());
+    }
+
+    #[test]
+    fn dragging_an_anchor_of_the_edited_shape_moves_it() {
+        let mut app = line_in_edit_tool();
+        let mut tool = EditingTool::new();
+        // a click on the curve first, that shows its handles
+        drag(&mut tool, &mut app, Point::new(50.0, 0.0), Point::new(50.0, 0.0), false);
+        drag(&mut tool, &mut app, Point::new(100.0, 0.0), Point::new(110.0, 20.0), false);
+
+        let end = PointId {
+            shape_idx: 0,
+            bez_idx: 0,
+            ctrl_idx: 3,
+        };
+        assert!(app.selected_points.contains(&end));
+        let bez = app.shapes[0].beziers[0];
+        assert_eq!(bez.p0, Point::ZERO);
+        assert_eq!(bez.p3, Point::new(110.0, 20.0));
+        // `HandleMode::Carry` takes the anchor's handle along
+        assert!((bez.p2 - Point::new(2.0 * 100.0 / 3.0 + 10.0, 20.0)).hypot() < 1e-9);
+    }
+
+    #[test]
+    fn a_locked_anchor_gets_selected_but_stays_put() {
+        let mut app = line_in_edit_tool();
+        app.show_handles = true;
+        let mut tool = EditingTool::new();
+        tool.begin_drag(&mut app, Point::new(100.0, 0.0));
+        tool.end_drag();
+        app.toggle_lock_selected_points();
+
+        let before = app.shapes[0].beziers.clone();
+        drag(&mut tool, &mut app, Point::new(100.0, 0.0), Point::new(110.0, 20.0), false);
+        assert_eq!(app.shapes[0].beziers, before);
+        assert_eq!(app.selected_point_count(), 1);
+        // the segment ends at it, so it stays put too
+        drag(&mut tool, &mut app, Point::new(50.0, 0.0), Point::new(50.0, 10.0), false);
+        assert_eq!(app.shapes[0].beziers, before);
+    }
+
+    #[test]
+    fn shift_snaps_a_handle_to_45_degrees() {
+        let mut app = line_in_edit_tool();
+        app.show_handles = true;
+        let mut tool = EditingTool::new();
+        let handle = app.shapes[0].beziers[0].p1;
+        tool.begin_drag(&mut app, handle);
+        tool.drag_to(&mut app, Point::new(30.0, 28.0), true);
+
+        // rotated onto the diagonal, as long as the pointer is from the anchor
+        let p1 = app.shapes[0].beziers[0].p1;
+        assert!((p1.x - p1.y).abs() < 1e-9, "{p1:?}");
+        assert!((p1.to_vec2().hypot() - Point::new(30.0, 28.0).to_vec2().hypot()).abs() < 1e-9);
+        let (_, angle) = tool.snapped_angle.unwrap();
+        assert!((angle + 45.0).abs() < 1e-9);
+
+        tool.end_drag();
+        assert!(tool.snapped_angle.is_none());
+    }
+
+    #[test]
+    fn pressing_empty_canvas_clears_the_selection_and_drags_nothing() {
+        let mut app = line_in_edit_tool();
+        let mut tool = EditingTool::new();
+        tool.begin_drag(&mut app, Point::new(50.0, 0.0));
+        tool.end_drag();
+        let before = app.shapes[0].beziers.clone();
+
+        drag(&mut tool, &mut app, Point::new(50.0, 80.0), Point::new(50.0, 90.0), false);
+        assert!(app.selected_shapes.is_empty() && app.selected_points.is_empty());
+        assert_eq!(tool.picked_segment, None);
+        assert_eq!(app.shapes[0].beziers, before);
+    }
+}
@@ -0,0 +1,90 @@
+use crate::Shaper;
+use crate::tool::Tool;
+use eframe::egui::{self, Align, Color32, Context, Layout, Painter, Pos2, Response, Stroke};
+use kurbo::Point as KPoint;
+
+pub struct KnifeTool {
+    /// world position where the cut line starts
+    cut_start: Option<Pos2>,
+    /// world position of the other end, follows the pointer
+    cut_end: Option<Pos2>,
+
+    /// how many shapes the last cut split, for the tool bar
+    last_cut_count: Option<usize>,
+}
+
+impl KnifeTool {
+    pub fn new() -> Self {
+        KnifeTool {
+            cut_start: None,
+            cut_end: None,
+            last_cut_count: None,
+        }
+    }
+}
+
+impl Tool for KnifeTool {
+    fn handle_input(&mut self, ctx: &Context, response: &Response, app: &mut Shaper) {
+        // zoom / pan with the mouse wheel, see `Shaper::handle_scroll`
+        app.handle_scroll(ctx, response);
+
+        if response.hovered() {
+            ctx.set_cursor_icon(egui::CursorIcon::Crosshair);
+        }
+
+        // drag out a straight line, everything it crosses
+        // gets cut when the button is released
+        if response.drag_started() {
+            self.cut_start = response
+                .interact_pointer_pos()
+                .map(|pos| app.screen_to_world(pos));
+            self.cut_end = self.cut_start;
+        }
+
+        if response.dragged() {
+            if let Some(pos) = response.interact_pointer_pos() {
+                self.cut_end = Some(app.screen_to_world(pos));
+            }
+        }
+
+        if response.drag_stopped() {
+            if let (Some(a), Some(b)) = (self.cut_start.take(), self.cut_end.take()) {
+                if a != b {
+                    let count = app.cut_shapes_with_line(
+                        KPoint::new(a.x as f64, a.y as f64),
+                        KPoint::new(b.x as f64, b.y as f64),
+                    );
+                    self.last_cut_count = Some(count);
+                }
+            }
+        }
+
+        // Escape drops the cut in progress
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.cut_start = None;
+            self.cut_end = None;
+        }
+    }
+
+    fn paint(&mut self, _ctx: &Context, painter: &Painter, app: &Shaper) {
+        if let (Some(a), Some(b)) = (self.cut_start, self.cut_end) {
+            painter.line_segment(
+                [app.world_to_screen(a), app.world_to_screen(b)],
+                Stroke::new(1.0, Color32::RED),
+            );
+        }
+    }
+
+    fn tool_ui(&mut self, ctx: &Context, _app: &mut Shaper) {
+        egui::TopBottomPanel::top("knife settings")
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
+                    ui.label("Drag a line across shapes to cut them.");
+                    if let Some(count) = self.last_cut_count {
+                        ui.label(format!("Last cut: {count} shape(s)"));
+                    }
+                });
+            });
+    }
+}
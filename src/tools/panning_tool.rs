@@ -22,35 +22,9 @@ impl PanningTool {
 
 impl Tool for PanningTool {
     fn handle_input(&mut self, ctx: &Context, response: &Response, app: &mut Shaper) {
-        // handle zooming with scroll wheel
-        if let Some(pointer_pos) = response.hover_pos() {
-            let scroll_delta = ctx.input(|i| i.smooth_scroll_delta.y);
-            if scroll_delta != 0.0 {
-                
-                if !self.is_panning {
-                    // convert world position before zoom
-                    let old_world_pos = app.screen_to_world(pointer_pos);
-    
-                    // apply zoom
-                    let zoom_delta = (scroll_delta * 0.009).exp();
-                    app.zoom *= zoom_delta;
-                    app.zoom = app.zoom.clamp(app.min_zoom, app.max_zoom);
-    
-                    // convert world position after zoom
-                    let new_world_pos = app.screen_to_world(pointer_pos);
-    
-                    // adjust pan offset to keep pointer position stable
-                    // convert Pos2 difference directly to Vec2
-                    let world_delta = Vec2::new(
-                        new_world_pos.x - old_world_pos.x,
-                        new_world_pos.y - old_world_pos.y,
-                    );
-                    app.pan_offset += world_delta * app.zoom;
-    
-                    // percentage calculation:
-                    app.calc_zoom_level();
-                }
-            }
+        // handle zooming with scroll wheel (not while dragging the view)
+        if !self.is_panning {
+            app.handle_scroll(ctx, response);
         }
 
         // when the user starts dragging, record the initial pointer and pan
@@ -68,6 +42,7 @@ impl Tool for PanningTool {
             {
                 let delta = current - start;
                 app.pan_offset = self.orig_pan + Vec2::new(delta.x, delta.y);
+                app.clamp_pan(response.rect);
             }
         }
 
@@ -76,6 +51,12 @@ impl Tool for PanningTool {
             self.drag_start = None;
             self.is_panning = false;
         }
+
+        if self.is_panning {
+            ctx.set_cursor_icon(egui::CursorIcon::Grabbing);
+        } else if response.hovered() {
+            ctx.set_cursor_icon(egui::CursorIcon::Grab);
+        }
     }
 
     fn paint(&mut self, _ctx: &Context, _painter: &Painter, _app: &Shaper) {
@@ -0,0 +1,860 @@
+use std::collections::HashSet;
+
+use crate::hit_test::HitTestResult;
+use crate::shape::Shape;
+use crate::shape_ops::ArraySpacing;
+use crate::tool::Tool;
+use crate::{Shaper, ToolKind, model};
+use eframe::egui::{
+    self, Align, Color32, Context, Event, Layout, Painter, PointerButton, Pos2, Rect, Response,
+    SliderOrientation, Stroke, Vec2,
+};
+use kurbo::{Point as KPoint, Rect as KRect};
+
+/// a rubber band dragged out on empty canvas: every shape whose bounds
+/// end up inside it gets selected
+struct Marquee {
+    /// where the drag started and where the pointer is now (world)
+    start: KPoint,
+    end: KPoint,
+    /// what was selected before the drag, if shift kept it
+    kept: HashSet<usize>,
+}
+
+impl Marquee {
+    fn rect(&self) -> KRect {
+        KRect::from_points(self.start, self.end)
+    }
+}
+
+pub struct SelectingTool {
+    /// world position of the pointer when the drag started
+    drag_start: Option<KPoint>,
+
+    /// the rubber band being dragged out, see `Marquee`
+    marquee: Option<Marquee>,
+
+    /// every shape moved by the current drag (the whole selection),
+    /// as it was before the drag. each frame they are reset to these
+    /// and moved by the total delta, so no rounding error builds up.
+    dragged_shapes: Vec<(usize, Shape)>,
+
+    /// tolerance the selected shapes get re-fitted with
+    refit_tolerance: f64,
+    /// the tolerance the last refit actually ran with. while the slider is
+    /// dragged we only refit again once it moved `REFIT_STEP` away from it.
+    last_refit_tolerance: f64,
+
+    /// with exactly two shapes selected: position of the in-between
+    /// shape (0 = the lower shape index, 1 = the other)
+    morph_t: f64,
+    /// segment count to refit both shapes to, if theirs don't match
+    morph_segments: usize,
+
+    /// with exactly two shapes selected: copies of one along the other,
+    /// see `array_pair` and `Shaper::array_along_path`
+    array_count: usize,
+    /// `array_spacing` apart instead of spread over the whole path
+    array_fixed_spacing: bool,
+    array_spacing: f64,
+    array_align: bool,
+    /// flip which of the two is copied and which is the path
+    array_swap: bool,
+
+    /// where the last Alt+click was (world) and the shape it selected,
+    /// so the next one there goes on below that shape, see `cycle_pick`
+    alt_cycle: Option<(KPoint, usize)>,
+
+    /// the next click picks the shape whose style the selection gets,
+    /// see `Shaper::copy_style_from`
+    picking_style: bool,
+}
+
+/// dash (and gap) length of the selection outline, screen px
+const ANTS_DASH: f32 = 4.0;
+/// how fast the dashes crawl along the outline, px per second
+const ANTS_SPEED: f32 = 16.0;
+/// time between marching ants frames, instead of repainting at full rate
+const ANTS_FRAME_MS: u64 = 33;
+
+/// how far (screen px) an Alt+click may be from the last one
+/// to still count as the same spot and keep cycling there
+const ALT_CYCLE_RADIUS_PX: f32 = 4.0;
+
+/// how far the tolerance slider has to move (while dragging)
+/// before the selected shapes are re-fitted again
+const REFIT_STEP: f64 = 5.0;
+
+/// the shape an Alt+click picks from `hits` (the shapes under the pointer,
+/// top first): the one below `last` (what the Alt+click before picked
+/// there), or else below the topmost `selected` one, wrapping around to
+/// the top. `None` with nothing under the pointer.
+fn next_in_stack(hits: &[usize], last: Option<usize>, selected: &HashSet<usize>) -> Option<usize> {
+    let current = last
+        .and_then(|last| hits.iter().position(|&idx| idx == last))
+        .or_else(|| hits.iter().position(|idx| selected.contains(idx)));
+    match current {
+        Some(i) => Some(hits[(i + 1) % hits.len()]),
+        None => hits.first().copied(),
+    }
+}
+
+impl SelectingTool {
+    pub fn new() -> Self {
+        SelectingTool {
+            drag_start: None,
+            marquee: None,
+            dragged_shapes: Vec::new(),
+            refit_tolerance: 10.0,
+            last_refit_tolerance: 10.0,
+            morph_t: 0.5,
+            morph_segments: 8,
+            array_count: 5,
+            array_fixed_spacing: false,
+            array_spacing: 50.0,
+            array_align: true,
+            array_swap: false,
+            alt_cycle: None,
+            picking_style: false,
+        }
+    }
+
+    /// the two selected shapes in index order, if exactly two are selected
+    fn morph_pair(app: &Shaper) -> Option<(usize, usize)> {
+        if app.selected_shapes.len() != 2 {
+            return None;
+        }
+        let mut pair: Vec<usize> = app.selected_shapes.iter().copied().collect();
+        pair.sort_unstable();
+        Some((pair[0], pair[1]))
+    }
+
+    /// the two selected shapes as (shape to copy, path to copy it along).
+    /// the path is the open one if only one of them is, otherwise the
+    /// upper one; `array_swap` turns that around.
+    fn array_pair(&self, app: &Shaper) -> Option<(usize, usize)> {
+        let (a, b) = Self::morph_pair(app)?;
+        let (shape, path) = if app.shapes[b].closed && !app.shapes[a].closed {
+            (b, a)
+        } else {
+            (a, b)
+        };
+        Some(if self.array_swap { (path, shape) } else { (shape, path) })
+    }
+
+    /// whether `mouse` (world) is inside the bounding box of a selected shape
+    fn over_selection(app: &Shaper, mouse: KPoint) -> bool {
+        app.selected_shapes.iter().any(|&idx| {
+            app.shapes
+                .get(idx)
+                .and_then(|shape| shape.bounding_box())
+                .is_some_and(|bbox| bbox.contains(mouse))
+        })
+    }
+
+    /// refit all selected shapes from their raw strokes
+    fn refit_selection(&mut self, app: &mut Shaper) {
+        let selected: Vec<usize> = app.selected_shapes.iter().copied().collect();
+        for idx in selected {
+            if let Some(shape) = app.shapes.get_mut(idx) {
+                shape.refit_all_strokes(self.refit_tolerance, model::STRAIGHT_ANGLE_TOL);
+            }
+            // the segment count changed, old point ids are meaningless
+            app.forget_points(idx);
+        }
+        app.selected_points.clear();
+        self.last_refit_tolerance = self.refit_tolerance;
+    }
+
+    /// Alt+click at `mouse` (world): select the shape beneath the selected
+    /// one there, back to the top after the bottom one. with nothing
+    /// selected there it's the topmost shape. repeated Alt+clicks on the
+    /// same spot step down from the shape the last one picked (as long as
+    /// it's still selected), even if more shapes are selected there.
+    fn cycle_pick(&mut self, app: &mut Shaper, mouse: KPoint) {
+        // every part of a shape is listed, one after the other
+        let mut hits: Vec<usize> = app
+            .hit_test_all_results(mouse, app.screen_to_world_tol(app.min_pick_px))
+            .iter()
+            .filter_map(|hit| hit.shape_idx())
+            .collect();
+        hits.dedup();
+        // the shape the last Alt+click picked, if it was on this spot
+        let same_spot = app.screen_to_world_tol(ALT_CYCLE_RADIUS_PX);
+        let last = self
+            .alt_cycle
+            .filter(|(pos, shape_idx)| {
+                pos.distance(mouse) <= same_spot && app.selected_shapes.contains(shape_idx)
+            })
+            .map(|(_, shape_idx)| shape_idx);
+        let Some(shape_idx) = next_in_stack(&hits, last, &app.selected_shapes) else {
+            self.alt_cycle = None;
+            app.clear_selection();
+            return;
+        };
+        app.select_single_shape(shape_idx);
+        self.grab_selection(app);
+        self.alt_cycle = Some((mouse, shape_idx));
+    }
+
+    /// press at `mouse` (world) without Alt: pick the shape there (`shift`
+    /// adds it to / takes it out of the selection) and get ready to drag
+    /// the selection
+    fn press(&mut self, app: &mut Shaper, mouse: KPoint, shift: bool) {
+        self.alt_cycle = None;
+        self.dragged_shapes.clear();
+        self.marquee = None;
+        let hit = app.hit_test_all(mouse);
+
+        match hit {
+            // off the strokes, but inside the bounds of a selected
+            // shape: drag the selection from there
+            HitTestResult::ShapeBody { .. } | HitTestResult::None
+                if !shift && Self::over_selection(app, mouse) =>
+            {
+                self.grab_selection(app);
+            }
+            // the stroke, a handle or the inside of a shape's
+            // bounds all pick that shape
+            HitTestResult::ControlPoint { shape_idx, .. }
+            | HitTestResult::CurveSegment { shape_idx, .. }
+            | HitTestResult::ShapeBody { shape_idx } => {
+                if shift {
+                    app.toggle_shape_selection(shape_idx);
+                } else if !app.selected_shapes.contains(&shape_idx) {
+                    // clicking a shape that is already part of the
+                    // selection keeps it, so the whole group can be dragged
+                    app.select_single_shape(shape_idx);
+                }
+                // grabbing any selected shape drags the whole selection
+                if app.selected_shapes.contains(&shape_idx) {
+                    self.grab_selection(app);
+                }
+            }
+            // only a click on empty canvas clears the selection,
+            // and dragging on from there selects with a marquee
+            HitTestResult::None => {
+                let kept = if shift {
+                    app.selected_shapes.clone()
+                } else {
+                    app.clear_selection();
+                    HashSet::new()
+                };
+                self.marquee = Some(Marquee { start: mouse, end: mouse, kept });
+            }
+        }
+    }
+
+    /// double-click at `mouse` (world): on a shape's stroke or handle,
+    /// select all its points and switch to the Edit tool. returns whether
+    /// it did
+    fn drill_down(&mut self, app: &mut Shaper, mouse: KPoint) -> bool {
+        let (HitTestResult::ControlPoint { shape_idx, .. }
+        | HitTestResult::CurveSegment { shape_idx, .. }) = app.hit_test_all(mouse)
+        else {
+            return false;
+        };
+        app.select_all_points_in_shape(shape_idx);
+        app.previous_tool = Some(ToolKind::Selection);
+        app.selected_tool = ToolKind::Editing;
+        self.dragged_shapes.clear();
+        self.drag_start = None;
+        true
+    }
+
+    /// a drag started at `mouse` (world), moving whatever `press` grabbed
+    fn begin_drag(&mut self, mouse: KPoint) {
+        self.drag_start = Some(mouse);
+    }
+
+    /// the pointer moved to `mouse` (world) while dragging: the grabbed
+    /// shapes are put back and moved by the whole way since the drag started.
+    /// dragging a marquee selects what's inside it instead
+    fn drag_to(&mut self, app: &mut Shaper, mouse: KPoint) {
+        if let Some(marquee) = &mut self.marquee {
+            marquee.end = mouse;
+            let rect = marquee.rect();
+            let mut selected = marquee.kept.clone();
+            selected.extend(app.shapes.iter().enumerate().filter_map(|(idx, shape)| {
+                // `contains` leaves out the far edges, a flat line's bbox has no area
+                let bbox = shape.bounding_box()?;
+                let inside = bbox.x0 >= rect.x0
+                    && bbox.y0 >= rect.y0
+                    && bbox.x1 <= rect.x1
+                    && bbox.y1 <= rect.y1;
+                inside.then_some(idx)
+            }));
+            app.selected_shapes = selected;
+            app.selected_points.clear();
+            return;
+        }
+        let Some(start) = self.drag_start else {
+            return;
+        };
+        let delta = mouse - start;
+        for (idx, original) in &self.dragged_shapes {
+            if let Some(shape) = app.shapes.get_mut(*idx) {
+                shape.clone_from(original);
+                shape.translate(delta);
+            }
+        }
+    }
+
+    /// the drag is over, the shapes stay where they were moved to
+    fn end_drag(&mut self) {
+        self.drag_start = None;
+        self.marquee = None;
+        self.dragged_shapes.clear();
+    }
+
+    /// Escape while dragging: the moved shapes go back to where they were,
+    /// a marquee gives back the selection from before it
+    fn cancel_drag(&mut self, app: &mut Shaper) {
+        for (idx, original) in &self.dragged_shapes {
+            if let Some(shape) = app.shapes.get_mut(*idx) {
+                shape.clone_from(original);
+            }
+        }
+        if let Some(marquee) = self.marquee.take() {
+            app.selected_shapes = marquee.kept;
+        }
+        self.end_drag();
+    }
+
+    /// while `picking_style`: a click on a shape copies its style to the
+    /// selection, a click anywhere else or Escape gives up
+    fn pick_style_source(&mut self, ctx: &Context, response: &Response, app: &mut Shaper) {
+        if response.hovered() {
+            ctx.set_cursor_icon(egui::CursorIcon::Copy);
+        }
+        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.picking_style = false;
+        }
+        if !(ctx.input(|i| i.pointer.primary_pressed()) && response.hovered()) {
+            return;
+        }
+        if let Some(pos) = response.interact_pointer_pos() {
+            let world = app.screen_to_world(pos);
+            let hit = app.hit_test_all(KPoint::new(world.x as f64, world.y as f64));
+            if let Some(src) = hit.shape_idx() {
+                app.copy_style_from(src);
+            }
+        }
+        self.picking_style = false;
+    }
+
+    /// snapshot all selected shapes, they'll move together
+    fn grab_selection(&mut self, app: &Shaper) {
+        self.dragged_shapes = app
+            .selected_shapes
+            .iter()
+            .filter_map(|&idx| app.shapes.get(idx).map(|shape| (idx, shape.clone())))
+            .collect();
+    }
+}
+
+impl Tool for SelectingTool {
+    fn handle_input(&mut self, ctx: &Context, response: &Response, app: &mut Shaper) {
+        // zoom / pan with the mouse wheel, see `Shaper::handle_scroll`
+        app.handle_scroll(ctx, response);
+
+        // I (eyedropper) copies the style of the next shape clicked
+        // to the selected shapes
+        let eyedropper = !ctx.wants_keyboard_input() && ctx.input(|i| i.key_pressed(egui::Key::I));
+        if eyedropper && app.selected_shape_count() > 0 {
+            self.picking_style = true;
+        }
+        if self.picking_style {
+            self.pick_style_source(ctx, response, app);
+            return;
+        }
+
+        // select on press (not on release), so a press + drag
+        // picks the shape up and moves it in one gesture
+        let (pressed, double_clicked, shift, alt) = ctx.input(|i| {
+            (
+                i.pointer.primary_pressed(),
+                i.pointer.button_double_clicked(PointerButton::Primary),
+                i.modifiers.shift,
+                i.modifiers.alt,
+            )
+        });
+
+        // Alt+click picks the shape beneath the selected one, see `cycle_pick`
+        let pointer = response
+            .interact_pointer_pos()
+            .map(|pos| app.screen_to_world(pos))
+            .map(|world| KPoint::new(world.x as f64, world.y as f64));
+        let press_pos = pointer.filter(|_| response.hovered());
+        if let (true, Some(mouse)) = (pressed, press_pos) {
+            if alt {
+                self.dragged_shapes.clear();
+                self.cycle_pick(app, mouse);
+            } else {
+                self.press(app, mouse, shift);
+            }
+        }
+
+        // double-click a shape to drill down into editing its points;
+        // Escape in the Edit tool brings us back here
+        if let (true, Some(mouse)) = (double_clicked, press_pos) {
+            if self.drill_down(app, mouse) {
+                return;
+            }
+        }
+
+        if let (true, Some(mouse)) = (response.drag_started(), pointer) {
+            self.begin_drag(mouse);
+        }
+        if let (true, Some(mouse)) = (response.dragged(), pointer) {
+            self.drag_to(app, mouse);
+        }
+
+        if response.drag_stopped() {
+            self.end_drag();
+        }
+
+        // escape aborts the drag, see `cancel_drag`. the rest of it
+        // (until the button is let go) does nothing
+        if self.drag_start.is_some() && ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.cancel_drag(app);
+        }
+
+        // move cursor while dragging a shape or hovering one that could be
+        if !self.dragged_shapes.is_empty() && response.dragged() {
+            ctx.set_cursor_icon(egui::CursorIcon::Move);
+        } else if let Some(pos) = response.hover_pos() {
+            let world = app.screen_to_world(pos);
+            let mouse = KPoint::new(world.x as f64, world.y as f64);
+            let over_shape = app.hit_test_all(mouse) != HitTestResult::None;
+            if over_shape || Self::over_selection(app, mouse) {
+                ctx.set_cursor_icon(egui::CursorIcon::Move);
+            }
+        }
+
+        // Delete/Backspace removes the selected shapes
+        // (not while typing in a text field, e.g. the shape's name)
+        if !ctx.wants_keyboard_input() {
+            for event in &ctx.input(|i: &egui::InputState| i.events.clone()) {
+                if let Event::Key {
+                    key: egui::Key::Delete | egui::Key::Backspace,
+                    pressed: true,
+                    ..
+                } = event
+                {
+                    app.delete_selected_shapes();
+                }
+            }
+        }
+    }
+
+    fn paint(&mut self, ctx: &Context, painter: &Painter, app: &Shaper) {
+        // only once the press turned into a drag
+        if let (Some(marquee), Some(_)) = (&self.marquee, self.drag_start) {
+            let rect = marquee.rect();
+            let min = app.world_to_screen(Pos2::new(rect.x0 as f32, rect.y0 as f32));
+            let max = app.world_to_screen(Pos2::new(rect.x1 as f32, rect.y1 as f32));
+            painter.rect(
+                Rect::from_min_max(min, max),
+                0.0,
+                app.selected_p_color.gamma_multiply(0.1),
+                Stroke::new(1.0, app.selected_p_color),
+                egui::StrokeKind::Inside,
+            );
+        }
+
+        if app.selected_shapes.is_empty() {
+            return;
+        }
+
+        if app.show_point_labels {
+            for shape in app.selected_shapes.iter().filter_map(|&idx| app.shapes.get(idx)) {
+                shape.draw_point_labels(painter, app);
+            }
+        }
+
+        // faint preview of the in-between shape
+        if let Some(mut between) = Self::morph_pair(app)
+            .and_then(|(a, b)| app.interpolate_shapes(a, b, self.morph_t))
+        {
+            between.stroke_color = between.stroke_color.gamma_multiply(0.5);
+            between.fill_color = between.fill_color.gamma_multiply(0.5);
+            between.draw_beziers(painter, app);
+        }
+
+        // marching ants around the bounding box of every selected shape:
+        // a white base with dashes on top that crawl along over time
+        let phase = (ctx.input(|i| i.time) as f32 * ANTS_SPEED) % (ANTS_DASH * 2.0);
+        let base = Stroke::new(1.0, Color32::WHITE);
+        let dashes = Stroke::new(1.0, app.selected_p_color);
+        for shape_idx in &app.selected_shapes {
+            let Some(shape) = app.shapes.get(*shape_idx) else {
+                continue;
+            };
+            let Some(bbox) = shape.bounding_box() else {
+                continue;
+            };
+            let min = app.world_to_screen(Pos2::new(bbox.x0 as f32, bbox.y0 as f32));
+            let max = app.world_to_screen(Pos2::new(bbox.x1 as f32, bbox.y1 as f32));
+            // just outside the box, like `StrokeKind::Outside`
+            let rect = Rect::from_min_max(min, max).expand(0.5);
+            let outline = [
+                rect.left_top(),
+                rect.right_top(),
+                rect.right_bottom(),
+                rect.left_bottom(),
+                rect.left_top(),
+            ];
+            painter.line(outline.to_vec(), base);
+            painter.text(
+                rect.left_top() - Vec2::new(0.0, 2.0),
+                egui::Align2::LEFT_BOTTOM,
+                &shape.name,
+                egui::FontId::proportional(11.0),
+                app.selected_p_color,
+            );
+            painter.extend(egui::Shape::dashed_line_with_offset(
+                &outline,
+                dashes,
+                &[ANTS_DASH],
+                &[ANTS_DASH],
+                phase,
+            ));
+        }
+
+        // keep the ants moving, ~30 fps is plenty for that
+        ctx.request_repaint_after(std::time::Duration::from_millis(ANTS_FRAME_MS));
+    }
+
+    fn on_deactivate(&mut self, _app: &mut Shaper) {
+        self.picking_style = false;
+    }
+
+    fn tool_ui(&mut self, ctx: &Context, app: &mut Shaper) {
+        egui::TopBottomPanel::top("selection settings")
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
+                    let count = app.selected_shape_count();
+                    ui.label(format!("Selected shapes: {count}"));
+
+                    // size of the selection in the document's units
+                    if let Some(bbox) = app
+                        .selected_shapes
+                        .iter()
+                        .filter_map(|&idx| app.shapes.get(idx)?.bounding_box())
+                        .reduce(|a, b| a.union(b))
+                    {
+                        ui.label(format!(
+                            "{} × {}",
+                            app.format_length(bbox.width()),
+                            app.format_length(bbox.height())
+                        ));
+                    }
+
+                    // rename a single selected shape
+                    if let (1, Some(&idx)) =
+                        (app.selected_shapes.len(), app.selected_shapes.iter().next())
+                    {
+                        if let Some(shape) = app.shapes.get_mut(idx) {
+                            ui.label("Name:");
+                            ui.add(egui::TextEdit::singleline(&mut shape.name).desired_width(120.0));
+                        }
+                    }
+
+                    // re-fit the selected shapes with a new tolerance.
+                    // refitting long strokes on every slider tick is slow,
+                    // so while dragging only every `REFIT_STEP`, and once
+                    // more with the final value on release
+                    let tol = egui::Slider::new(&mut self.refit_tolerance, 1.0..=100.0)
+                        .text("Tolerance")
+                        .orientation(SliderOrientation::Horizontal);
+                    let tol_response = ui.add_enabled(count > 0, tol);
+
+                    let moved = (self.refit_tolerance - self.last_refit_tolerance).abs();
+                    let settled = tol_response.drag_stopped()
+                        || (tol_response.changed() && !tol_response.dragged());
+                    if (settled && moved > 0.0) || moved >= REFIT_STEP {
+                        self.refit_selection(app);
+                    }
+
+                    // cheap preview of the current fit
+                    let anchors: usize = app
+                        .selected_shapes
+                        .iter()
+                        .filter_map(|&idx| app.shapes.get(idx))
+                        .map(|shape| shape.beziers.len() + usize::from(!shape.closed))
+                        .sum();
+                    if anchors > 0 {
+                        ui.label(format!("Anchors: {anchors}"));
+                    }
+
+                    let beautify = egui::Button::new("Beautify");
+                    let beautify = ui
+                        .add_enabled(count > 0, beautify)
+                        .on_disabled_hover_text("select a shape first");
+                    if beautify.clicked() {
+                        let selected: Vec<usize> = app.selected_shapes.iter().copied().collect();
+                        for idx in selected {
+                            app.beautify_shape(idx);
+                        }
+                    }
+
+                    let combine = ui
+                        .add_enabled(count >= 2, egui::Button::new("Combine"))
+                        .on_disabled_hover_text("select two or more shapes");
+                    if combine.clicked() {
+                        app.combine_selected_into_subpaths();
+                    }
+
+                    // pick a shape to copy its stroke and fill to the selection
+                    let copy_style =
+                        egui::SelectableLabel::new(self.picking_style, "Copy style (I)");
+                    let copy_style = ui
+                        .add_enabled(count > 0, copy_style)
+                        .on_hover_text("then click the shape to copy the style from")
+                        .on_disabled_hover_text("select the shapes to restyle first");
+                    if copy_style.clicked() {
+                        self.picking_style = !self.picking_style;
+                    }
+
+                    // morphing between two shapes
+                    if let Some((a, b)) = Self::morph_pair(app) {
+                        ui.separator();
+                        ui.add(egui::Slider::new(&mut self.morph_t, 0.0..=1.0).text("Morph"));
+
+                        let counts = (app.shapes[a].beziers.len(), app.shapes[b].beziers.len());
+                        if counts.0 == counts.1 {
+                            if ui.button("Bake").clicked() {
+                                if let Some(between) = app.interpolate_shapes(a, b, self.morph_t) {
+                                    app.add_shape(between);
+                                }
+                            }
+                        } else {
+                            // interpolating needs matching segment counts
+                            ui.label(format!("Segments differ ({} vs {})", counts.0, counts.1));
+                            ui.add(egui::DragValue::new(&mut self.morph_segments).range(1..=100));
+                            if ui.button("Refit both").clicked() {
+                                app.refit_segment_count(a, self.morph_segments);
+                                app.refit_segment_count(b, self.morph_segments);
+                            }
+                        }
+                    }
+
+                    // copies of one shape along the other
+                    if let Some((shape, path)) = self.array_pair(app) {
+                        ui.separator();
+                        ui.label(format!(
+                            "Array \"{}\" along \"{}\"",
+                            app.shapes[shape].name, app.shapes[path].name
+                        ));
+                        if ui.small_button("⇄").on_hover_text("swap shape and path").clicked() {
+                            self.array_swap = !self.array_swap;
+                        }
+                        ui.add(
+                            egui::DragValue::new(&mut self.array_count)
+                                .range(1..=500)
+                                .prefix("count: "),
+                        );
+                        ui.checkbox(&mut self.array_fixed_spacing, "Fixed spacing");
+                        if self.array_fixed_spacing {
+                            ui.add(
+                                egui::DragValue::new(&mut self.array_spacing)
+                                    .range(0.1..=10_000.0)
+                                    .prefix("spacing: "),
+                            );
+                        }
+                        ui.checkbox(&mut self.array_align, "Align to path");
+                        if ui.button("Array").clicked() {
+                            let spacing = if self.array_fixed_spacing {
+                                ArraySpacing::Fixed(self.array_spacing)
+                            } else {
+                                ArraySpacing::Fit
+                            };
+                            app.array_along_path(
+                                shape,
+                                path,
+                                self.array_count,
+                                spacing,
+                                self.array_align,
+                            );
+                        }
+                    }
+                });
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// two horizontal lines, from (0, 0) and (0, 50) to 100 px right
+    fn two_lines() -> Shaper {
+        let mut app = Shaper::default();
+        for y in [0.0, 50.0] {
+            app.add_line(KPoint::new(0.0, y), KPoint::new(100.0, y), 2.0, Color32::BLACK);
+        }
+        app.selected_tool = ToolKind::Selection;
+        app
+    }
+
+    /// press at `from`, drag to `to` and let go, like one mouse gesture
+    fn drag(tool: &mut SelectingTool, app: &mut Shaper, from: KPoint, to: KPoint, shift: bool) {
+        tool.press(app, from, shift);
+        tool.begin_drag(from);
+        tool.drag_to(app, to);
+        tool.end_drag();
+    }
+
+    fn start(app: &Shaper, shape_idx: usize) -> KPoint {
+        app.shapes[shape_idx].beziers[0].p0
+    }
+
+    #[test]
+    fn dragging_a_shape_selects_and_moves_it() {
+        let mut app = two_lines();
+        let mut tool = SelectingTool::new();
+        drag(&mut tool, &mut app, KPoint::new(50.0, 0.0), KPoint::new(60.0, 20.0), false);
+
+        assert_eq!(app.selected_shapes, HashSet::from([0]));
+        assert_eq!(start(&app, 0), KPoint::new(10.0, 20.0));
+        assert_eq!(start(&app, 1), KPoint::new(0.0, 50.0));
+
+        // the drag is over, moving on does nothing
+        tool.drag_to(&mut app, KPoint::new(90.0, 90.0));
+        assert_eq!(start(&app, 0), KPoint::new(10.0, 20.0));
+    }
+
+    #[test]
+    fn escape_puts_the_dragged_shapes_back() {
+        let mut app = two_lines();
+        let mut tool = SelectingTool::new();
+        tool.press(&mut app, KPoint::new(50.0, 0.0), false);
+        tool.begin_drag(KPoint::new(50.0, 0.0));
+        tool.drag_to(&mut app, KPoint::new(60.0, 20.0));
+        assert_eq!(start(&app, 0), KPoint::new(10.0, 20.0));
+
+        tool.cancel_drag(&mut app);
+        assert_eq!(start(&app, 0), KPoint::ZERO);
+        assert!(tool.drag_start.is_none() && tool.dragged_shapes.is_empty());
+        // the rest of the drag doesn't pick it up again
+        tool.drag_to(&mut app, KPoint::new(90.0, 90.0));
+        assert_eq!(start(&app, 0), KPoint::ZERO);
+        assert_eq!(app.selected_shapes, HashSet::from([0]));
+    }
+
+    #[test]
+    fn dragging_inside_a_shape_off_its_outline_moves_it() {
+        // a line's bounds have no inside, a rect's do
+        let mut app = Shaper::default();
+        app.add_rect(KRect::new(0.0, 0.0, 100.0, 100.0), 2.0, Color32::BLACK);
+        let mut tool = SelectingTool::new();
+        let inside = KPoint::new(50.0, 50.0);
+        assert!(app.hit_test_all(inside) == HitTestResult::ShapeBody { shape_idx: 0 });
+
+        drag(&mut tool, &mut app, inside, KPoint::new(60.0, 70.0), false);
+        assert_eq!(app.selected_shapes, HashSet::from([0]));
+        assert_eq!(start(&app, 0), KPoint::new(10.0, 20.0));
+    }
+
+    #[test]
+    fn shift_adds_to_the_selection_and_the_whole_of_it_moves() {
+        let mut app = two_lines();
+        let mut tool = SelectingTool::new();
+        tool.press(&mut app, KPoint::new(50.0, 0.0), false);
+        tool.end_drag();
+        drag(&mut tool, &mut app, KPoint::new(50.0, 50.0), KPoint::new(50.0, 60.0), true);
+
+        assert_eq!(app.selected_shapes, HashSet::from([0, 1]));
+        assert_eq!(start(&app, 0), KPoint::new(0.0, 10.0));
+        assert_eq!(start(&app, 1), KPoint::new(0.0, 60.0));
+
+        // and takes a shape out of it again
+        tool.press(&mut app, KPoint::new(50.0, 10.0), true);
+        assert_eq!(app.selected_shapes, HashSet::from([1]));
+    }
+
+    #[test]
+    fn pressing_empty_canvas_clears_the_selection_unless_shift_is_held() {
+        let mut app = two_lines();
+        let mut tool = SelectingTool::new();
+        tool.press(&mut app, KPoint::new(50.0, 0.0), false);
+        tool.press(&mut app, KPoint::new(50.0, 200.0), true);
+        assert_eq!(app.selected_shapes, HashSet::from([0]));
+
+        drag(&mut tool, &mut app, KPoint::new(50.0, 200.0), KPoint::new(80.0, 230.0), false);
+        assert!(app.selected_shapes.is_empty());
+        assert_eq!(start(&app, 0), KPoint::ZERO);
+    }
+
+    #[test]
+    fn a_marquee_selects_the_shapes_inside_it() {
+        let mut app = two_lines();
+        let mut tool = SelectingTool::new();
+        // around the top line only
+        drag(&mut tool, &mut app, KPoint::new(-10.0, -10.0), KPoint::new(110.0, 20.0), false);
+        assert_eq!(app.selected_shapes, HashSet::from([0]));
+        assert_eq!(start(&app, 0), KPoint::ZERO);
+
+        // half of a line isn't enough
+        drag(&mut tool, &mut app, KPoint::new(-10.0, -10.0), KPoint::new(50.0, 60.0), false);
+        assert!(app.selected_shapes.is_empty());
+
+        // shift keeps what was selected
+        tool.press(&mut app, KPoint::new(50.0, 0.0), false);
+        drag(&mut tool, &mut app, KPoint::new(110.0, 60.0), KPoint::new(-10.0, 40.0), true);
+        assert_eq!(app.selected_shapes, HashSet::from([0, 1]));
+    }
+
+    #[test]
+    fn double_clicking_a_shape_edits_its_points() {
+        let mut app = two_lines();
+        let mut tool = SelectingTool::new();
+        assert!(!tool.drill_down(&mut app, KPoint::new(50.0, 200.0)));
+        assert!(app.selected_tool == ToolKind::Selection);
+
+        assert!(tool.drill_down(&mut app, KPoint::new(50.0, 50.0)));
+        assert!(app.selected_tool == ToolKind::Editing);
+        assert!(app.previous_tool == Some(ToolKind::Selection));
+        assert!(app.selected_points.iter().all(|id| id.shape_idx == 1));
+        assert_eq!(app.selected_point_count(), 2);
+    }
+
+    #[test]
+    fn alt_clicks_step_down_the_stack_and_wrap() {
+        let hits = [2, 1, 0];
+        let none = HashSet::new();
+        assert_eq!(next_in_stack(&hits, None, &none), Some(2));
+        assert_eq!(next_in_stack(&hits, Some(2), &HashSet::from([2])), Some(1));
+        assert_eq!(next_in_stack(&hits, Some(0), &HashSet::from([0])), Some(2));
+        // without an Alt+click before, from below the topmost selected one
+        assert_eq!(next_in_stack(&hits, None, &HashSet::from([0, 1])), Some(0));
+        // the last pick wins over other selected shapes
+        assert_eq!(next_in_stack(&hits, Some(2), &HashSet::from([1, 2])), Some(1));
+        assert_eq!(next_in_stack(&[], None, &none), None);
+    }
+
+    #[test]
+    fn two_alt_clicks_over_three_shapes_pick_the_second_and_third() {
+        let mut app = Shaper::default();
+        for _ in 0..3 {
+            app.add_line(KPoint::new(0.0, 0.0), KPoint::new(100.0, 0.0), 2.0, Color32::BLACK);
+        }
+        let mut tool = SelectingTool::new();
+        let mouse = KPoint::new(50.0, 0.0);
+        tool.press(&mut app, mouse, false);
+        assert_eq!(app.selected_shapes, HashSet::from([2]));
+
+        tool.cycle_pick(&mut app, mouse);
+        assert_eq!(app.selected_shapes, HashSet::from([1]));
+        tool.cycle_pick(&mut app, KPoint::new(51.0, 0.0));
+        assert_eq!(app.selected_shapes, HashSet::from([0]));
+        tool.cycle_pick(&mut app, mouse);
+        assert_eq!(app.selected_shapes, HashSet::from([2]));
+
+        // nothing there: nothing selected
+        tool.cycle_pick(&mut app, KPoint::new(50.0, 80.0));
+        assert!(app.selected_shapes.is_empty());
+    }
+}
@@ -0,0 +1,73 @@
+use crate::Shaper;
+use crate::tool::Tool;
+use eframe::egui::{
+    self, Align, Color32, Context, Layout, Painter, Response, SliderOrientation, Stroke,
+};
+use kurbo::Point as KPoint;
+
+pub struct SmoothingTool {
+    /// brush radius in screen pixels
+    radius: f32,
+
+    /// how far (0..=1) an anchor under the brush center
+    /// moves towards its smoothed position per frame
+    strength: f64,
+}
+
+impl SmoothingTool {
+    pub fn new() -> Self {
+        SmoothingTool {
+            radius: 30.0,
+            strength: 0.2,
+        }
+    }
+}
+
+impl Tool for SmoothingTool {
+    fn handle_input(&mut self, ctx: &Context, response: &Response, app: &mut Shaper) {
+        // zoom / pan with the mouse wheel, see `Shaper::handle_scroll`
+        app.handle_scroll(ctx, response);
+
+        // keep relaxing whatever is under the brush while the button is down
+        if response.dragged() {
+            if let Some(pos) = response.interact_pointer_pos() {
+                let world = app.screen_to_world(pos);
+                let center = KPoint::new(world.x as f64, world.y as f64);
+                // the brush is sized on screen, so convert it to world units
+                let radius = app.screen_to_world_tol(self.radius);
+                for shape_idx in 0..app.shapes.len() {
+                    app.smooth_region(shape_idx, center, radius, self.strength);
+                }
+            }
+        }
+
+        // the brush circle acts as the cursor
+        if response.hovered() || response.dragged() {
+            ctx.set_cursor_icon(egui::CursorIcon::None);
+        }
+    }
+
+    fn paint(&mut self, ctx: &Context, painter: &Painter, _app: &Shaper) {
+        if let Some(mouse_pos) = ctx.input(|i| i.pointer.hover_pos()) {
+            painter.circle_stroke(mouse_pos, self.radius, Stroke::new(1.0, Color32::GRAY));
+        }
+    }
+
+    fn tool_ui(&mut self, ctx: &Context, _app: &mut Shaper) {
+        egui::TopBottomPanel::top("smoothing settings")
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.with_layout(Layout::left_to_right(Align::Center), |ui| {
+                    let radius = egui::Slider::new(&mut self.radius, 5.0..=200.0)
+                        .text("Brush Size")
+                        .orientation(SliderOrientation::Horizontal);
+                    ui.add(radius);
+
+                    let strength = egui::Slider::new(&mut self.strength, 0.01..=1.0)
+                        .text("Strength")
+                        .orientation(SliderOrientation::Horizontal);
+                    ui.add(strength);
+                });
+            });
+    }
+}
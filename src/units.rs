@@ -0,0 +1,79 @@
+// real-world units for a drawing. the canvas itself always works in px
+// (world units); `Shaper::px_per_unit` says how many of them make one
+// unit, so measurements and exports can be given in mm or inches.
+
+use eframe::egui;
+
+use crate::Shaper;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Units {
+    Px,
+    Mm,
+    In,
+}
+
+impl Units {
+    pub const ALL: [Units; 3] = [Units::Px, Units::Mm, Units::In];
+
+    /// short name, also the SVG length unit
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Units::Px => "px",
+            Units::Mm => "mm",
+            Units::In => "in",
+        }
+    }
+
+    /// px per unit at 96 dpi (CSS px), what `px_per_unit` starts at
+    pub fn default_px_per_unit(self) -> f64 {
+        match self {
+            Units::Px => 1.0,
+            Units::Mm => 96.0 / 25.4,
+            Units::In => 96.0,
+        }
+    }
+
+    /// the DXF `$INSUNITS` code (0 = unitless)
+    pub fn dxf_code(self) -> i32 {
+        match self {
+            Units::Px => 0,
+            Units::Mm => 4,
+            Units::In => 1,
+        }
+    }
+}
+
+impl Shaper {
+    /// `world` (px) in the document's units, e.g. "12.70 mm"
+    pub fn format_length(&self, world: f64) -> String {
+        format!("{:.2} {}", world / self.px_per_unit, self.units.suffix())
+    }
+
+    /// unit picker and scale, for the settings window
+    pub fn units_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Units:");
+            let before = self.units;
+            egui::ComboBox::from_id_salt("units")
+                .selected_text(self.units.suffix())
+                .show_ui(ui, |ui| {
+                    for units in Units::ALL {
+                        ui.selectable_value(&mut self.units, units, units.suffix());
+                    }
+                });
+            if self.units != before {
+                self.px_per_unit = self.units.default_px_per_unit();
+            }
+            if self.units != Units::Px {
+                ui.add(
+                    egui::DragValue::new(&mut self.px_per_unit)
+                        .range(0.001..=100_000.0)
+                        .speed(0.01)
+                        .prefix(format!("1 {} = ", self.units.suffix()))
+                        .suffix(" px"),
+                );
+            }
+        });
+    }
+}
@@ -0,0 +1,68 @@
+// runs the headless `--convert` mode of the built binary on a fixture
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/two_paths.svg");
+
+fn shaper(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_Shaper"))
+        .args(args)
+        .output()
+        .expect("failed to run the shaper binary")
+}
+
+/// a path in the temp dir no other test (or test run) writes to
+fn temp_out(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("shaper-cli-{}-{name}", std::process::id()))
+}
+
+fn convert(args: &[&str], out: &Path) -> (Output, String) {
+    let mut all = vec!["--convert", FIXTURE, out.to_str().unwrap()];
+    all.extend_from_slice(args);
+    let output = shaper(&all);
+    let written = std::fs::read_to_string(out).unwrap_or_default();
+    let _ = std::fs::remove_file(out);
+    (output, written)
+}
+
+#[test]
+fn converts_svg_to_svg() {
+    let (output, svg) = convert(&[], &temp_out("out.svg"));
+    assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(svg.contains("<svg"));
+    assert_eq!(svg.matches("<path").count(), 2);
+}
+
+#[test]
+fn converts_svg_to_json_with_simplify() {
+    let (output, json) = convert(&["--simplify", "2.5", "--to-json"], &temp_out("out.json"));
+    assert_eq!(output.status.code(), Some(0), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(json.starts_with('['));
+    assert_eq!(json.matches("\"beziers\"").count(), 2);
+    assert_eq!(json.matches("\"closed\": true").count(), 1);
+}
+
+#[test]
+fn bad_arguments_exit_with_2() {
+    assert_eq!(shaper(&["--bogus"]).status.code(), Some(2));
+    assert_eq!(shaper(&["--convert", FIXTURE]).status.code(), Some(2));
+    let out = temp_out("never.svg");
+    let out = out.to_str().unwrap();
+    assert_eq!(shaper(&["--convert", FIXTURE, out, "--simplify", "-1"]).status.code(), Some(2));
+}
+
+#[test]
+fn unreadable_or_empty_input_exits_with_1() {
+    let out = temp_out("missing.svg");
+    let missing = temp_out("does-not-exist.svg");
+    let status = shaper(&["--convert", missing.to_str().unwrap(), out.to_str().unwrap()]).status;
+    assert_eq!(status.code(), Some(1));
+
+    let empty = temp_out("empty.svg");
+    std::fs::write(&empty, "<svg xmlns=\"http://www.w3.org/2000/svg\"></svg>").unwrap();
+    let status = shaper(&["--convert", empty.to_str().unwrap(), out.to_str().unwrap()]).status;
+    let _ = std::fs::remove_file(&empty);
+    assert_eq!(status.code(), Some(1));
+    assert!(!out.exists());
+}